@@ -0,0 +1,212 @@
+//! Load-time benchmark on synthetic projects.
+//!
+//! Run with `cargo bench -p topobus-core --bench load`; results are recorded in
+//! `benches/RESULTS.md`.
+
+use std::fmt::Write as _;
+use std::io::{Cursor, Write as _};
+use std::time::{Duration, Instant};
+
+use topobus_core::ProjectLoader;
+
+const MANUFACTURERS: usize = 3;
+const APPS_PER_MANUFACTURER: usize = 4;
+const OBJECTS_PER_DEVICE: usize = 16;
+const PARAMETERS_PER_DEVICE: usize = 40;
+
+/// Size of the generated project.
+struct Shape {
+    devices: usize,
+    objects_per_app: usize,
+    parameters_per_app: usize,
+    /// Translated languages per application program.
+    languages: usize,
+}
+
+const SHAPES: [Shape; 5] = [
+    Shape { devices: 500, objects_per_app: 120, parameters_per_app: 400, languages: 0 },
+    Shape { devices: 2000, objects_per_app: 120, parameters_per_app: 400, languages: 0 },
+    Shape { devices: 5000, objects_per_app: 120, parameters_per_app: 400, languages: 0 },
+    Shape { devices: 100, objects_per_app: 1500, parameters_per_app: 6000, languages: 3 },
+    Shape { devices: 100, objects_per_app: 3000, parameters_per_app: 12000, languages: 6 },
+];
+
+fn main() {
+    let runs = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(5);
+    println!("| devices | group addresses | app objects / parameters / languages | archive | median load | topology + GAs only |");
+    println!("|--------:|----------------:|--------------------------------------|--------:|------------:|--------------------:|");
+    for shape in &SHAPES {
+        let archive = synthetic_project(shape);
+        let full = median_load(runs, shape, || ProjectLoader::from_bytes(&archive));
+        let quick = median_load(runs, shape, || {
+            ProjectLoader::from_bytes(&archive)
+                .skip_app_programs(true)
+                .skip_parameters(true)
+                .skip_locations(true)
+        });
+        println!(
+            "| {} | {} | {} / {} / {} | {:.1} MiB | {:.0} ms | {:.0} ms |",
+            shape.devices,
+            shape.devices * 2,
+            shape.objects_per_app,
+            shape.parameters_per_app,
+            shape.languages,
+            archive.len() as f64 / (1024.0 * 1024.0),
+            full.as_secs_f64() * 1000.0,
+            quick.as_secs_f64() * 1000.0
+        );
+    }
+}
+
+fn median_load<'a>(runs: usize, shape: &Shape, loader: impl Fn() -> ProjectLoader<'a>) -> Duration {
+    let mut timings: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            let project = loader().load().expect("synthetic project loads");
+            assert_eq!(project.devices.len(), shape.devices);
+            start.elapsed()
+        })
+        .collect();
+    timings.sort();
+    timings[timings.len() / 2]
+}
+
+fn app_id(manufacturer: usize, app: usize) -> String {
+    format!("M-{:04X}_A-{:04X}-11-0000", manufacturer + 1, app + 1)
+}
+
+fn synthetic_project(shape: &Shape) -> Vec<u8> {
+    let devices = shape.devices;
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut add = |name: &str, content: &str| {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    };
+
+    add(
+        "P-0001/project.xml",
+        r#"<KNX xmlns="http://knx.org/xml/project/21"><Project Id="P-0001"><ProjectInformation Name="Bench" GroupAddressStyle="ThreeLevel"/></Project></KNX>"#,
+    );
+
+    let mut master = String::from("<KNX><MasterData><Manufacturers>");
+    for manufacturer in 0..MANUFACTURERS {
+        let id = format!("M-{:04X}", manufacturer + 1);
+        write!(master, r#"<Manufacturer Id="{id}" Name="Vendor {manufacturer}"/>"#).unwrap();
+
+        let mut hardware = format!(r#"<KNX><ManufacturerData><Manufacturer RefId="{id}"><Hardware>"#);
+        for app in 0..APPS_PER_MANUFACTURER {
+            let app_id = app_id(manufacturer, app);
+            write!(
+                hardware,
+                r#"<Hardware Id="{id}_H-{app}"><Products><Product Id="{id}_H-{app}_P-1" Text="Product {app}" OrderNumber="ON-{app}"/></Products><Hardware2Programs><Hardware2Program Id="{id}_H-{app}_HP-{app}"><ApplicationProgramRef RefId="{app_id}"/></Hardware2Program></Hardware2Programs></Hardware>"#
+            )
+            .unwrap();
+            add(&format!("{id}/{app_id}.xml"), &application_program(&app_id, shape));
+        }
+        hardware.push_str("</Hardware></Manufacturer></ManufacturerData></KNX>");
+        add(&format!("{id}/Hardware.xml"), &hardware);
+    }
+    master.push_str("</Manufacturers></MasterData></KNX>");
+    add("knx_master.xml", &master);
+
+    let group_addresses = devices * 2;
+    let mut data = String::from(
+        r#"<KNX xmlns="http://knx.org/xml/project/21"><Project Id="P-0001"><Installations><Installation><Topology>"#,
+    );
+    let mut device = 0;
+    'areas: for area in 1..=15 {
+        write!(data, r#"<Area Id="P-0001-0_A-{area}" Address="{area}">"#).unwrap();
+        for line in 1..=15 {
+            write!(data, r#"<Line Id="P-0001-0_L-{area}-{line}" Address="{line}"><Segment Id="P-0001-0_S-{area}-{line}" MediumTypeRefId="MT-0"><DeviceInstances>"#).unwrap();
+            for address in 1..=64 {
+                if device == devices {
+                    data.push_str("</DeviceInstances></Segment></Line></Area>");
+                    break 'areas;
+                }
+                let manufacturer = device % MANUFACTURERS;
+                let app = (device / MANUFACTURERS) % APPS_PER_MANUFACTURER;
+                let id = format!("M-{:04X}", manufacturer + 1);
+                let app_id = app_id(manufacturer, app);
+                write!(data, r#"<DeviceInstance Id="P-0001-0_DI-{device}" Name="Device {device}" Address="{address}" ProductRefId="{id}_H-{app}_P-1" Hardware2ProgramRefId="{id}_H-{app}_HP-{app}"><ComObjectInstanceRefs>"#).unwrap();
+                for object in 0..OBJECTS_PER_DEVICE {
+                    let ga = (device * OBJECTS_PER_DEVICE + object) % group_addresses;
+                    write!(data, r#"<ComObjectInstanceRef RefId="O-{object}_R-{object}" Links="GA-{ga}"/>"#).unwrap();
+                }
+                data.push_str("</ComObjectInstanceRefs><ParameterInstanceRefs>");
+                for parameter in 0..PARAMETERS_PER_DEVICE {
+                    write!(data, r#"<ParameterInstanceRef RefId="{app_id}_P-{parameter}_R-{parameter}" Value="{}"/>"#, parameter % 2).unwrap();
+                }
+                data.push_str("</ParameterInstanceRefs></DeviceInstance>");
+                device += 1;
+            }
+            data.push_str("</DeviceInstances></Segment></Line>");
+        }
+        data.push_str("</Area>");
+    }
+    data.push_str(r#"</Topology><GroupAddresses><GroupRanges>"#);
+    for main in 0..(group_addresses / 2048 + 1) {
+        write!(data, r#"<GroupRange Id="P-0001-0_GR-{main}" Name="Main {main}">"#).unwrap();
+        for middle in 0..8 {
+            write!(data, r#"<GroupRange Id="P-0001-0_GR-{main}-{middle}" Name="Middle {middle}">"#).unwrap();
+            for sub in 0..256 {
+                let ga = main * 2048 + middle * 256 + sub;
+                if ga < group_addresses {
+                    write!(data, r#"<GroupAddress Id="P-0001-0_GA-{ga}" Address="{ga}" Name="GA {ga}" DatapointType="DPST-1-1"/>"#).unwrap();
+                }
+            }
+            data.push_str("</GroupRange>");
+        }
+        data.push_str("</GroupRange>");
+    }
+    data.push_str(r#"</GroupRanges></GroupAddresses><Locations><Space Id="P-0001-0_BP-1" Type="Building" Name="Building">"#);
+    for device in (0..devices).step_by(10) {
+        write!(data, r#"<DeviceInstanceRef RefId="P-0001-0_DI-{device}"/>"#).unwrap();
+    }
+    data.push_str("</Space></Locations></Installation></Installations></Project></KNX>");
+    add("P-0001/0.xml", &data);
+
+    zip.finish().unwrap().into_inner()
+}
+
+fn application_program(app_id: &str, shape: &Shape) -> String {
+    let mut xml = format!(
+        r#"<KNX><ManufacturerData><Manufacturer><ApplicationPrograms><ApplicationProgram Id="{app_id}" Name="App {app_id}" ApplicationVersion="1"><Static><ParameterTypes><ParameterType Id="{app_id}_PT-Bool" Name="Bool"><TypeRestriction Base="Value" SizeInBit="1"><Enumeration Text="Off" Value="0" Id="{app_id}_PT-Bool_EN-0"/><Enumeration Text="On" Value="1" Id="{app_id}_PT-Bool_EN-1"/></TypeRestriction></ParameterType></ParameterTypes><Parameters>"#
+    );
+    for parameter in 0..shape.parameters_per_app {
+        write!(xml, r#"<Parameter Id="{app_id}_P-{parameter}" Name="Param{parameter}" ParameterType="{app_id}_PT-Bool" Text="Parameter {parameter}"/>"#).unwrap();
+    }
+    xml.push_str("</Parameters><ParameterRefs>");
+    for parameter in 0..shape.parameters_per_app {
+        write!(xml, r#"<ParameterRef Id="{app_id}_P-{parameter}_R-{parameter}" RefId="{app_id}_P-{parameter}"/>"#).unwrap();
+    }
+    xml.push_str("</ParameterRefs><ComObjectTable>");
+    for object in 0..shape.objects_per_app {
+        write!(xml, r#"<ComObject Id="{app_id}_O-{object}" Name="Object {object}" Text="Switch" FunctionText="On/Off" Number="{object}" ObjectSize="1 Bit" CommunicationFlag="Enabled" WriteFlag="Enabled" DatapointType="DPST-1-1"/>"#).unwrap();
+    }
+    xml.push_str("</ComObjectTable><ComObjectRefs>");
+    for object in 0..shape.objects_per_app {
+        write!(xml, r#"<ComObjectRef Id="{app_id}_O-{object}_R-{object}" RefId="{app_id}_O-{object}"/>"#).unwrap();
+    }
+    xml.push_str("</ComObjectRefs></Static><Dynamic><ChannelIndependentBlock>");
+    for parameter in 0..shape.parameters_per_app {
+        write!(xml, r#"<ParameterRefRef RefId="{app_id}_P-{parameter}_R-{parameter}"/>"#).unwrap();
+    }
+    xml.push_str("</ChannelIndependentBlock></Dynamic></ApplicationProgram></ApplicationPrograms><Languages>");
+    for language in ["de-DE", "en-US", "fr-FR", "it-IT", "nl-NL", "es-ES"].iter().take(shape.languages) {
+        write!(xml, r#"<Language Identifier="{language}"><TranslationUnit RefId="{app_id}">"#).unwrap();
+        for parameter in 0..shape.parameters_per_app {
+            write!(xml, r#"<TranslationElement RefId="{app_id}_P-{parameter}"><Translation AttributeName="Text" Text="{language} {parameter}"/></TranslationElement>"#).unwrap();
+        }
+        for object in 0..shape.objects_per_app {
+            write!(xml, r#"<TranslationElement RefId="{app_id}_O-{object}"><Translation AttributeName="Text" Text="{language} {object}"/></TranslationElement>"#).unwrap();
+        }
+        xml.push_str("</TranslationUnit></Language>");
+    }
+    xml.push_str("</Languages></Manufacturer></ManufacturerData></KNX>");
+    xml
+}
@@ -0,0 +1,327 @@
+use std::fmt;
+
+use crate::export::flags_label;
+use crate::knx::KnxProjectData;
+
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Flat tables available as CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvTable {
+    Devices,
+    GroupAddresses,
+    Associations,
+    Parameters,
+}
+
+impl CsvTable {
+    pub const ALL: [CsvTable; 4] = [
+        CsvTable::Devices,
+        CsvTable::GroupAddresses,
+        CsvTable::Associations,
+        CsvTable::Parameters,
+    ];
+
+    /// File name stem used when exporting several tables side by side.
+    pub fn file_stem(self) -> &'static str {
+        match self {
+            CsvTable::Devices => "devices",
+            CsvTable::GroupAddresses => "group_addresses",
+            CsvTable::Associations => "associations",
+            CsvTable::Parameters => "parameters",
+        }
+    }
+}
+
+impl fmt::Display for CsvTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.file_stem())
+    }
+}
+
+/// CSV output settings.
+///
+/// The defaults produce UTF-8 with a byte order mark so that Excel detects the encoding; use `;`
+/// as separator for locales where `,` is the decimal separator (French, German, ...).
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub separator: char,
+    pub include_bom: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            separator: ',',
+            include_bom: true,
+        }
+    }
+}
+
+/// Render one table of the project as CSV text.
+pub fn render_csv(project: &KnxProjectData, table: CsvTable, options: &CsvOptions) -> String {
+    let mut writer = CsvWriter::new(options);
+    match table {
+        CsvTable::Devices => write_devices(&mut writer, project),
+        CsvTable::GroupAddresses => write_group_addresses(&mut writer, project),
+        CsvTable::Associations => write_associations(&mut writer, project),
+        CsvTable::Parameters => write_parameters(&mut writer, project),
+    }
+    writer.finish()
+}
+
+fn write_devices(writer: &mut CsvWriter, project: &KnxProjectData) {
+    writer.row([
+        "individual_address",
+        "name",
+        "instance_id",
+        "manufacturer",
+        "product",
+        "product_reference",
+        "description",
+        "comment",
+        "serial_number",
+        "app_program_name",
+        "app_program_version",
+        "app_program_number",
+        "app_program_type",
+        "app_mask_version",
+        "medium_type",
+        "segment_id",
+        "segment_number",
+        "segment_domain_address",
+        "segment_medium_type",
+        "ip_assignment",
+        "ip_address",
+        "ip_subnet_mask",
+        "ip_default_gateway",
+        "mac_address",
+        "last_modified",
+        "last_download",
+    ]);
+    for device in &project.devices {
+        writer.row([
+            device.individual_address.as_str(),
+            device.name.as_str(),
+            device.instance_id.as_str(),
+            opt(&device.manufacturer),
+            opt(&device.product),
+            opt(&device.product_reference),
+            opt(&device.description),
+            opt(&device.comment),
+            opt(&device.serial_number),
+            opt(&device.app_program_name),
+            opt(&device.app_program_version),
+            opt(&device.app_program_number),
+            opt(&device.app_program_type),
+            opt(&device.app_mask_version),
+            opt(&device.medium_type),
+            opt(&device.segment_id),
+            opt(&device.segment_number),
+            opt(&device.segment_domain_address),
+            opt(&device.segment_medium_type),
+            opt(&device.ip_assignment),
+            opt(&device.ip_address),
+            opt(&device.ip_subnet_mask),
+            opt(&device.ip_default_gateway),
+            opt(&device.mac_address),
+            opt(&device.last_modified),
+            opt(&device.last_download),
+        ]);
+    }
+}
+
+fn write_group_addresses(writer: &mut CsvWriter, project: &KnxProjectData) {
+    writer.row([
+        "address",
+        "name",
+        "main_group_name",
+        "middle_group_name",
+        "datapoint_type",
+        "description",
+        "comment",
+        "security",
+        "linked_devices",
+    ]);
+    for ga in &project.group_addresses {
+        let linked = ga.linked_devices.join(" ");
+        writer.row([
+            ga.address.as_str(),
+            ga.name.as_str(),
+            opt(&ga.main_group_name),
+            opt(&ga.middle_group_name),
+            opt(&ga.datapoint_type),
+            opt(&ga.description),
+            opt(&ga.comment),
+            opt(&ga.security),
+            linked.as_str(),
+        ]);
+    }
+}
+
+fn write_associations(writer: &mut CsvWriter, project: &KnxProjectData) {
+    writer.row([
+        "individual_address",
+        "device_name",
+        "object_number",
+        "object_name",
+        "channel",
+        "datapoint_type",
+        "flags",
+        "group_address",
+        "group_address_name",
+        "sending",
+        "receiving",
+    ]);
+    let ga_names: std::collections::HashMap<&str, &str> = project
+        .group_addresses
+        .iter()
+        .map(|ga| (ga.address.as_str(), ga.name.as_str()))
+        .collect();
+    for device in &project.devices {
+        for link in &device.group_links {
+            let number = link.number.map(|n| n.to_string()).unwrap_or_default();
+            let flags = flags_label(link.flags.as_ref());
+            writer.row([
+                device.individual_address.as_str(),
+                device.name.as_str(),
+                number.as_str(),
+                link.object_name.as_str(),
+                opt(&link.channel),
+                opt(&link.datapoint_type),
+                flags.as_str(),
+                link.group_address.as_str(),
+                ga_names.get(link.group_address.as_str()).copied().unwrap_or(""),
+                bool_label(link.ets_sending),
+                bool_label(link.ets_receiving),
+            ]);
+        }
+    }
+}
+
+fn write_parameters(writer: &mut CsvWriter, project: &KnxProjectData) {
+    writer.row([
+        "individual_address",
+        "device_name",
+        "context",
+        "parameter",
+        "value",
+        "value_raw",
+        "parameter_type",
+        "ref_id",
+        "source",
+    ]);
+    for device in &project.devices {
+        for entry in &device.configuration_entries {
+            writer.row([
+                device.individual_address.as_str(),
+                device.name.as_str(),
+                opt(&entry.context),
+                entry.name.as_str(),
+                entry.value.as_str(),
+                opt(&entry.value_raw),
+                opt(&entry.parameter_type),
+                opt(&entry.ref_id),
+                opt(&entry.source),
+            ]);
+        }
+    }
+}
+
+struct CsvWriter {
+    out: String,
+    separator: char,
+}
+
+impl CsvWriter {
+    fn new(options: &CsvOptions) -> Self {
+        let mut out = String::new();
+        if options.include_bom {
+            out.push_str(UTF8_BOM);
+        }
+        Self {
+            out,
+            separator: options.separator,
+        }
+    }
+
+    fn row<'a, I: IntoIterator<Item = &'a str>>(&mut self, fields: I) {
+        for (index, field) in fields.into_iter().enumerate() {
+            if index > 0 {
+                self.out.push(self.separator);
+            }
+            self.field(field);
+        }
+        // RFC 4180 line ending; Excel handles it on every platform.
+        self.out.push_str("\r\n");
+    }
+
+    fn field(&mut self, value: &str) {
+        let needs_quotes = value
+            .chars()
+            .any(|ch| ch == self.separator || ch == '"' || ch == '\n' || ch == '\r');
+        if !needs_quotes {
+            self.out.push_str(value);
+            return;
+        }
+        self.out.push('"');
+        self.out.push_str(&value.replace('"', "\"\""));
+        self.out.push('"');
+    }
+
+    fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn opt(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("")
+}
+
+fn bool_label(value: bool) -> &'static str {
+    if value {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_csv, CsvOptions, CsvTable};
+    use crate::knx::{DeviceConfigEntry, DeviceInfo, KnxProjectData};
+
+    #[test]
+    fn quotes_fields_with_separator_and_writes_bom() {
+        let project = KnxProjectData {
+            devices: vec![DeviceInfo {
+                individual_address: "1.1.1".to_string(),
+                name: "Stores; salon \"sud\"".to_string(),
+                configuration_entries: vec![DeviceConfigEntry {
+                    name: "Temps de montée".to_string(),
+                    value: "1,5 s".to_string(),
+                    value_raw: None,
+                    value_label: None,
+                    parameter_type: None,
+                    context: Some("Canal A".to_string()),
+                    ref_id: None,
+                    source: None,
+                }],
+                ..DeviceInfo::default()
+            }],
+            ..KnxProjectData::default()
+        };
+        let options = CsvOptions {
+            separator: ';',
+            include_bom: true,
+        };
+
+        let csv = render_csv(&project, CsvTable::Parameters, &options);
+        let mut lines = csv.split("\r\n");
+        assert!(lines.next().unwrap().starts_with("\u{feff}individual_address;"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "1.1.1;\"Stores; salon \"\"sud\"\"\";Canal A;Temps de montée;1,5 s;;;;"
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::export::flags_label;
+use crate::knx::{BuildingSpace, DeviceInfo, GroupLink, KnxProjectData};
+
+const REPORT_STYLE: &str = r#"
+body { font-family: "Segoe UI", Helvetica, Arial, sans-serif; font-size: 10pt; color: #1e1e2e; margin: 2em; }
+h1 { font-size: 20pt; margin-bottom: 0.2em; }
+h2 { font-size: 15pt; border-bottom: 2px solid #1e66f5; padding-bottom: 0.2em; margin-top: 2em; page-break-before: always; }
+h2.first { page-break-before: avoid; }
+h3 { font-size: 12pt; margin-top: 1.5em; page-break-after: avoid; }
+h4 { font-size: 10.5pt; margin: 1em 0 0.3em; page-break-after: avoid; }
+table { border-collapse: collapse; width: 100%; margin: 0.5em 0 1em; page-break-inside: auto; }
+tr { page-break-inside: avoid; }
+th, td { border: 1px solid #bcc0cc; padding: 3px 6px; text-align: left; vertical-align: top; }
+th { background: #e6e9ef; }
+table.kv th { width: 30%; }
+td.mono, span.mono { font-family: Consolas, "Courier New", monospace; white-space: nowrap; }
+.muted { color: #6c6f85; }
+.device { page-break-inside: avoid; margin-bottom: 1.5em; }
+nav ol { columns: 2; }
+ul.spaces { list-style: none; padding-left: 1.2em; }
+ul.spaces > li { margin: 0.2em 0; }
+svg { display: block; margin: 0.5em 0; }
+svg text { font-family: Consolas, "Courier New", monospace; }
+@media print { body { margin: 0; } a { color: inherit; text-decoration: none; } }
+"#;
+
+const SVG_DEVICE_WIDTH: usize = 112;
+const SVG_DEVICE_HEIGHT: usize = 40;
+const SVG_DEVICE_GAP: usize = 12;
+const SVG_DEVICES_PER_ROW: usize = 6;
+
+/// Render a project as a single static HTML document.
+///
+/// The report only uses inline CSS and SVG so it can be printed, archived or converted to PDF
+/// without any script support. Secrets (keys, tracing password) are never included.
+pub fn render_html_report(project: &KnxProjectData) -> String {
+    let title = project
+        .project_info
+        .as_ref()
+        .and_then(|info| info.name.clone())
+        .unwrap_or_else(|| project.project_name.clone());
+    let lines = group_devices_by_line(project);
+    let links_by_address = links_by_group_address(&project.devices);
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", escape(&title));
+    let _ = writeln!(out, "<style>{}</style>", REPORT_STYLE);
+    out.push_str("</head>\n<body>\n");
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&title));
+    let _ = writeln!(
+        out,
+        "<p class=\"muted\">{} devices, {} group addresses, {} lines</p>",
+        project.devices.len(),
+        project.group_addresses.len(),
+        lines.len()
+    );
+
+    out.push_str("<nav><ol>\n");
+    out.push_str("<li><a href=\"#project\">Project information</a></li>\n");
+    out.push_str("<li><a href=\"#topology\">Topology</a></li>\n");
+    out.push_str("<li><a href=\"#devices\">Devices</a></li>\n");
+    out.push_str("<li><a href=\"#group-addresses\">Group addresses</a></li>\n");
+    out.push_str("<li><a href=\"#locations\">Buildings and rooms</a></li>\n");
+    out.push_str("</ol></nav>\n");
+
+    write_project_section(&mut out, project);
+    write_topology_section(&mut out, project, &lines);
+    write_devices_section(&mut out, &lines);
+    write_group_address_section(&mut out, project, &links_by_address);
+    write_locations_section(&mut out, &project.locations);
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+struct LineGroup<'a> {
+    area: String,
+    line: String,
+    name: Option<String>,
+    medium: Option<String>,
+    devices: Vec<&'a DeviceInfo>,
+}
+
+fn write_project_section(out: &mut String, project: &KnxProjectData) {
+    out.push_str("<h2 id=\"project\" class=\"first\">Project information</h2>\n");
+    let Some(info) = project.project_info.as_ref() else {
+        let _ = writeln!(
+            out,
+            "<table class=\"kv\"><tr><th>Name</th><td>{}</td></tr></table>",
+            escape(&project.project_name)
+        );
+        return;
+    };
+
+    out.push_str("<table class=\"kv\">\n");
+    write_kv_row(out, "Name", Some(&project.project_name));
+    write_kv_row(out, "Project number", info.project_number.as_deref());
+    write_kv_row(out, "Contract number", info.contract_number.as_deref());
+    write_kv_row(out, "Project type", info.project_type.as_deref());
+    write_kv_row(out, "Description", info.description.as_deref());
+    write_kv_row(out, "Completion status", info.completion_status.as_deref());
+    write_kv_row(out, "ETS version", info.archived_version.as_deref());
+    write_kv_row(out, "Group address style", info.group_address_style.as_deref());
+    let schema = info.schema_version.map(|version| format!("project/{}", version));
+    write_kv_row(out, "Project schema", schema.as_deref());
+    write_kv_row(out, "Security mode", info.security_mode.as_deref());
+    write_kv_row(out, "Last modified", info.last_modified.as_deref());
+    write_kv_row(out, "Areas", Some(&project.areas.len().to_string()));
+    write_kv_row(out, "Lines", Some(&project.lines.len().to_string()));
+    write_kv_row(out, "Devices", Some(&project.devices.len().to_string()));
+    write_kv_row(
+        out,
+        "Group addresses",
+        Some(&project.group_addresses.len().to_string()),
+    );
+    if !info.tags.is_empty() {
+        let tags = info
+            .tags
+            .iter()
+            .map(|tag| tag.text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        write_kv_row(out, "Tags", Some(&tags));
+    }
+    out.push_str("</table>\n");
+
+    if !info.attachments.is_empty() {
+        out.push_str("<h3>Attachments</h3>\n<table>\n<tr><th>File</th><th>Comment</th></tr>\n");
+        for attachment in &info.attachments {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape(&attachment.filename),
+                escape_opt(attachment.comment.as_deref())
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if !info.history.is_empty() {
+        out.push_str("<h3>History</h3>\n<table>\n");
+        out.push_str("<tr><th>Date</th><th>User</th><th>Text</th><th>Detail</th></tr>\n");
+        for entry in &info.history {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"mono\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_opt(entry.date.as_deref()),
+                escape_opt(entry.user.as_deref()),
+                escape_opt(entry.text.as_deref()),
+                escape_opt(entry.detail.as_deref())
+            );
+        }
+        out.push_str("</table>\n");
+    }
+}
+
+fn write_topology_section(out: &mut String, project: &KnxProjectData, lines: &[LineGroup]) {
+    out.push_str("<h2 id=\"topology\">Topology</h2>\n");
+    if lines.is_empty() {
+        out.push_str("<p class=\"muted\">No devices in the topology.</p>\n");
+        return;
+    }
+
+    let area_names: HashMap<&str, &str> = project
+        .areas
+        .iter()
+        .filter_map(|area| area.name.as_deref().map(|name| (area.address.as_str(), name)))
+        .collect();
+
+    let mut current_area: Option<&str> = None;
+    for group in lines {
+        if current_area != Some(group.area.as_str()) {
+            current_area = Some(group.area.as_str());
+            let _ = writeln!(
+                out,
+                "<h3>Area {}{}</h3>",
+                escape(&group.area),
+                area_names
+                    .get(group.area.as_str())
+                    .map(|name| format!(" &ndash; {}", escape(name)))
+                    .unwrap_or_default()
+            );
+        }
+
+        let mut heading = format!("Line {}.{}", escape(&group.area), escape(&group.line));
+        if let Some(name) = &group.name {
+            let _ = write!(heading, " &ndash; {}", escape(name));
+        }
+        if let Some(medium) = &group.medium {
+            let _ = write!(heading, " <span class=\"muted\">({})</span>", escape(medium));
+        }
+        let _ = writeln!(out, "<h4>{}</h4>", heading);
+        write_line_svg(out, group);
+
+        out.push_str("<table>\n<tr><th>Address</th><th>Name</th><th>Manufacturer</th><th>Product</th><th>Order number</th><th>Application</th></tr>\n");
+        for device in &group.devices {
+            let _ = writeln!(
+                out,
+                "<tr><td class=\"mono\"><a href=\"#{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                device_anchor(device),
+                escape(&device.individual_address),
+                escape(&device.name),
+                escape_opt(device.manufacturer.as_deref()),
+                escape_opt(device.product.as_deref()),
+                escape_opt(device.product_reference.as_deref()),
+                escape(&application_label(device))
+            );
+        }
+        out.push_str("</table>\n");
+    }
+}
+
+fn write_line_svg(out: &mut String, group: &LineGroup) {
+    let count = group.devices.len().max(1);
+    let columns = count.min(SVG_DEVICES_PER_ROW);
+    let rows = count.div_ceil(SVG_DEVICES_PER_ROW);
+    let row_height = SVG_DEVICE_HEIGHT + 2 * SVG_DEVICE_GAP;
+    let width = columns * (SVG_DEVICE_WIDTH + SVG_DEVICE_GAP) + SVG_DEVICE_GAP;
+    let height = rows * row_height;
+
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" role=\"img\" aria-label=\"Line {a}.{l}\">",
+        w = width,
+        h = height,
+        a = escape(&group.area),
+        l = escape(&group.line)
+    );
+    for row in 0..rows {
+        // Bus cable drawn above each row of devices.
+        let bus_y = row * row_height + SVG_DEVICE_GAP / 2;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#40a02b\" stroke-width=\"3\"/>",
+            SVG_DEVICE_GAP / 2,
+            width - SVG_DEVICE_GAP / 2,
+            y = bus_y
+        );
+    }
+    for (index, device) in group.devices.iter().enumerate() {
+        let row = index / SVG_DEVICES_PER_ROW;
+        let column = index % SVG_DEVICES_PER_ROW;
+        let x = SVG_DEVICE_GAP + column * (SVG_DEVICE_WIDTH + SVG_DEVICE_GAP);
+        let y = row * row_height + SVG_DEVICE_GAP + SVG_DEVICE_GAP / 2;
+        let center = x + SVG_DEVICE_WIDTH / 2;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{c}\" y1=\"{}\" x2=\"{c}\" y2=\"{}\" stroke=\"#40a02b\" stroke-width=\"2\"/>",
+            y - SVG_DEVICE_GAP,
+            y,
+            c = center
+        );
+        let _ = writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"#eff1f5\" stroke=\"#1e66f5\"/>",
+            x, y, SVG_DEVICE_WIDTH, SVG_DEVICE_HEIGHT
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>",
+            center,
+            y + 16,
+            escape(&device.individual_address)
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" font-size=\"9\" text-anchor=\"middle\">{}</text>",
+            center,
+            y + 31,
+            escape(&truncate(&device.name, 18))
+        );
+    }
+    out.push_str("</svg>\n");
+}
+
+fn write_devices_section(out: &mut String, lines: &[LineGroup]) {
+    out.push_str("<h2 id=\"devices\">Devices</h2>\n");
+    for device in lines.iter().flat_map(|group| group.devices.iter()) {
+        let _ = writeln!(
+            out,
+            "<section class=\"device\" id=\"{}\">\n<h3>{} &ndash; {}</h3>",
+            device_anchor(device),
+            escape(&device.individual_address),
+            escape(&device.name)
+        );
+
+        out.push_str("<table class=\"kv\">\n");
+        write_kv_row(out, "Manufacturer", device.manufacturer.as_deref());
+        write_kv_row(out, "Product", device.product.as_deref());
+        write_kv_row(out, "Order number", device.product_reference.as_deref());
+        write_kv_row(out, "Serial number", device.serial_number.as_deref());
+        write_kv_row(out, "Application", device.app_program_name.as_deref());
+        write_kv_row(out, "Application version", device.app_program_version.as_deref());
+        write_kv_row(out, "Application number", device.app_program_number.as_deref());
+        write_kv_row(out, "Medium", device.medium_type.as_deref());
+        write_kv_row(out, "IP assignment", device.ip_assignment.as_deref());
+        write_kv_row(out, "IP address", device.ip_address.as_deref());
+        write_kv_row(out, "Subnet mask", device.ip_subnet_mask.as_deref());
+        write_kv_row(out, "Default gateway", device.ip_default_gateway.as_deref());
+        write_kv_row(out, "MAC address", device.mac_address.as_deref());
+        write_kv_row(out, "Description", device.description.as_deref());
+        write_kv_row(out, "Comment", device.comment.as_deref());
+        write_kv_row(out, "Last modified", device.last_modified.as_deref());
+        write_kv_row(out, "Last download", device.last_download.as_deref());
+        out.push_str("</table>\n");
+
+        if !device.group_links.is_empty() {
+            out.push_str("<h4>Group objects</h4>\n<table>\n");
+            out.push_str("<tr><th>#</th><th>Object</th><th>Group address</th><th>DPT</th><th>Flags</th><th>Role</th></tr>\n");
+            for link in sorted_links(&device.group_links) {
+                let _ = writeln!(
+                    out,
+                    "<tr><td class=\"mono\">{}</td><td>{}</td><td class=\"mono\"><a href=\"#{}\">{}</a></td><td class=\"mono\">{}</td><td class=\"mono\">{}</td><td>{}</td></tr>",
+                    link.number.map(|n| n.to_string()).unwrap_or_default(),
+                    escape(&link.object_name),
+                    group_address_anchor(&link.group_address),
+                    escape(&link.group_address),
+                    escape_opt(link.datapoint_type.as_deref()),
+                    flags_label(link.flags.as_ref()),
+                    link_role(link)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+
+        if !device.configuration_entries.is_empty() {
+            out.push_str("<h4>Parameters</h4>\n<table>\n");
+            out.push_str("<tr><th>Context</th><th>Parameter</th><th>Value</th></tr>\n");
+            for entry in &device.configuration_entries {
+                let _ = writeln!(
+                    out,
+                    "<tr><td class=\"muted\">{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_opt(entry.context.as_deref()),
+                    escape(&entry.name),
+                    escape(&entry.value)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+
+        out.push_str("</section>\n");
+    }
+}
+
+fn write_group_address_section(
+    out: &mut String,
+    project: &KnxProjectData,
+    links_by_address: &HashMap<&str, Vec<(&DeviceInfo, &GroupLink)>>,
+) {
+    out.push_str("<h2 id=\"group-addresses\">Group addresses</h2>\n");
+    if project.group_addresses.is_empty() {
+        out.push_str("<p class=\"muted\">No group addresses.</p>\n");
+        return;
+    }
+
+    let mut current_range: Option<(Option<&str>, Option<&str>)> = None;
+    let mut table_open = false;
+    for ga in &project.group_addresses {
+        let range = (ga.main_group_name.as_deref(), ga.middle_group_name.as_deref());
+        if current_range != Some(range) {
+            if table_open {
+                out.push_str("</table>\n");
+            }
+            current_range = Some(range);
+            let heading = [range.0, range.1]
+                .into_iter()
+                .flatten()
+                .map(escape)
+                .collect::<Vec<_>>()
+                .join(" / ");
+            if !heading.is_empty() {
+                let _ = writeln!(out, "<h4>{}</h4>", heading);
+            }
+            out.push_str("<table>\n<tr><th>Address</th><th>Name</th><th>DPT</th><th>Description</th><th>Linked objects</th></tr>\n");
+            table_open = true;
+        }
+
+        let mut linked = String::new();
+        if let Some(links) = links_by_address.get(ga.address.as_str()) {
+            for (device, link) in links {
+                let _ = write!(
+                    linked,
+                    "<a href=\"#{}\" class=\"mono\">{}</a> {}{} <span class=\"mono muted\">{}</span>{}<br>",
+                    device_anchor(device),
+                    escape(&device.individual_address),
+                    link.number.map(|n| format!("#{} ", n)).unwrap_or_default(),
+                    escape(&link.object_name),
+                    flags_label(link.flags.as_ref()),
+                    if link.ets_sending { " (S)" } else { "" }
+                );
+            }
+        } else if !ga.linked_devices.is_empty() {
+            linked = escape(&ga.linked_devices.join(", "));
+        }
+
+        let _ = writeln!(
+            out,
+            "<tr id=\"{}\"><td class=\"mono\">{}</td><td>{}</td><td class=\"mono\">{}</td><td>{}</td><td>{}</td></tr>",
+            group_address_anchor(&ga.address),
+            escape(&ga.address),
+            escape(&ga.name),
+            escape_opt(ga.datapoint_type.as_deref()),
+            escape_opt(ga.description.as_deref().or(ga.comment.as_deref())),
+            linked
+        );
+    }
+    if table_open {
+        out.push_str("</table>\n");
+    }
+}
+
+fn write_locations_section(out: &mut String, locations: &[BuildingSpace]) {
+    out.push_str("<h2 id=\"locations\">Buildings and rooms</h2>\n");
+    if locations.is_empty() {
+        out.push_str("<p class=\"muted\">No building structure defined.</p>\n");
+        return;
+    }
+    write_spaces(out, locations);
+}
+
+fn write_spaces(out: &mut String, spaces: &[BuildingSpace]) {
+    out.push_str("<ul class=\"spaces\">\n");
+    for space in spaces {
+        let _ = write!(
+            out,
+            "<li><strong>{}</strong> <span class=\"muted\">{}{}</span>",
+            escape(space.name.as_deref().unwrap_or(&space.id)),
+            escape(&space.space_type),
+            space
+                .number
+                .as_deref()
+                .map(|number| format!(" {}", escape(number)))
+                .unwrap_or_default()
+        );
+        if !space.devices.is_empty() {
+            out.push_str("\n<table>\n<tr><th>Address</th><th>Device</th></tr>\n");
+            for device in &space.devices {
+                let address = device.address.as_deref().unwrap_or("");
+                let _ = writeln!(
+                    out,
+                    "<tr><td class=\"mono\"><a href=\"#device-{}\">{}</a></td><td>{}</td></tr>",
+                    anchor_fragment(address),
+                    escape(address),
+                    escape(device.name.as_deref().unwrap_or(&device.instance_id))
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        if !space.children.is_empty() {
+            write_spaces(out, &space.children);
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+}
+
+fn write_kv_row(out: &mut String, label: &str, value: Option<&str>) {
+    let Some(value) = value.filter(|value| !value.trim().is_empty()) else {
+        return;
+    };
+    let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(label), escape(value));
+}
+
+fn group_devices_by_line(project: &KnxProjectData) -> Vec<LineGroup<'_>> {
+    let mut groups: BTreeMap<(u32, u32, String, String), Vec<&DeviceInfo>> = BTreeMap::new();
+    for device in &project.devices {
+        let mut parts = device.individual_address.split('.');
+        let area = parts.next().unwrap_or("").trim().to_string();
+        let line = parts.next().unwrap_or("").trim().to_string();
+        let key = (numeric_key(&area), numeric_key(&line), area, line);
+        groups.entry(key).or_default().push(device);
+    }
+
+    groups
+        .into_iter()
+        .map(|((_, _, area, line), mut devices)| {
+            devices.sort_by_key(|device| {
+                let number = device.individual_address.rsplit('.').next().unwrap_or("");
+                (numeric_key(number), device.individual_address.clone())
+            });
+            let info = project
+                .lines
+                .iter()
+                .find(|info| info.area == area && info.line == line);
+            LineGroup {
+                name: info.and_then(|info| info.name.clone()),
+                medium: info
+                    .and_then(|info| info.medium_type.clone())
+                    .or_else(|| devices.iter().find_map(|device| device.medium_type.clone())),
+                area,
+                line,
+                devices,
+            }
+        })
+        .collect()
+}
+
+fn links_by_group_address(devices: &[DeviceInfo]) -> HashMap<&str, Vec<(&DeviceInfo, &GroupLink)>> {
+    let mut map: HashMap<&str, Vec<(&DeviceInfo, &GroupLink)>> = HashMap::new();
+    for device in devices {
+        for link in &device.group_links {
+            map.entry(link.group_address.as_str())
+                .or_default()
+                .push((device, link));
+        }
+    }
+    map
+}
+
+fn sorted_links(links: &[GroupLink]) -> Vec<&GroupLink> {
+    let mut sorted: Vec<&GroupLink> = links.iter().collect();
+    sorted.sort_by_key(|link| (link.number.unwrap_or(u32::MAX), !link.ets_sending));
+    sorted
+}
+
+fn numeric_key(value: &str) -> u32 {
+    value.parse().unwrap_or(u32::MAX)
+}
+
+fn application_label(device: &DeviceInfo) -> String {
+    match (&device.app_program_name, &device.app_program_version) {
+        (Some(name), Some(version)) => format!("{} ({})", name, version),
+        (Some(name), None) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+fn link_role(link: &GroupLink) -> &'static str {
+    if link.ets_sending {
+        "Sending"
+    } else {
+        "Receiving"
+    }
+}
+
+fn device_anchor(device: &DeviceInfo) -> String {
+    format!("device-{}", anchor_fragment(&device.individual_address))
+}
+
+fn group_address_anchor(address: &str) -> String {
+    format!("ga-{}", anchor_fragment(address))
+}
+
+fn anchor_fragment(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+        .collect()
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    if value.chars().count() <= max_chars {
+        return value.to_string();
+    }
+    let mut truncated: String = value.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('\u{2026}');
+    truncated
+}
+
+fn escape_opt(value: Option<&str>) -> String {
+    value.map(escape).unwrap_or_default()
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_html_report;
+    use crate::knx::{DeviceInfo, GroupAddressInfo, GroupLink, KnxProjectData, ObjectFlags};
+
+    fn sample_project() -> KnxProjectData {
+        KnxProjectData {
+            project_name: "Villa <Test>".to_string(),
+            devices: vec![DeviceInfo {
+                instance_id: "P-0001-0_DI-1".to_string(),
+                individual_address: "1.1.5".to_string(),
+                name: "Switch actuator".to_string(),
+                group_links: vec![GroupLink {
+                    object_name: "Switch".to_string(),
+                    group_address: "1/0/1".to_string(),
+                    ets_sending: true,
+                    number: Some(1),
+                    flags: Some(ObjectFlags {
+                        communication: true,
+                        read: false,
+                        write: true,
+                        transmit: true,
+                        update: false,
+                        read_on_init: false,
+                    }),
+                    ..GroupLink::default()
+                }],
+                ..DeviceInfo::default()
+            }],
+            group_addresses: vec![GroupAddressInfo {
+                address: "1/0/1".to_string(),
+                name: "Kitchen light".to_string(),
+                security_key: Some("c2VjcmV0".to_string()),
+                linked_devices: vec!["1.1.5".to_string()],
+                ..GroupAddressInfo::default()
+            }],
+            ..KnxProjectData::default()
+        }
+    }
+
+    #[test]
+    fn report_escapes_text_and_links_devices() {
+        let html = render_html_report(&sample_project());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Villa &lt;Test&gt;</title>"));
+        assert!(html.contains("id=\"device-1-1-5\""));
+        assert!(html.contains("href=\"#ga-1-0-1\""));
+        assert!(html.contains("<svg"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("c2VjcmV0"));
+    }
+}
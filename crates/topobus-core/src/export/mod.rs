@@ -0,0 +1,49 @@
+pub mod csv;
+pub mod html;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use csv::{render_csv, CsvOptions, CsvTable};
+pub use html::render_html_report;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{export_sqlite, write_sqlite};
+
+use crate::knx::ObjectFlags;
+
+/// Format communication object flags in ETS order, using `-` for disabled flags.
+pub(crate) fn flags_label(flags: Option<&ObjectFlags>) -> String {
+    let Some(flags) = flags else {
+        return String::new();
+    };
+    [
+        (flags.communication, 'C'),
+        (flags.read, 'R'),
+        (flags.write, 'W'),
+        (flags.transmit, 'T'),
+        (flags.update, 'U'),
+        (flags.read_on_init, 'I'),
+    ]
+    .iter()
+    .map(|(enabled, letter)| if *enabled { *letter } else { '-' })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::flags_label;
+    use crate::knx::ObjectFlags;
+
+    #[test]
+    fn flags_label_uses_ets_order() {
+        let flags = ObjectFlags {
+            communication: true,
+            read: true,
+            write: false,
+            transmit: true,
+            update: false,
+            read_on_init: true,
+        };
+        assert_eq!(flags_label(Some(&flags)), "CR-T-I");
+        assert_eq!(flags_label(None), "");
+    }
+}
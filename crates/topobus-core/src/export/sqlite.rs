@@ -0,0 +1,669 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Transaction};
+
+use crate::knx::{BuildingSpace, DeviceInfo, GroupAddressInfo, KnxProjectData};
+
+/// Relational schema written by [`export_sqlite`].
+///
+/// Group objects are deduplicated per device and `ComObjectInstanceRef`; each association to a
+/// group address becomes one `group_links` row. Datapoint types are additionally split into
+/// numeric `dpt_main`/`dpt_sub` columns so that queries such as "all DPT 9 objects" stay simple.
+/// Secrets (group address keys, BCU key, tracing password) are not exported.
+pub const SQLITE_SCHEMA: &str = r#"
+CREATE TABLE project (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    project_number TEXT,
+    contract_number TEXT,
+    description TEXT,
+    archived_version TEXT,
+    group_address_style TEXT,
+    schema_version INTEGER,
+    last_modified TEXT
+);
+CREATE TABLE areas (
+    id INTEGER PRIMARY KEY,
+    address INTEGER,
+    name TEXT,
+    description TEXT,
+    comment TEXT,
+    completion_status TEXT
+);
+CREATE TABLE lines (
+    id INTEGER PRIMARY KEY,
+    area_id INTEGER REFERENCES areas(id),
+    address INTEGER,
+    full_address TEXT NOT NULL,
+    name TEXT,
+    description TEXT,
+    comment TEXT,
+    medium_type TEXT,
+    completion_status TEXT
+);
+CREATE TABLE devices (
+    id INTEGER PRIMARY KEY,
+    line_id INTEGER REFERENCES lines(id),
+    instance_id TEXT NOT NULL UNIQUE,
+    individual_address TEXT NOT NULL,
+    name TEXT NOT NULL,
+    manufacturer TEXT,
+    product TEXT,
+    product_reference TEXT,
+    description TEXT,
+    comment TEXT,
+    serial_number TEXT,
+    app_program_name TEXT,
+    app_program_version TEXT,
+    app_program_number TEXT,
+    app_program_type TEXT,
+    app_mask_version TEXT,
+    medium_type TEXT,
+    segment_id TEXT,
+    segment_number TEXT,
+    segment_domain_address TEXT,
+    segment_medium_type TEXT,
+    ip_assignment TEXT,
+    ip_address TEXT,
+    ip_subnet_mask TEXT,
+    ip_default_gateway TEXT,
+    mac_address TEXT,
+    last_modified TEXT,
+    last_download TEXT
+);
+CREATE TABLE group_ranges (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER REFERENCES group_ranges(id),
+    level INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    name TEXT,
+    description TEXT,
+    comment TEXT
+);
+CREATE TABLE group_addresses (
+    id INTEGER PRIMARY KEY,
+    group_range_id INTEGER REFERENCES group_ranges(id),
+    address TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    description TEXT,
+    comment TEXT,
+    datapoint_type TEXT,
+    dpt_main INTEGER,
+    dpt_sub INTEGER,
+    security TEXT
+);
+CREATE TABLE com_objects (
+    id INTEGER PRIMARY KEY,
+    device_id INTEGER NOT NULL REFERENCES devices(id),
+    com_object_ref_id TEXT,
+    number INTEGER,
+    name TEXT NOT NULL,
+    name_raw TEXT,
+    text TEXT,
+    function_text TEXT,
+    channel TEXT,
+    datapoint_type TEXT,
+    dpt_main INTEGER,
+    dpt_sub INTEGER,
+    object_size TEXT,
+    description TEXT,
+    security TEXT,
+    building_function TEXT,
+    building_part TEXT,
+    flag_communication INTEGER,
+    flag_read INTEGER,
+    flag_write INTEGER,
+    flag_transmit INTEGER,
+    flag_update INTEGER,
+    flag_read_on_init INTEGER
+);
+CREATE TABLE group_links (
+    id INTEGER PRIMARY KEY,
+    com_object_id INTEGER NOT NULL REFERENCES com_objects(id),
+    group_address_id INTEGER REFERENCES group_addresses(id),
+    group_address TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    is_sending INTEGER NOT NULL,
+    is_receiving INTEGER NOT NULL
+);
+CREATE TABLE parameters (
+    id INTEGER PRIMARY KEY,
+    device_id INTEGER NOT NULL REFERENCES devices(id),
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    value_raw TEXT,
+    value_label TEXT,
+    parameter_type TEXT,
+    context TEXT,
+    ref_id TEXT,
+    source TEXT
+);
+CREATE TABLE spaces (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER REFERENCES spaces(id),
+    space_id TEXT NOT NULL,
+    name TEXT,
+    space_type TEXT NOT NULL,
+    number TEXT,
+    default_line TEXT,
+    description TEXT,
+    completion_status TEXT
+);
+CREATE TABLE space_devices (
+    space_id INTEGER NOT NULL REFERENCES spaces(id),
+    device_id INTEGER REFERENCES devices(id),
+    instance_id TEXT NOT NULL
+);
+CREATE INDEX idx_devices_line ON devices(line_id);
+CREATE INDEX idx_com_objects_device ON com_objects(device_id);
+CREATE INDEX idx_group_links_object ON group_links(com_object_id);
+CREATE INDEX idx_group_links_address ON group_links(group_address_id);
+CREATE INDEX idx_parameters_device ON parameters(device_id);
+CREATE INDEX idx_spaces_parent ON spaces(parent_id);
+CREATE INDEX idx_space_devices_space ON space_devices(space_id);
+"#;
+
+/// Export a project to a new SQLite database at `path`, replacing any existing file.
+pub fn export_sqlite(project: &KnxProjectData, path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    let mut conn = Connection::open(path)
+        .with_context(|| format!("Failed to create SQLite database {}", path.display()))?;
+    write_sqlite(project, &mut conn)
+}
+
+/// Write the schema and project rows into an empty SQLite connection.
+pub fn write_sqlite(project: &KnxProjectData, conn: &mut Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    let tx = conn.transaction()?;
+    tx.execute_batch(SQLITE_SCHEMA)
+        .context("Failed to create SQLite schema")?;
+
+    insert_project(&tx, project)?;
+    let line_ids = insert_topology(&tx, project)?;
+    let device_ids = insert_devices(&tx, &project.devices, &line_ids)?;
+    let group_address_ids = insert_group_addresses(&tx, &project.group_addresses)?;
+    insert_com_objects(&tx, &project.devices, &device_ids, &group_address_ids)?;
+    insert_parameters(&tx, &project.devices, &device_ids)?;
+    let instance_ids: HashMap<&str, i64> = project
+        .devices
+        .iter()
+        .zip(device_ids.iter())
+        .map(|(device, id)| (device.instance_id.as_str(), *id))
+        .collect();
+    for space in &project.locations {
+        insert_space(&tx, space, None, &instance_ids)?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+fn insert_project(tx: &Transaction, project: &KnxProjectData) -> Result<()> {
+    let info = project.project_info.clone().unwrap_or_default();
+    tx.execute(
+        "INSERT INTO project (name, project_number, contract_number, description, archived_version, group_address_style, schema_version, last_modified)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            project.project_name,
+            info.project_number,
+            info.contract_number,
+            info.description,
+            info.archived_version,
+            info.group_address_style,
+            info.schema_version,
+            info.last_modified,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert areas and lines, returning line row ids keyed by "area.line".
+fn insert_topology(tx: &Transaction, project: &KnxProjectData) -> Result<HashMap<String, i64>> {
+    let mut area_ids: HashMap<String, i64> = HashMap::new();
+    let mut area_stmt = tx.prepare(
+        "INSERT INTO areas (address, name, description, comment, completion_status) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for area in &project.areas {
+        if area_ids.contains_key(&area.address) {
+            continue;
+        }
+        area_stmt.execute(params![
+            area.address.parse::<i64>().ok(),
+            area.name,
+            area.description,
+            area.comment,
+            area.completion_status,
+        ])?;
+        area_ids.insert(area.address.clone(), tx.last_insert_rowid());
+    }
+
+    let mut line_ids: HashMap<String, i64> = HashMap::new();
+    let mut line_stmt = tx.prepare(
+        "INSERT INTO lines (area_id, address, full_address, name, description, comment, medium_type, completion_status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for line in &project.lines {
+        let full_address = format!("{}.{}", line.area, line.line);
+        if line_ids.contains_key(&full_address) {
+            continue;
+        }
+        line_stmt.execute(params![
+            area_ids.get(&line.area),
+            line.line.parse::<i64>().ok(),
+            full_address,
+            line.name,
+            line.description,
+            line.comment,
+            line.medium_type,
+            line.completion_status,
+        ])?;
+        line_ids.insert(full_address, tx.last_insert_rowid());
+    }
+
+    Ok(line_ids)
+}
+
+fn insert_devices(
+    tx: &Transaction,
+    devices: &[DeviceInfo],
+    line_ids: &HashMap<String, i64>,
+) -> Result<Vec<i64>> {
+    let mut stmt = tx.prepare(
+        "INSERT INTO devices (line_id, instance_id, individual_address, name, manufacturer, product, product_reference,
+            description, comment, serial_number, app_program_name, app_program_version, app_program_number,
+            app_program_type, app_mask_version, medium_type, segment_id, segment_number, segment_domain_address,
+            segment_medium_type, ip_assignment, ip_address, ip_subnet_mask, ip_default_gateway, mac_address,
+            last_modified, last_download)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+            ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
+    )?;
+    let mut ids = Vec::with_capacity(devices.len());
+    for device in devices {
+        let mut parts = device.individual_address.split('.');
+        let line_key = match (parts.next(), parts.next()) {
+            (Some(area), Some(line)) => format!("{}.{}", area.trim(), line.trim()),
+            _ => String::new(),
+        };
+        stmt.execute(params![
+            line_ids.get(&line_key),
+            device.instance_id,
+            device.individual_address,
+            device.name,
+            device.manufacturer,
+            device.product,
+            device.product_reference,
+            device.description,
+            device.comment,
+            device.serial_number,
+            device.app_program_name,
+            device.app_program_version,
+            device.app_program_number,
+            device.app_program_type,
+            device.app_mask_version,
+            device.medium_type,
+            device.segment_id,
+            device.segment_number,
+            device.segment_domain_address,
+            device.segment_medium_type,
+            device.ip_assignment,
+            device.ip_address,
+            device.ip_subnet_mask,
+            device.ip_default_gateway,
+            device.mac_address,
+            device.last_modified,
+            device.last_download,
+        ])
+        .with_context(|| format!("Failed to insert device {}", device.instance_id))?;
+        ids.push(tx.last_insert_rowid());
+    }
+    Ok(ids)
+}
+
+/// Insert group ranges (derived from the main/middle names) and group addresses.
+fn insert_group_addresses(
+    tx: &Transaction,
+    group_addresses: &[GroupAddressInfo],
+) -> Result<HashMap<String, i64>> {
+    let mut range_stmt = tx.prepare(
+        "INSERT INTO group_ranges (parent_id, level, address, name, description, comment) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut ga_stmt = tx.prepare(
+        "INSERT INTO group_addresses (group_range_id, address, name, description, comment, datapoint_type, dpt_main, dpt_sub, security)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+
+    let mut range_ids: HashMap<String, i64> = HashMap::new();
+    let mut ga_ids: HashMap<String, i64> = HashMap::new();
+    for ga in group_addresses {
+        if ga_ids.contains_key(&ga.address) {
+            continue;
+        }
+        let parts: Vec<&str> = ga.address.split('/').collect();
+        let mut range_id = None;
+        if parts.len() >= 2 {
+            let main_key = parts[0].to_string();
+            let main_id = match range_ids.get(&main_key) {
+                Some(id) => *id,
+                None => {
+                    range_stmt.execute(params![
+                        None::<i64>,
+                        0,
+                        main_key,
+                        ga.main_group_name,
+                        ga.main_group_description,
+                        ga.main_group_comment,
+                    ])?;
+                    let id = tx.last_insert_rowid();
+                    range_ids.insert(main_key, id);
+                    id
+                }
+            };
+            range_id = Some(main_id);
+            if parts.len() == 3 {
+                let middle_key = format!("{}/{}", parts[0], parts[1]);
+                let middle_id = match range_ids.get(&middle_key) {
+                    Some(id) => *id,
+                    None => {
+                        range_stmt.execute(params![
+                            main_id,
+                            1,
+                            middle_key,
+                            ga.middle_group_name,
+                            ga.middle_group_description,
+                            ga.middle_group_comment,
+                        ])?;
+                        let id = tx.last_insert_rowid();
+                        range_ids.insert(middle_key, id);
+                        id
+                    }
+                };
+                range_id = Some(middle_id);
+            }
+        }
+
+        let (dpt_main, dpt_sub) = split_datapoint_type(ga.datapoint_type.as_deref());
+        ga_stmt.execute(params![
+            range_id,
+            ga.address,
+            ga.name,
+            ga.description,
+            ga.comment,
+            ga.datapoint_type,
+            dpt_main,
+            dpt_sub,
+            ga.security,
+        ])?;
+        ga_ids.insert(ga.address.clone(), tx.last_insert_rowid());
+    }
+    Ok(ga_ids)
+}
+
+fn insert_com_objects(
+    tx: &Transaction,
+    devices: &[DeviceInfo],
+    device_ids: &[i64],
+    group_address_ids: &HashMap<String, i64>,
+) -> Result<()> {
+    let mut object_stmt = tx.prepare(
+        "INSERT INTO com_objects (device_id, com_object_ref_id, number, name, name_raw, text, function_text, channel,
+            datapoint_type, dpt_main, dpt_sub, object_size, description, security, building_function, building_part,
+            flag_communication, flag_read, flag_write, flag_transmit, flag_update, flag_read_on_init)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+    )?;
+    let mut link_stmt = tx.prepare(
+        "INSERT INTO group_links (com_object_id, group_address_id, group_address, position, is_sending, is_receiving)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+
+    for (device, device_id) in devices.iter().zip(device_ids.iter()) {
+        let mut object_ids: HashMap<&str, (i64, i64)> = HashMap::new();
+        for (index, link) in device.group_links.iter().enumerate() {
+            // Links without a ComObjectInstanceRef id cannot be regrouped; keep them as single objects.
+            let fallback_key = format!("#{}", index);
+            let key = link.com_object_ref_id.as_deref().unwrap_or(&fallback_key);
+            let (object_id, position) = match object_ids.get(key) {
+                Some((id, position)) => (*id, *position),
+                None => {
+                    let flags = link.flags.as_ref();
+                    let (dpt_main, dpt_sub) = split_datapoint_type(link.datapoint_type.as_deref());
+                    object_stmt.execute(params![
+                        device_id,
+                        link.com_object_ref_id,
+                        link.number,
+                        link.object_name,
+                        link.object_name_raw,
+                        link.object_text,
+                        link.object_function_text,
+                        link.channel,
+                        link.datapoint_type,
+                        dpt_main,
+                        dpt_sub,
+                        link.object_size,
+                        link.description,
+                        link.security,
+                        link.building_function,
+                        link.building_part,
+                        flags.map(|f| f.communication),
+                        flags.map(|f| f.read),
+                        flags.map(|f| f.write),
+                        flags.map(|f| f.transmit),
+                        flags.map(|f| f.update),
+                        flags.map(|f| f.read_on_init),
+                    ])?;
+                    (tx.last_insert_rowid(), 0)
+                }
+            };
+            link_stmt.execute(params![
+                object_id,
+                group_address_ids.get(&link.group_address),
+                link.group_address,
+                position,
+                link.ets_sending,
+                link.ets_receiving,
+            ])?;
+            if link.com_object_ref_id.is_some() {
+                object_ids.insert(
+                    link.com_object_ref_id.as_deref().unwrap_or_default(),
+                    (object_id, position + 1),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn insert_parameters(tx: &Transaction, devices: &[DeviceInfo], device_ids: &[i64]) -> Result<()> {
+    let mut stmt = tx.prepare(
+        "INSERT INTO parameters (device_id, name, value, value_raw, value_label, parameter_type, context, ref_id, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for (device, device_id) in devices.iter().zip(device_ids.iter()) {
+        for entry in &device.configuration_entries {
+            stmt.execute(params![
+                device_id,
+                entry.name,
+                entry.value,
+                entry.value_raw,
+                entry.value_label,
+                entry.parameter_type,
+                entry.context,
+                entry.ref_id,
+                entry.source,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+fn insert_space(
+    tx: &Transaction,
+    space: &BuildingSpace,
+    parent_id: Option<i64>,
+    device_ids: &HashMap<&str, i64>,
+) -> Result<()> {
+    tx.execute(
+        "INSERT INTO spaces (parent_id, space_id, name, space_type, number, default_line, description, completion_status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            parent_id,
+            space.id,
+            space.name,
+            space.space_type,
+            space.number,
+            space.default_line,
+            space.description,
+            space.completion_status,
+        ],
+    )?;
+    let id = tx.last_insert_rowid();
+    for device in &space.devices {
+        tx.execute(
+            "INSERT INTO space_devices (space_id, device_id, instance_id) VALUES (?1, ?2, ?3)",
+            params![id, device_ids.get(device.instance_id.as_str()), device.instance_id],
+        )?;
+    }
+    for child in &space.children {
+        insert_space(tx, child, Some(id), device_ids)?;
+    }
+    Ok(())
+}
+
+/// Split "DPST-9-1" / "DPT-9" into numeric main and sub numbers.
+fn split_datapoint_type(value: Option<&str>) -> (Option<i64>, Option<i64>) {
+    let Some(value) = value else {
+        return (None, None);
+    };
+    // Several DPTs may be listed; the first one is the effective type.
+    let first = value.split_whitespace().next().unwrap_or("");
+    let mut parts = first.split('-').skip(1);
+    let main = parts.next().and_then(|part| part.parse().ok());
+    let sub = parts.next().and_then(|part| part.parse().ok());
+    (main, sub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_datapoint_type, write_sqlite};
+    use crate::knx::{
+        AreaInfo, BuildingDeviceRef, BuildingSpace, DeviceInfo, GroupAddressInfo, GroupLink,
+        KnxProjectData, LineInfo, ObjectFlags,
+    };
+    use rusqlite::Connection;
+
+    fn link(ref_id: &str, address: &str, sending: bool) -> GroupLink {
+        GroupLink {
+            com_object_ref_id: Some(ref_id.to_string()),
+            object_name: "Temperature".to_string(),
+            group_address: address.to_string(),
+            ets_sending: sending,
+            ets_receiving: !sending,
+            datapoint_type: Some("DPST-9-1".to_string()),
+            flags: Some(ObjectFlags {
+                communication: true,
+                read: true,
+                write: false,
+                transmit: true,
+                update: false,
+                read_on_init: false,
+            }),
+            ..GroupLink::default()
+        }
+    }
+
+    #[test]
+    fn writes_relational_rows() -> anyhow::Result<()> {
+        let project = KnxProjectData {
+            project_name: "Test".to_string(),
+            areas: vec![AreaInfo {
+                address: "1".to_string(),
+                name: Some("Building".to_string()),
+                description: None,
+                comment: None,
+                completion_status: None,
+            }],
+            lines: vec![LineInfo {
+                area: "1".to_string(),
+                line: "1".to_string(),
+                name: None,
+                description: None,
+                comment: None,
+                medium_type: Some("TP".to_string()),
+                completion_status: None,
+            }],
+            devices: vec![DeviceInfo {
+                instance_id: "DI-1".to_string(),
+                individual_address: "1.1.1".to_string(),
+                name: "Sensor".to_string(),
+                group_links: vec![link("O-1_R-1", "1/2/3", true), link("O-1_R-1", "1/2/4", false)],
+                ..DeviceInfo::default()
+            }],
+            group_addresses: vec![
+                GroupAddressInfo {
+                    address: "1/2/3".to_string(),
+                    name: "Temp".to_string(),
+                    main_group_name: Some("Heating".to_string()),
+                    ..GroupAddressInfo::default()
+                },
+                GroupAddressInfo {
+                    address: "1/2/4".to_string(),
+                    name: "Temp status".to_string(),
+                    ..GroupAddressInfo::default()
+                },
+            ],
+            locations: vec![BuildingSpace {
+                id: "BP-1".to_string(),
+                name: Some("B".to_string()),
+                space_type: "Building".to_string(),
+                children: vec![BuildingSpace {
+                    id: "BP-2".to_string(),
+                    space_type: "Room".to_string(),
+                    devices: vec![BuildingDeviceRef {
+                        instance_id: "DI-1".to_string(),
+                        address: None,
+                        name: None,
+                    }],
+                    ..BuildingSpace::default()
+                }],
+                ..BuildingSpace::default()
+            }],
+            ..KnxProjectData::default()
+        };
+
+        let mut conn = Connection::open_in_memory()?;
+        write_sqlite(&project, &mut conn)?;
+
+        let objects: i64 = conn.query_row("SELECT COUNT(*) FROM com_objects", [], |row| row.get(0))?;
+        let links: i64 = conn.query_row("SELECT COUNT(*) FROM group_links", [], |row| row.get(0))?;
+        let ranges: i64 = conn.query_row("SELECT COUNT(*) FROM group_ranges", [], |row| row.get(0))?;
+        assert_eq!((objects, links, ranges), (1, 2, 2));
+
+        let in_building: i64 = conn.query_row(
+            "WITH RECURSIVE tree(id) AS (
+                SELECT id FROM spaces WHERE name = 'B'
+                UNION ALL SELECT spaces.id FROM spaces JOIN tree ON spaces.parent_id = tree.id)
+             SELECT COUNT(*) FROM com_objects o
+             JOIN space_devices sd ON sd.device_id = o.device_id
+             WHERE sd.space_id IN tree AND o.flag_transmit = 1 AND o.dpt_main = 9",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(in_building, 1);
+
+        let violations: i64 =
+            conn.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))?;
+        assert_eq!(violations, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn splits_datapoint_types() {
+        assert_eq!(split_datapoint_type(Some("DPST-9-1")), (Some(9), Some(1)));
+        assert_eq!(split_datapoint_type(Some("DPT-5")), (Some(5), None));
+        assert_eq!(split_datapoint_type(None), (None, None));
+    }
+}
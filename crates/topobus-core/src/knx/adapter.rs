@@ -1,11 +1,22 @@
 use super::model::*;
+use crate::knx::address::{parse_group_address_style, GroupAddressStyle};
+use crate::knx::archive::ProjectArchive;
+use crate::knx::limits::ArchiveLimitError;
+use crate::knx::loader::LoadOptions;
 use crate::knx::parsers::device::extract_devices;
 use crate::knx::parsers::group_addresses::extract_group_addresses;
-use crate::knx::parsers::project::{extract_project_info, extract_project_name};
+use crate::knx::parsers::project::{
+    extract_project_info,
+    extract_project_name,
+    extract_schema_version,
+};
 use crate::knx::parsers::topology::extract_topology_metadata;
+use crate::knx::progress::{report, ProgressEvent};
+use crate::knx::schema::schema_adapter;
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::attr_value;
-use crate::knx::zip_utils::{read_zip_entry, strip_bom};
+use crate::knx::zip_utils::strip_bom;
 use anyhow::{anyhow, Context, Result};
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine;
@@ -13,10 +24,6 @@ use pbkdf2::pbkdf2_hmac;
 use roxmltree::Document;
 use sha2::Sha256;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{Cursor, Read, Seek};
-use zip::result::{InvalidPassword, ZipError};
-use zip::ZipArchive;
 
 #[derive(Debug)]
 pub struct PasswordRequiredError;
@@ -44,73 +51,57 @@ const ZIP_PASSWORD_SALT: &str = "21.project.ets.knx.org";
 const ZIP_PASSWORD_ITERATIONS: u32 = 65_536;
 const ZIP_PASSWORD_KEY_LEN: usize = 32;
 
-pub fn load_knxproj(path: &str, password: Option<&str>) -> Result<KnxProjectData> {
-    log::info!("Loading KNX project from: {}", path);
-    let file = File::open(path).context("Failed to open .knxproj file")?;
-    load_knxproj_reader(file, password, None)
-}
-
-pub fn load_knxproj_bytes(data: &[u8], password: Option<&str>) -> Result<KnxProjectData> {
-    log::info!("Loading KNX project from bytes ({} bytes)", data.len());
-    let cursor = Cursor::new(data);
-    load_knxproj_reader(cursor, password, None)
-}
-
-pub fn load_knxproj_with_language(
-    path: &str,
+/// Load a project from an opened archive; [`crate::knx::ProjectLoader`] is the public entry point.
+pub(crate) fn load_archive(
+    archive: &mut dyn ProjectArchive,
     password: Option<&str>,
-    preferred_language: Option<&str>,
+    options: &LoadOptions,
 ) -> Result<KnxProjectData> {
-    log::info!("Loading KNX project from: {}", path);
-    let file = File::open(path).context("Failed to open .knxproj file")?;
-    load_knxproj_reader(file, password, preferred_language)
-}
-
-pub fn load_knxproj_bytes_with_language(
-    data: &[u8],
-    password: Option<&str>,
-    preferred_language: Option<&str>,
-) -> Result<KnxProjectData> {
-    log::info!("Loading KNX project from bytes ({} bytes)", data.len());
-    let cursor = Cursor::new(data);
-    load_knxproj_reader(cursor, password, preferred_language)
-}
-
-fn load_knxproj_reader<R: Read + Seek>(
-    reader: R,
-    password: Option<&str>,
-    preferred_language: Option<&str>,
-) -> Result<KnxProjectData> {
-    let mut zip = ZipArchive::new(reader).context("Failed to read .knxproj archive")?;
-
-    let zip_password = password.map(derive_zip_password);
-    if zip_password.is_some() {
-        log::info!("Derived zip password for encrypted project");
-    }
+    let progress = options.progress;
+    let zip_password = match password {
+        Some(password) => {
+            report(progress, ProgressEvent::DerivingKey)?;
+            let derived = derive_zip_password(password);
+            log::info!("Derived zip password for encrypted project");
+            Some(derived)
+        }
+        None => None,
+    };
 
-    let (project_xml, data_xml) = read_project_docs_any(&mut zip, zip_password.as_deref())?;
+    report(progress, ProgressEvent::LocatingDocuments)?;
+    let (project_xml, data_xml) = read_project_docs_any(archive, zip_password.as_deref())?;
 
     let project_doc =
         Document::parse(strip_bom(&project_xml)).context("Failed to parse project.xml")?;
+    let schema = schema_adapter(extract_schema_version(&project_doc))?;
     let data_doc = Document::parse(strip_bom(&data_xml)).context("Failed to parse 0.xml")?;
+    let data_index = XmlIndex::new(&data_doc);
 
-    let manufacturer_names = read_manufacturer_names(&mut zip)?;
+    let manufacturer_names = read_manufacturer_names(archive)?;
 
     let project_name = extract_project_name(&project_doc);
     let project_info = extract_project_info(&project_doc);
-    let (areas, lines) = extract_topology_metadata(&data_doc)?;
-    let group_address_style = project_info
-        .as_ref()
-        .and_then(|info| info.group_address_style.as_deref());
+    report(progress, ProgressEvent::Topology)?;
+    let (areas, lines) = extract_topology_metadata(&data_index)?;
+    let group_address_style = options.group_address_style.unwrap_or_else(|| {
+        project_info
+            .as_ref()
+            .and_then(|info| info.group_address_style.as_deref())
+            .map(parse_group_address_style)
+            .unwrap_or(GroupAddressStyle::ThreeLevel)
+    });
+    report(progress, ProgressEvent::GroupAddresses)?;
     let (mut group_addresses, group_address_by_id) =
-        extract_group_addresses(&data_doc, group_address_style)?;
+        extract_group_addresses(&data_index, group_address_style)?;
 
+    report(progress, ProgressEvent::Devices)?;
     let devices = extract_devices(
-        &data_doc,
-        &mut zip,
+        &data_index,
+        archive,
         &group_address_by_id,
         &manufacturer_names,
-        preferred_language,
+        schema,
+        options,
     )?;
 
     let mut inferred_dpts: HashMap<String, String> = HashMap::new();
@@ -135,7 +126,12 @@ fn load_knxproj_reader<R: Read + Seek>(
         .map(|device| (device.instance_id.clone(), (device.individual_address.clone(), device.name.clone())))
         .collect();
 
-    let locations = extract_locations(&data_doc, &device_index);
+    let locations = if options.skip_locations {
+        Vec::new()
+    } else {
+        report(progress, ProgressEvent::Locations)?;
+        extract_locations(&data_index, &device_index)
+    };
 
     let mut linked_devices: HashMap<String, Vec<String>> = HashMap::new();
     for device in &devices {
@@ -153,6 +149,14 @@ fn load_knxproj_reader<R: Read + Seek>(
         }
     }
 
+    report(
+        progress,
+        ProgressEvent::Done {
+            devices: devices.len(),
+            group_addresses: group_addresses.len(),
+        },
+    )?;
+
     Ok(KnxProjectData {
         project_name,
         project_info,
@@ -164,24 +168,14 @@ fn load_knxproj_reader<R: Read + Seek>(
     })
 }
 
-fn read_manufacturer_names<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-) -> Result<HashMap<String, String>> {
-    match read_zip_entry(zip, "knx_master.xml") {
-        Ok(xml) => {
-            let doc = Document::parse(strip_bom(&xml)).context("Failed to parse knx_master.xml")?;
-            Ok(extract_manufacturer_names(&doc))
-        }
-        Err(err) => {
-            if let Some(zip_err) = err.downcast_ref::<ZipError>() {
-                if matches!(*zip_err, ZipError::FileNotFound) {
-                    log::warn!("knx_master.xml not found in project");
-                    return Ok(HashMap::new());
-                }
-            }
-            Err(err)
-        }
+fn read_manufacturer_names(archive: &mut dyn ProjectArchive) -> Result<HashMap<String, String>> {
+    if !archive.contains("knx_master.xml") {
+        log::warn!("knx_master.xml not found in project");
+        return Ok(HashMap::new());
     }
+    let xml = archive.read_text("knx_master.xml", None)?;
+    let doc = Document::parse(strip_bom(&xml)).context("Failed to parse knx_master.xml")?;
+    Ok(extract_manufacturer_names(&doc))
 }
 
 fn extract_manufacturer_names(doc: &Document) -> HashMap<String, String> {
@@ -202,7 +196,7 @@ fn extract_manufacturer_names(doc: &Document) -> HashMap<String, String> {
     map
 }
 
-fn derive_zip_password(project_password: &str) -> String {
+pub(crate) fn derive_zip_password(project_password: &str) -> String {
     let mut password_bytes = Vec::with_capacity(project_password.len() * 2);
     for unit in project_password.encode_utf16() {
         password_bytes.extend_from_slice(&unit.to_le_bytes());
@@ -256,12 +250,13 @@ fn numeric_xml_index(name: &str) -> Option<u32> {
     base.parse().ok()
 }
 
-fn find_project_paths<R: Read + Seek>(zip: &ZipArchive<R>) -> Result<(String, String)> {
+fn find_project_paths(archive: &dyn ProjectArchive) -> Result<(String, String)> {
     let mut project_xml = None;
     let mut data_xml = None;
     let mut data_candidate: Option<(u32, String)> = None;
+    let names = archive.file_names();
 
-    for name in zip.file_names() {
+    for name in &names {
         if name.starts_with("P-") && name.ends_with("/project.xml") {
             project_xml = Some(name.to_string());
         } else if name.starts_with("P-") && name.ends_with("/0.xml") {
@@ -280,7 +275,7 @@ fn find_project_paths<R: Read + Seek>(zip: &ZipArchive<R>) -> Result<(String, St
     }
 
     if project_xml.is_none() || data_xml.is_none() {
-        for name in zip.file_names() {
+        for name in &names {
             if project_xml.is_none() && name.ends_with("project.xml") {
                 project_xml = Some(name.to_string());
             }
@@ -312,11 +307,19 @@ fn find_project_paths<R: Read + Seek>(zip: &ZipArchive<R>) -> Result<(String, St
     Ok((project_xml, data_xml))
 }
 
-fn find_project_paths_by_content<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
+/// Errors that end a load instead of skipping the entry: a wrong or missing password, or an
+/// archive over its limits.
+fn is_fatal_read_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<PasswordRequiredError>().is_some()
+        || err.downcast_ref::<InvalidPasswordError>().is_some()
+        || err.downcast_ref::<ArchiveLimitError>().is_some()
+}
+
+fn find_project_paths_by_content(
+    archive: &mut dyn ProjectArchive,
     password: Option<&str>,
 ) -> Result<(String, String)> {
-    let names: Vec<String> = zip.file_names().map(|name| name.to_string()).collect();
+    let names = archive.file_names();
     let mut project_xml = None;
     let mut data_xml = None;
 
@@ -328,12 +331,10 @@ fn find_project_paths_by_content<R: Read + Seek>(
             continue;
         }
 
-        let xml = match read_zip_entry_with_password(zip, &name, password) {
+        let xml = match archive.read_text(&name, password) {
             Ok(xml) => xml,
             Err(err) => {
-                if err.downcast_ref::<PasswordRequiredError>().is_some()
-                    || err.downcast_ref::<InvalidPasswordError>().is_some()
-                {
+                if is_fatal_read_error(&err) {
                     return Err(err);
                 }
                 log::warn!("Unable to read xml {} ({})", name, err);
@@ -350,17 +351,13 @@ fn find_project_paths_by_content<R: Read + Seek>(
         };
 
         match classify_project_doc(&doc) {
-            Some(ProjectDocKind::Project) => {
-                if project_xml.is_none() {
-                    project_xml = Some(name.clone());
-                }
+            Some(ProjectDocKind::Project) if project_xml.is_none() => {
+                project_xml = Some(name.clone());
             }
-            Some(ProjectDocKind::Data) => {
-                if data_xml.is_none() {
-                    data_xml = Some(name.clone());
-                }
+            Some(ProjectDocKind::Data) if data_xml.is_none() => {
+                data_xml = Some(name.clone());
             }
-            None => {}
+            _ => {}
         }
 
         if project_xml.is_some() && data_xml.is_some() {
@@ -374,140 +371,48 @@ fn find_project_paths_by_content<R: Read + Seek>(
     Ok((project_xml, data_xml))
 }
 
-fn read_zip_entry_with_password<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-    path: &str,
-    password: Option<&str>,
-) -> Result<String> {
-    log::debug!(
-        "Reading entry {} (password: {})",
-        path,
-        if password.is_some() { "yes" } else { "no" }
-    );
-    let mut file = if let Some(password) = password {
-        match zip.by_name_decrypt(path, password.as_bytes()) {
-            Ok(Ok(file)) => file,
-            Ok(Err(InvalidPassword)) => {
-                log::warn!("Invalid password for {}", path);
-                return Err(InvalidPasswordError.into());
-            }
-            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
-                log::warn!("Password required for {}", path);
-                return Err(PasswordRequiredError.into());
-            }
-            Err(err) => return Err(err.into()),
-        }
-    } else {
-        match zip.by_name(path) {
-            Ok(file) => file,
-            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
-                log::warn!("Password required for {}", path);
-                return Err(PasswordRequiredError.into());
-            }
-            Err(err) => return Err(err.into()),
-        }
-    };
-
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)
-        .with_context(|| format!("Failed to read {}", path))?;
-    Ok(contents)
-}
-
-fn read_zip_bytes_with_password<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-    path: &str,
-    password: Option<&str>,
-) -> Result<Vec<u8>> {
-    log::debug!(
-        "Reading zip bytes {} (password: {})",
-        path,
-        if password.is_some() { "yes" } else { "no" }
-    );
-    let mut file = if let Some(password) = password {
-        match zip.by_name_decrypt(path, password.as_bytes()) {
-            Ok(Ok(file)) => file,
-            Ok(Err(InvalidPassword)) => {
-                log::warn!("Invalid password for {}", path);
-                return Err(InvalidPasswordError.into());
-            }
-            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
-                log::warn!("Password required for {}", path);
-                return Err(PasswordRequiredError.into());
-            }
-            Err(err) => return Err(err.into()),
-        }
-    } else {
-        match zip.by_name(path) {
-            Ok(file) => file,
-            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
-                log::warn!("Password required for {}", path);
-                return Err(PasswordRequiredError.into());
-            }
-            Err(err) => return Err(err.into()),
-        }
-    };
-
-    let mut contents = Vec::new();
-    file.read_to_end(&mut contents)
-        .with_context(|| format!("Failed to read {}", path))?;
-    Ok(contents)
-}
-
-fn read_project_docs<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
+fn read_project_docs(
+    archive: &mut dyn ProjectArchive,
     password: Option<&str>,
 ) -> Result<(String, String)> {
-    let (project_xml_path, data_xml_path) = match find_project_paths(zip) {
+    let (project_xml_path, data_xml_path) = match find_project_paths(archive) {
         Ok(paths) => paths,
-        Err(_) => find_project_paths_by_content(zip, password)?,
+        Err(_) => find_project_paths_by_content(archive, password)?,
     };
     log::info!(
         "Project docs: project={}, data={}",
         project_xml_path,
         data_xml_path
     );
-    let project_xml = read_zip_entry_with_password(zip, &project_xml_path, password)?;
-    let data_xml = read_zip_entry_with_password(zip, &data_xml_path, password)?;
+    let project_xml = archive.read_text(&project_xml_path, password)?;
+    let data_xml = archive.read_text(&data_xml_path, password)?;
     Ok((project_xml, data_xml))
 }
 
-fn read_project_docs_any<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
+fn read_project_docs_any(
+    archive: &mut dyn ProjectArchive,
     password: Option<&str>,
 ) -> Result<(String, String)> {
-    match read_project_docs(zip, password) {
+    match read_project_docs(archive, password) {
         Ok(docs) => return Ok(docs),
         Err(err) => {
-            if err.downcast_ref::<PasswordRequiredError>().is_some()
-                || err.downcast_ref::<InvalidPasswordError>().is_some()
-            {
+            if is_fatal_read_error(&err) {
                 return Err(err);
             }
         }
     }
 
-    let names: Vec<String> = zip.file_names().map(|name| name.to_string()).collect();
-    for name in names {
+    for name in archive.file_names() {
         if !name.ends_with(".zip") {
             continue;
         }
         log::debug!("Scanning nested archive {}", name);
-        let nested_bytes = match read_zip_bytes_with_password(zip, &name, password) {
-            Ok(bytes) => bytes,
+        let mut nested_zip = match archive.open_nested(&name, password) {
+            Ok(zip) => zip,
             Err(err) => {
-                if err.downcast_ref::<PasswordRequiredError>().is_some()
-                    || err.downcast_ref::<InvalidPasswordError>().is_some()
-                {
+                if is_fatal_read_error(&err) {
                     return Err(err);
                 }
-                log::warn!("Unable to read nested zip {} ({})", name, err);
-                continue;
-            }
-        };
-        let mut nested_zip = match ZipArchive::new(Cursor::new(nested_bytes)) {
-            Ok(zip) => zip,
-            Err(err) => {
                 log::warn!("Unable to open nested zip {} ({})", name, err);
                 continue;
             }
@@ -518,9 +423,7 @@ fn read_project_docs_any<R: Read + Seek>(
                 return Ok(docs);
             }
             Err(err) => {
-                if err.downcast_ref::<PasswordRequiredError>().is_some()
-                    || err.downcast_ref::<InvalidPasswordError>().is_some()
-                {
+                if is_fatal_read_error(&err) {
                     return Err(err);
                 }
             }
@@ -531,14 +434,11 @@ fn read_project_docs_any<R: Read + Seek>(
 }
 
 fn extract_locations(
-    doc: &Document,
+    index: &XmlIndex,
     device_index: &HashMap<String, (String, String)>,
 ) -> Vec<BuildingSpace> {
     let mut roots = Vec::new();
-    for locations in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::LOCATIONS)
-    {
+    for locations in index.elements(xml_tags::LOCATIONS) {
         for space in locations
             .children()
             .filter(|n| n.is_element() && n.tag_name().name() == xml_tags::SPACE)
@@ -601,6 +501,76 @@ fn parse_space(
 #[cfg(test)]
 mod tests {
     use super::derive_zip_password;
+    use crate::knx::loader::ProjectLoader;
+    use crate::knx::progress::{ParseCancelledError, ProgressEvent, ProgressObserver};
+    use std::cell::RefCell;
+    use std::io::Write;
+
+    fn minimal_archive() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        zip.start_file("P-0001/project.xml", options).unwrap();
+        zip.write_all(br#"<KNX><Project Id="P-0001"><ProjectInformation Name="Mini"/></Project></KNX>"#)
+            .unwrap();
+        zip.start_file("P-0001/0.xml", options).unwrap();
+        zip.write_all(
+            br#"<KNX><Project Id="P-0001"><Installations><Installation><Topology>
+                <Area Id="A-1" Address="1"><Line Id="L-1" Address="1"/></Area>
+            </Topology></Installation></Installations></Project></KNX>"#,
+        )
+        .unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    struct CancelAfterTopology(RefCell<Vec<ProgressEvent>>);
+
+    impl ProgressObserver for CancelAfterTopology {
+        fn on_progress(&self, event: &ProgressEvent) {
+            self.0.borrow_mut().push(event.clone());
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.0.borrow().contains(&ProgressEvent::Topology)
+        }
+    }
+
+    #[test]
+    fn reports_phases_in_order() {
+        let events = RefCell::new(Vec::new());
+        let observer = |event: &ProgressEvent| events.borrow_mut().push(event.clone());
+        let archive = minimal_archive();
+        let project = ProjectLoader::from_bytes(&archive)
+            .progress(&observer)
+            .load()
+            .unwrap();
+        assert_eq!(project.project_name, "Mini");
+        assert_eq!(
+            events.into_inner(),
+            vec![
+                ProgressEvent::LocatingDocuments,
+                ProgressEvent::Topology,
+                ProgressEvent::GroupAddresses,
+                ProgressEvent::Devices,
+                ProgressEvent::Locations,
+                ProgressEvent::Done {
+                    devices: 0,
+                    group_addresses: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_when_observer_cancels() {
+        let observer = CancelAfterTopology(RefCell::new(Vec::new()));
+        let archive = minimal_archive();
+        let error = ProjectLoader::from_bytes(&archive)
+            .progress(&observer)
+            .load()
+            .unwrap_err();
+        assert!(error.downcast_ref::<ParseCancelledError>().is_some());
+        assert_eq!(observer.0.borrow().len(), 2);
+    }
 
     #[test]
     fn derive_zip_password_vectors() {
@@ -625,7 +595,7 @@ mod tests {
         }
 
         let password = Some("*Domoserv1");
-        let project = super::load_knxproj(path, password)?;
+        let project = ProjectLoader::from_path(path).password(password).load()?;
 
         println!("Project: {}", project.project_name);
 
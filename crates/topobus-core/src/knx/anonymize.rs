@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::net::Ipv4Addr;
+
+use anyhow::{Context, Result};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use roxmltree::Document;
+use sha2::{Digest, Sha256};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::knx::adapter::derive_zip_password;
+use crate::knx::archive::{LimitedZip, ProjectArchive};
+use crate::knx::limits::ArchiveLimits;
+use crate::knx::zip_utils::strip_bom;
+
+/// Copy of a `.knxproj` that can leave the customer, e.g. attached to a parser bug report.
+///
+/// In the project files, names, comments, descriptions, serial numbers, MAC and IP addresses,
+/// building parts and keys are replaced with pseudonyms. The same value always gets the same
+/// pseudonym, and keys and serial numbers keep their encoding and length. IDs, addresses,
+/// datapoint types, flags, links and product references are kept, as are the manufacturer files,
+/// so the copy parses like the original.
+///
+/// A password-protected project is written without protection. Project files that are not XML,
+/// like attachments, are left out.
+pub fn anonymize_knxproj(data: &[u8], password: Option<&str>) -> Result<Vec<u8>> {
+    let zip_password = password.map(derive_zip_password);
+    let mut archive = LimitedZip::open(Cursor::new(data), ArchiveLimits::default())?;
+    let mut names = archive.file_names();
+    names.sort();
+
+    let mut pseudonyms = Pseudonyms::default();
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for name in names.iter().filter(|name| !name.ends_with('/')) {
+        if !is_project_entry(name) {
+            let contents = archive.read_bytes(name, None)?;
+            write_entry(&mut writer, name, &contents)?;
+            continue;
+        }
+        let Some(folder) = name.strip_suffix(".zip") else {
+            let contents = archive.read_bytes(name, zip_password.as_deref())?;
+            anonymize_entry(&mut writer, &mut pseudonyms, name, &contents)?;
+            continue;
+        };
+        let mut nested = archive.open_nested(name, zip_password.as_deref())?;
+        let mut nested_names = nested.file_names();
+        nested_names.sort();
+        for nested_name in nested_names.iter().filter(|name| !name.ends_with('/')) {
+            let contents = nested.read_bytes(nested_name, zip_password.as_deref())?;
+            let path = if nested_name.starts_with(&format!("{}/", folder)) {
+                nested_name.clone()
+            } else {
+                format!("{}/{}", folder, nested_name)
+            };
+            anonymize_entry(&mut writer, &mut pseudonyms, &path, &contents)?;
+        }
+    }
+    let output = writer.finish().context("Failed to write anonymized archive")?;
+    Ok(output.into_inner())
+}
+
+/// Project files live in `P-xxxx/` or, for protected projects, in `P-xxxx.zip`.
+fn is_project_entry(name: &str) -> bool {
+    name.split('/').next().is_some_and(|top| top.starts_with("P-"))
+}
+
+fn anonymize_entry(
+    writer: &mut ZipWriter<Cursor<Vec<u8>>>,
+    pseudonyms: &mut Pseudonyms,
+    name: &str,
+    contents: &[u8],
+) -> Result<()> {
+    if !name.to_ascii_lowercase().ends_with(".xml") {
+        log::info!("Leaving {} out of the anonymized project", name);
+        return Ok(());
+    }
+    let xml = std::str::from_utf8(contents).with_context(|| format!("{} is not valid UTF-8", name))?;
+    let anonymized = anonymize_xml(xml, pseudonyms).with_context(|| format!("Failed to parse {}", name))?;
+    write_entry(writer, name, anonymized.as_bytes())
+}
+
+fn write_entry(writer: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, contents: &[u8]) -> Result<()> {
+    // A fixed timestamp keeps the output identical from one run to the next.
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+    writer
+        .start_file(name, options)
+        .with_context(|| format!("Failed to write {}", name))?;
+    writer
+        .write_all(contents)
+        .with_context(|| format!("Failed to write {}", name))
+}
+
+/// Replace the sensitive attribute values and texts of one project document.
+fn anonymize_xml(xml: &str, pseudonyms: &mut Pseudonyms) -> Result<String> {
+    let body = strip_bom(xml);
+    let doc = Document::parse(body)?;
+    let mut replacements = Vec::new();
+    for node in doc.descendants() {
+        if node.is_text() {
+            let text = node.text().unwrap_or("");
+            if !text.trim().is_empty() {
+                replacements.push((node.range(), pseudonyms.get(&Field::Text("Text".into()), text)));
+            }
+            continue;
+        }
+        if !node.is_element() {
+            continue;
+        }
+        for attribute in node.attributes() {
+            let Some(field) = classify(node.tag_name().name(), attribute.name()) else {
+                continue;
+            };
+            if attribute.value().is_empty() {
+                continue;
+            }
+            replacements.push((attribute.range_value(), pseudonyms.get(&field, attribute.value())));
+        }
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start);
+    let mut output = String::with_capacity(xml.len());
+    output.push_str(&xml[..xml.len() - body.len()]);
+    let mut position = 0;
+    for (range, pseudonym) in replacements {
+        output.push_str(&body[position..range.start]);
+        output.push_str(&pseudonym);
+        position = range.end;
+    }
+    output.push_str(&body[position..]);
+    Ok(output)
+}
+
+/// What a project attribute holds, as far as anonymizing goes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Field {
+    /// Free text, replaced with a numbered label such as `GroupAddress 4`.
+    Text(String),
+    /// Keys, passwords, serial and MAC addresses, replaced with random-looking values of the
+    /// same shape.
+    Opaque(String),
+    Ip,
+}
+
+fn classify(element: &str, attribute: &str) -> Option<Field> {
+    let field = match attribute {
+        "Name" => Field::Text(element.to_string()),
+        "Text" | "Comment" | "Description" | "FunctionText" | "InstallationHints" | "User"
+        | "Detail" | "Filename" | "ProjectNumber" | "ContractNumber" => Field::Text(attribute.to_string()),
+        "Number" if matches!(element, "Space" | "Function") => Field::Text(attribute.to_string()),
+        "IPAddress" | "DefaultGateway" => Field::Ip,
+        "SerialNumber" | "MACAddress" => Field::Opaque(attribute.to_string()),
+        // One kind for all secrets: a key reused across attributes stays recognisable.
+        _ if attribute.ends_with("Key")
+            || attribute.contains("Password")
+            || attribute.contains("AuthenticationCode") =>
+        {
+            Field::Opaque("Key".to_string())
+        }
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Pseudonyms handed out so far, so that equal values stay equal after anonymizing.
+#[derive(Default)]
+struct Pseudonyms {
+    assigned: HashMap<(Field, String), String>,
+    counts: HashMap<Field, usize>,
+}
+
+impl Pseudonyms {
+    fn get(&mut self, field: &Field, value: &str) -> String {
+        let key = (field.clone(), value.to_string());
+        if let Some(pseudonym) = self.assigned.get(&key) {
+            return pseudonym.clone();
+        }
+        let count = self.counts.entry(field.clone()).or_default();
+        *count += 1;
+        let pseudonym = match field {
+            Field::Text(label) => format!("{} {}", label, count),
+            Field::Ip => match value.parse::<Ipv4Addr>() {
+                Ok(_) => Ipv4Addr::from(0x0a00_0000 | (*count as u32 & 0x00ff_ffff)).to_string(),
+                Err(_) => disguise(value, &format!("IP {}", count)),
+            },
+            Field::Opaque(kind) => disguise(value, &format!("{} {}", kind, count)),
+        };
+        self.assigned.insert(key, pseudonym.clone());
+        pseudonym
+    }
+}
+
+/// A value shaped like `value`, derived from `seed` only: hex digits stay hex digits, base64
+/// stays base64 of the same length, other letters and digits stay letters and digits.
+fn disguise(value: &str, seed: &str) -> String {
+    let mut bytes = DerivedBytes::new(seed);
+    if !value.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        if let Ok(decoded) = BASE64_STANDARD.decode(value) {
+            let replacement: Vec<u8> = (0..decoded.len()).map(|_| bytes.next()).collect();
+            return BASE64_STANDARD.encode(replacement);
+        }
+    }
+    value
+        .chars()
+        .map(|ch| {
+            const HEX: &[u8] = b"0123456789abcdef";
+            const ALPHANUMERIC: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ0123456789";
+            let byte = bytes.next() as usize;
+            if ch.is_ascii_hexdigit() {
+                let digit = HEX[byte % 16] as char;
+                if ch.is_ascii_uppercase() {
+                    digit.to_ascii_uppercase()
+                } else {
+                    digit
+                }
+            } else if ch.is_ascii_alphanumeric() {
+                ALPHANUMERIC[byte % ALPHANUMERIC.len()] as char
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+/// Endless SHA-256 stream over a seed.
+struct DerivedBytes {
+    seed: String,
+    block: Vec<u8>,
+    counter: u32,
+}
+
+impl DerivedBytes {
+    fn new(seed: &str) -> Self {
+        Self {
+            seed: format!("topobus-anonymize\0{}", seed),
+            block: Vec::new(),
+            counter: 0,
+        }
+    }
+
+    fn next(&mut self) -> u8 {
+        if self.block.is_empty() {
+            let mut hasher = Sha256::new();
+            hasher.update(self.seed.as_bytes());
+            hasher.update(self.counter.to_le_bytes());
+            self.counter += 1;
+            self.block = hasher.finalize().to_vec();
+        }
+        self.block.pop().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::anonymize_knxproj;
+    use crate::knx::loader::ProjectLoader;
+    use std::io::Write;
+
+    const PROJECT_XML: &str = "\u{feff}<KNX xmlns=\"http://knx.org/xml/project/21\"><Project Id=\"P-0001\">\
+        <ProjectInformation Name=\"Villa Meier\" Comment=\"Call Mrs. Meier &amp; son\" \
+        ProjectTracingPassword=\"hunter2\" GroupAddressStyle=\"ThreeLevel\"/></Project></KNX>";
+    const DATA_XML: &str = r#"<KNX xmlns="http://knx.org/xml/project/21"><Project Id="P-0001"><Installations>
+        <Installation Name="" BCUKey="4142434445464748">
+        <Topology><Area Id="P-0001-0_A-1" Address="1" Name="House"><Line Id="P-0001-0_L-1" Address="1" Name="Ground floor">
+            <DeviceInstances>
+                <DeviceInstance Id="P-0001-0_DI-1" Name="Kitchen actuator" Address="1" SerialNumber="AIMAAAAA"
+                    ProductRefId="M-0083_H-1-1_P-1" Description="Behind the fridge">
+                    <IPConfig IPAddress="192.168.1.20" MACAddress="00:24:6D:01:02:03"/>
+                    <Security ToolKey="c2VjcmV0a2V5MTIzNDU2Nw==" DeviceAuthenticationCode="hunter2"/>
+                    <ComObjectInstanceRefs>
+                        <ComObjectInstanceRef RefId="O-1_R-1" Text="Kitchen" Links="GA-1"/>
+                    </ComObjectInstanceRefs>
+                </DeviceInstance>
+            </DeviceInstances>
+        </Line></Area></Topology>
+        <GroupAddresses><GroupRanges><GroupRange Id="P-0001-0_GR-1" Name="Kitchen" RangeStart="2048" RangeEnd="2303">
+            <GroupAddress Id="P-0001-0_GA-1" Address="2049" Name="Kitchen" DatapointType="DPST-1-1"
+                Key="c2VjcmV0a2V5MTIzNDU2Nw=="/>
+        </GroupRange></GroupRanges></GroupAddresses>
+        <Locations><Space Id="P-0001-0_BP-1" Type="Room" Name="Kitchen" Number="R.012"/></Locations>
+        </Installation></Installations></Project></KNX>"#;
+
+    fn archive() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        for (name, contents) in [
+            ("P-0001/project.xml", PROJECT_XML),
+            ("P-0001/0.xml", DATA_XML),
+            ("P-0001/Attachments/contract.txt", "Villa Meier"),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn entry(archive: &[u8], name: &str) -> String {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name(name).unwrap(), &mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn replaces_personal_data_and_keeps_structure() {
+        let anonymized = anonymize_knxproj(&archive(), None).unwrap();
+        let data = entry(&anonymized, "P-0001/0.xml");
+        let project = entry(&anonymized, "P-0001/project.xml");
+        let everything = format!("{}{}", project, data);
+        for secret in ["Meier", "Kitchen", "fridge", "hunter2", "AIMAAAAA", "192.168.1.20", "00:24:6D"] {
+            assert!(!everything.contains(secret), "{} left in output", secret);
+        }
+        assert!(!everything.contains("c2VjcmV0a2V5MTIzNDU2Nw=="));
+        assert!(project.starts_with('\u{feff}'));
+        assert!(project.contains(r#"Name="ProjectInformation 1""#));
+        assert!(data.contains(r#"Name="GroupRange 1""#));
+        assert!(data.contains(r#"Number="Number 1""#));
+        assert!(data.contains(r#"IPAddress="10.0.0.1""#));
+        // The device's tool key and the group address key are equal, so they stay equal.
+        let key = data.split("ToolKey=\"").nth(1).unwrap().split('"').next().unwrap();
+        assert_eq!(key.len(), 24);
+        assert!(data.contains(&format!(r#" Key="{}""#, key)));
+        let bcu_key = data.split("BCUKey=\"").nth(1).unwrap().split('"').next().unwrap();
+        assert!(bcu_key.len() == 16 && bcu_key.chars().all(|ch| ch.is_ascii_hexdigit()));
+        assert!(data.contains(r#"MACAddress=""#));
+        assert!(zip::ZipArchive::new(std::io::Cursor::new(&anonymized))
+            .unwrap()
+            .by_name("P-0001/Attachments/contract.txt")
+            .is_err());
+
+        let original = ProjectLoader::from_bytes(&archive()).load().unwrap();
+        let copy = ProjectLoader::from_bytes(&anonymized).load().unwrap();
+        assert_eq!(copy.devices[0].individual_address, original.devices[0].individual_address);
+        assert_eq!(copy.group_addresses[0].address, original.group_addresses[0].address);
+        assert_eq!(
+            copy.group_addresses[0].datapoint_type,
+            original.group_addresses[0].datapoint_type
+        );
+        assert_eq!(copy.devices[0].name, "DeviceInstance 1");
+
+        assert_eq!(anonymize_knxproj(&archive(), None).unwrap(), anonymized);
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+
+use crate::knx::app_model::AppProgram;
+use crate::knx::fingerprint::sha256_hex;
+use crate::knx::parsers::app_program::parse_app_program;
+
+/// Parsed application programs shared across project loads.
+///
+/// ETS projects from the same installer tend to embed the same application programs, which are
+/// the most expensive part of a load. Entries are keyed by application ID, preferred language and
+/// SHA-256 of the program XML, so a vendor update shipped under the same ID is never confused with
+/// the old one. Beyond `max_entries` the least recently used program is dropped.
+pub struct AppProgramStore {
+    max_entries: usize,
+    inner: Mutex<StoreState>,
+}
+
+#[derive(Default)]
+struct StoreState {
+    entries: HashMap<StoreKey, StoreEntry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct StoreKey {
+    app_id: String,
+    language: Option<String>,
+    content_hash: String,
+}
+
+struct StoreEntry {
+    program: Arc<AppProgram>,
+    last_used: u64,
+}
+
+/// Counters describing how well an [`AppProgramStore`] is doing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppProgramStoreStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl AppProgramStore {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            inner: Mutex::new(StoreState::default()),
+        }
+    }
+
+    pub fn stats(&self) -> AppProgramStoreStats {
+        let state = self.lock();
+        AppProgramStoreStats {
+            entries: state.entries.len(),
+            hits: state.hits,
+            misses: state.misses,
+        }
+    }
+
+    /// Return the cached program for `xml`, parsing and remembering it on a miss.
+    pub(crate) fn get_or_parse(
+        &self,
+        app_id: &str,
+        preferred_language: Option<&str>,
+        xml: &str,
+    ) -> Result<Arc<AppProgram>> {
+        let key = StoreKey {
+            app_id: app_id.to_string(),
+            language: preferred_language.map(str::to_string),
+            content_hash: sha256_hex(xml.as_bytes()),
+        };
+        {
+            let mut state = self.lock();
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(&key) {
+                entry.last_used = tick;
+                let program = entry.program.clone();
+                state.hits += 1;
+                return Ok(program);
+            }
+            state.misses += 1;
+        }
+
+        // Parse without holding the lock so concurrent loads of other programs proceed.
+        let program = Arc::new(parse_app_program(xml, app_id, preferred_language)?);
+
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        if !state.entries.contains_key(&key) {
+            while state.entries.len() >= self.max_entries {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                match oldest {
+                    Some(oldest) => {
+                        state.entries.remove(&oldest);
+                    }
+                    None => break,
+                }
+            }
+        }
+        state.entries.insert(
+            key,
+            StoreEntry {
+                program: program.clone(),
+                last_used: tick,
+            },
+        );
+        Ok(program)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StoreState> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppProgramStore;
+    use std::sync::Arc;
+
+    const APP: &str = r#"<KNX><ManufacturerData><Manufacturer><ApplicationPrograms>
+        <ApplicationProgram Id="M-0001_A-0001-01-0000" Name="Dimmer" ApplicationVersion="1"/>
+        </ApplicationPrograms></Manufacturer></ManufacturerData></KNX>"#;
+
+    #[test]
+    fn reuses_programs_with_identical_content() {
+        let store = AppProgramStore::new(4);
+        let first = store.get_or_parse("M-0001_A-0001-01-0000", None, APP).unwrap();
+        let second = store.get_or_parse("M-0001_A-0001-01-0000", None, APP).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.name.as_deref(), Some("Dimmer"));
+
+        let updated = APP.replace("Dimmer", "Dimmer v2");
+        let third = store
+            .get_or_parse("M-0001_A-0001-01-0000", None, &updated)
+            .unwrap();
+        assert!(!Arc::ptr_eq(&first, &third));
+
+        let stats = store.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 1, 2));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_program() {
+        let store = AppProgramStore::new(1);
+        store.get_or_parse("M-0001_A-0001-01-0000", None, APP).unwrap();
+        store
+            .get_or_parse("M-0001_A-0001-01-0000", Some("de-DE"), APP)
+            .unwrap();
+        assert_eq!(store.stats().entries, 1);
+    }
+}
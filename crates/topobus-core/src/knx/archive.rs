@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use zip::result::{InvalidPassword, ZipError};
+use zip::ZipArchive;
+
+use crate::knx::adapter::{InvalidPasswordError, PasswordRequiredError};
+use crate::knx::limits::{is_safe_entry_name, ArchiveLimitError, ArchiveLimits, ReadBudget};
+
+/// Capacity reserved up front for an entry, whatever size it claims to have.
+const INITIAL_BUFFER: u64 = 1024 * 1024;
+
+/// Read access to the files of a project, zipped or unpacked.
+///
+/// Paths use `/` separators and are relative to the archive root, as in `.knxproj` files.
+pub(crate) trait ProjectArchive {
+    fn file_names(&self) -> Vec<String>;
+
+    /// Read an entry, decrypting it with `password` when one is given.
+    fn read_bytes(&mut self, path: &str, password: Option<&str>) -> Result<Vec<u8>>;
+
+    fn contains(&self, path: &str) -> bool {
+        self.file_names().iter().any(|name| name == path)
+    }
+
+    fn read_text(&mut self, path: &str, password: Option<&str>) -> Result<String> {
+        let bytes = self.read_bytes(path, password)?;
+        String::from_utf8(bytes).with_context(|| format!("{} is not valid UTF-8", path))
+    }
+
+    /// Open a zip stored in this archive, e.g. the `P-xxxx.zip` of a protected project.
+    fn open_nested(&mut self, path: &str, password: Option<&str>) -> Result<LimitedZip<Cursor<Vec<u8>>>>;
+}
+
+/// A zip archive read within [`ArchiveLimits`].
+///
+/// Entry names are checked when the archive is opened, sizes while entries are decompressed,
+/// so a declared size in the central directory cannot understate what is actually inflated.
+pub(crate) struct LimitedZip<R> {
+    zip: ZipArchive<R>,
+    budget: Arc<ReadBudget>,
+    depth: usize,
+}
+
+impl<R: Read + Seek> LimitedZip<R> {
+    pub(crate) fn open(reader: R, limits: ArchiveLimits) -> Result<Self> {
+        Self::open_within(reader, Arc::new(ReadBudget::new(limits)), 0)
+    }
+
+    fn open_within(reader: R, budget: Arc<ReadBudget>, depth: usize) -> Result<Self> {
+        let zip = ZipArchive::new(reader).context("Failed to read .knxproj archive")?;
+        budget.check_entries(zip.len())?;
+        if let Some(name) = zip.file_names().find(|name| !is_safe_entry_name(name)) {
+            return Err(ArchiveLimitError::UnsafePath {
+                name: name.to_string(),
+            }
+            .into());
+        }
+        Ok(Self { zip, budget, depth })
+    }
+
+    /// The underlying archive, for reading entry headers. Contents go through `read_bytes`.
+    pub(crate) fn headers(&mut self) -> &mut ZipArchive<R> {
+        &mut self.zip
+    }
+}
+
+/// Open `bytes`, read from `name`, as an archive one level below `depth`.
+fn open_nested_zip(
+    name: &str,
+    bytes: Vec<u8>,
+    budget: &Arc<ReadBudget>,
+    depth: usize,
+) -> Result<LimitedZip<Cursor<Vec<u8>>>> {
+    budget.enter_nested(name, depth + 1)?;
+    LimitedZip::open_within(Cursor::new(bytes), budget.clone(), depth + 1)
+        .with_context(|| format!("Failed to read nested archive {}", name))
+}
+
+impl<R: Read + Seek> ProjectArchive for LimitedZip<R> {
+    fn file_names(&self) -> Vec<String> {
+        self.zip.file_names().map(str::to_string).collect()
+    }
+
+    fn read_bytes(&mut self, path: &str, password: Option<&str>) -> Result<Vec<u8>> {
+        log::debug!(
+            "Reading entry {} (password: {})",
+            path,
+            if password.is_some() { "yes" } else { "no" }
+        );
+        let mut file = if let Some(password) = password {
+            match self.zip.by_name_decrypt(path, password.as_bytes()) {
+                Ok(Ok(file)) => file,
+                Ok(Err(InvalidPassword)) => {
+                    log::warn!("Invalid password for {}", path);
+                    return Err(InvalidPasswordError.into());
+                }
+                Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
+                    log::warn!("Password required for {}", path);
+                    return Err(PasswordRequiredError.into());
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Missing file in .knxproj: {}", path))
+                }
+            }
+        } else {
+            match self.zip.by_name(path) {
+                Ok(file) => file,
+                Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED => {
+                    log::warn!("Password required for {}", path);
+                    return Err(PasswordRequiredError.into());
+                }
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Missing file in .knxproj: {}", path))
+                }
+            }
+        };
+
+        let limits = self.budget.limits;
+        if file.size() > limits.max_entry_size {
+            return Err(ArchiveLimitError::EntryTooLarge {
+                name: path.to_string(),
+                max: limits.max_entry_size,
+            }
+            .into());
+        }
+        let compressed = file.compressed_size();
+        // The declared size comes from the archive itself; let the buffer grow past this instead.
+        let mut contents = Vec::with_capacity(file.size().min(INITIAL_BUFFER) as usize);
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to read {}", path))?;
+            if read == 0 {
+                break;
+            }
+            contents.extend_from_slice(&buffer[..read]);
+            self.budget
+                .charge(path, read as u64, contents.len() as u64, compressed)?;
+        }
+        Ok(contents)
+    }
+
+    fn open_nested(&mut self, path: &str, password: Option<&str>) -> Result<LimitedZip<Cursor<Vec<u8>>>> {
+        let bytes = self.read_bytes(path, password)?;
+        open_nested_zip(path, bytes, &self.budget, self.depth)
+    }
+}
+
+/// A `.knxproj` extracted to a directory, e.g. for projects kept under version control.
+pub(crate) struct DirectoryArchive {
+    root: PathBuf,
+    names: Vec<String>,
+    budget: Arc<ReadBudget>,
+}
+
+impl DirectoryArchive {
+    pub(crate) fn open(root: &Path, limits: ArchiveLimits) -> Result<Self> {
+        let mut names = Vec::new();
+        collect_file_names(root, "", &mut names)
+            .with_context(|| format!("Failed to list project directory {}", root.display()))?;
+        let budget = Arc::new(ReadBudget::new(limits));
+        budget.check_entries(names.len())?;
+        names.sort();
+        Ok(Self {
+            root: root.to_path_buf(),
+            names,
+            budget,
+        })
+    }
+}
+
+fn collect_file_names(dir: &Path, prefix: &str, names: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_file_names(&entry.path(), &format!("{}{}/", prefix, name), names)?;
+        } else if file_type.is_file() {
+            names.push(format!("{}{}", prefix, name));
+        }
+    }
+    Ok(())
+}
+
+impl ProjectArchive for DirectoryArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        self.names.binary_search_by(|name| name.as_str().cmp(path)).is_ok()
+    }
+
+    fn read_bytes(&mut self, path: &str, _password: Option<&str>) -> Result<Vec<u8>> {
+        // Entry names are derived from IDs inside the project; never let them leave the root.
+        let relative = Path::new(path);
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow!("Refusing to read {} outside the project directory", path));
+        }
+        let full_path = self.root.join(relative);
+        let file = fs::File::open(&full_path).with_context(|| format!("Missing file in project directory: {}", path))?;
+        let declared = file
+            .metadata()
+            .with_context(|| format!("Failed to read {}", path))?
+            .len();
+        self.budget.check_size(path, declared)?;
+        // The file may grow after the check; never read more than one byte past the limit.
+        let mut contents = Vec::with_capacity(declared.min(INITIAL_BUFFER) as usize);
+        file.take(self.budget.limits.max_entry_size.saturating_add(1))
+            .read_to_end(&mut contents)
+            .with_context(|| format!("Failed to read {}", path))?;
+        let size = contents.len() as u64;
+        self.budget.charge(path, size, size, size)?;
+        Ok(contents)
+    }
+
+    fn open_nested(&mut self, path: &str, password: Option<&str>) -> Result<LimitedZip<Cursor<Vec<u8>>>> {
+        let bytes = self.read_bytes(path, password)?;
+        open_nested_zip(path, bytes, &self.budget, 0)
+    }
+}
+
+/// Project files held in memory, e.g. received one by one from a browser or another tool.
+pub(crate) struct MemoryArchive {
+    files: BTreeMap<String, Vec<u8>>,
+    budget: Arc<ReadBudget>,
+}
+
+impl MemoryArchive {
+    pub(crate) fn new(files: impl IntoIterator<Item = (String, Vec<u8>)>, limits: ArchiveLimits) -> Result<Self> {
+        let files = files
+            .into_iter()
+            .map(|(name, contents)| {
+                let name = name.replace('\\', "/");
+                (name.trim_start_matches("./").trim_start_matches('/').to_string(), contents)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let budget = Arc::new(ReadBudget::new(limits));
+        budget.check_entries(files.len())?;
+        Ok(Self { files, budget })
+    }
+}
+
+impl ProjectArchive for MemoryArchive {
+    fn file_names(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn read_bytes(&mut self, path: &str, _password: Option<&str>) -> Result<Vec<u8>> {
+        let contents = self
+            .files
+            .get(path)
+            .ok_or_else(|| anyhow!("Missing file in project: {}", path))?;
+        let size = contents.len() as u64;
+        self.budget.charge(path, size, size, size)?;
+        Ok(contents.clone())
+    }
+
+    fn open_nested(&mut self, path: &str, password: Option<&str>) -> Result<LimitedZip<Cursor<Vec<u8>>>> {
+        let bytes = self.read_bytes(path, password)?;
+        open_nested_zip(path, bytes, &self.budget, 0)
+    }
+}
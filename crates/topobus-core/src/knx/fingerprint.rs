@@ -0,0 +1,61 @@
+use sha2::{Digest, Sha256};
+
+use crate::knx::adapter::derive_zip_password;
+
+/// Lowercase hex SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Cache key identifying the result of loading `data` with the given password and language.
+///
+/// The password only enters the key through the PBKDF2 key derived from it, and that key is
+/// hashed together with the archive digest, so the key reveals neither.
+pub fn project_cache_key(
+    data: &[u8],
+    password: Option<&str>,
+    preferred_language: Option<&str>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"topobus-project-v1\0");
+    hasher.update(Sha256::digest(data));
+    match password {
+        Some(password) => {
+            hasher.update([1]);
+            hasher.update(derive_zip_password(password).as_bytes());
+        }
+        None => hasher.update([0]),
+    }
+    hasher.update([0]);
+    hasher.update(preferred_language.unwrap_or("").as_bytes());
+    to_hex(&hasher.finalize())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project_cache_key, sha256_hex};
+
+    #[test]
+    fn hashes_known_vector() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn key_depends_on_password_and_language() {
+        let data = b"archive";
+        let plain = project_cache_key(data, None, None);
+        let secret = project_cache_key(data, Some("secret"), None);
+        assert_ne!(plain, secret);
+        assert_ne!(secret, project_cache_key(data, Some("other"), None));
+        assert_ne!(plain, project_cache_key(data, None, Some("fr-FR")));
+        assert_eq!(secret, project_cache_key(data, Some("secret"), None));
+        assert!(!secret.contains("secret"));
+    }
+}
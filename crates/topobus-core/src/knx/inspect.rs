@@ -0,0 +1,217 @@
+use std::collections::BTreeSet;
+use std::io::{Cursor, Read, Seek};
+
+use anyhow::{Context, Result};
+use roxmltree::Document;
+use serde::Serialize;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::knx::archive::{LimitedZip, ProjectArchive};
+use crate::knx::limits::{ArchiveLimitError, ArchiveLimits};
+use crate::knx::parsers::project::extract_schema_version;
+use crate::knx::xml_tags;
+use crate::knx::xml_utils::attr_value;
+use crate::knx::zip_utils::strip_bom;
+
+/// What can be learned about a `.knxproj` without the password and without reading `0.xml`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectSummary {
+    pub project_id: Option<String>,
+    /// `None` when `project.xml` is encrypted.
+    pub project_name: Option<String>,
+    /// Version of the project schema, e.g. 20 for ETS 5.7 and 21 for ETS 6.
+    pub schema_version: Option<u32>,
+    pub created_by: Option<String>,
+    pub tool_version: Option<String>,
+    pub password_required: bool,
+    pub entries: Vec<ArchiveEntry>,
+    /// Manufacturer IDs with product data in the archive, e.g. `M-0083`.
+    pub manufacturers: Vec<String>,
+    /// Files attached to the project in ETS.
+    pub attachments: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    /// Path in the archive; entries of a nested project archive are prefixed with its name.
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    pub encrypted: bool,
+}
+
+/// Summarise a `.knxproj` from its archive directory and `project.xml`.
+///
+/// This is cheap enough to run before a load, e.g. to ask for a password up front.
+pub fn inspect_knxproj(data: &[u8]) -> Result<ProjectSummary> {
+    let mut archive = LimitedZip::open(Cursor::new(data), ArchiveLimits::default())?;
+    let mut summary = ProjectSummary {
+        entries: list_entries(archive.headers(), "")?,
+        ..ProjectSummary::default()
+    };
+
+    let manufacturers: BTreeSet<&str> = summary
+        .entries
+        .iter()
+        .filter_map(|entry| entry.name.split_once('/'))
+        .map(|(top, _)| top)
+        .filter(|top| top.starts_with("M-"))
+        .collect();
+    summary.manufacturers = manufacturers.into_iter().map(str::to_string).collect();
+
+    let mut project_xml = read_plain(&mut archive, &summary.entries, "");
+    let nested: Vec<String> = summary
+        .entries
+        .iter()
+        .filter(|entry| entry.name.starts_with("P-") && entry.name.ends_with(".zip"))
+        .map(|entry| entry.name.clone())
+        .collect();
+    for name in nested {
+        let mut nested_zip = match archive.open_nested(&name, None) {
+            Ok(zip) => zip,
+            Err(err) if err.downcast_ref::<ArchiveLimitError>().is_some() => return Err(err),
+            Err(err) => {
+                log::warn!("Unable to open nested zip {} ({})", name, err);
+                continue;
+            }
+        };
+        let prefix = format!("{}/", name);
+        let nested_entries = list_entries(nested_zip.headers(), &prefix)?;
+        if project_xml.is_none() {
+            project_xml = read_plain(&mut nested_zip, &nested_entries, &prefix);
+        }
+        summary.entries.extend(nested_entries);
+    }
+    summary.password_required = summary.entries.iter().any(|entry| entry.encrypted);
+
+    if let Some(xml) = project_xml {
+        let doc = Document::parse(strip_bom(&xml)).context("Failed to parse project.xml")?;
+        summarise_project_xml(&doc, &mut summary);
+    }
+    Ok(summary)
+}
+
+fn list_entries<R: Read + Seek>(archive: &mut ZipArchive<R>, prefix: &str) -> Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let (name, size, compressed_size) = {
+            let file = archive.by_index_raw(index)?;
+            if file.is_dir() {
+                continue;
+            }
+            (file.name().to_string(), file.size(), file.compressed_size())
+        };
+        // Opening without a password only reads the local header; it fails for encrypted entries.
+        let encrypted = matches!(
+            archive.by_index(index),
+            Err(ZipError::UnsupportedArchive(msg)) if msg == ZipError::PASSWORD_REQUIRED
+        );
+        entries.push(ArchiveEntry {
+            name: format!("{}{}", prefix, name),
+            size,
+            compressed_size,
+            encrypted,
+        });
+    }
+    Ok(entries)
+}
+
+/// Read `project.xml` when the archive holds an unencrypted copy.
+fn read_plain(
+    archive: &mut dyn ProjectArchive,
+    entries: &[ArchiveEntry],
+    prefix: &str,
+) -> Option<String> {
+    let entry = entries
+        .iter()
+        .find(|entry| entry.name.ends_with("project.xml") && !entry.encrypted)?;
+    let name = &entry.name[prefix.len()..];
+    match archive.read_bytes(name, None).map(String::from_utf8) {
+        Ok(Ok(xml)) => Some(xml),
+        Ok(Err(err)) => {
+            log::warn!("{} is not valid UTF-8 ({})", entry.name, err);
+            None
+        }
+        Err(err) => {
+            log::warn!("Unable to read {} ({})", entry.name, err);
+            None
+        }
+    }
+}
+
+fn summarise_project_xml(doc: &Document, summary: &mut ProjectSummary) {
+    let root = doc.root_element();
+    summary.schema_version = extract_schema_version(doc);
+    summary.created_by = attr_value(&root, "CreatedBy");
+    summary.tool_version = attr_value(&root, "ToolVersion");
+    for node in doc.descendants().filter(|node| node.is_element()) {
+        match node.tag_name().name() {
+            "Project" if summary.project_id.is_none() => {
+                summary.project_id = attr_value(&node, "Id");
+            }
+            xml_tags::PROJECT_INFORMATION if summary.project_name.is_none() => {
+                summary.project_name = attr_value(&node, "Name");
+            }
+            xml_tags::USER_FILE => {
+                if let Some(filename) = attr_value(&node, "Filename") {
+                    summary.attachments.push(filename);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inspect_knxproj;
+    use std::io::Write;
+
+    const PROJECT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<KNX xmlns="http://knx.org/xml/project/21" CreatedBy="ETS6" ToolVersion="6.1.0.5618">
+  <Project Id="P-0A1B">
+    <ProjectInformation Name="Maison">
+      <UserFiles><UserFile Filename="plan.pdf" Comment="RDC"/></UserFiles>
+    </ProjectInformation>
+  </Project>
+</KNX>"#;
+
+    fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        for (name, contents) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn summarises_directory_and_project_xml() {
+        // 0.xml is not well-formed: inspection must not touch it.
+        let nested = archive(&[("project.xml", PROJECT_XML.as_bytes()), ("0.xml", b"<KNX")]);
+        let bytes = archive(&[
+            ("knx_master.xml", b"<KNX/>"),
+            ("M-0083/Hardware.xml", b"<KNX/>"),
+            ("M-0002/M-0002_A-1234-10-ABCD.xml", b"<KNX/>"),
+            ("M-0083/Catalog.xml", b"<KNX/>"),
+            ("P-0A1B.zip", &nested),
+        ]);
+
+        let summary = inspect_knxproj(&bytes).unwrap();
+        assert_eq!(summary.project_id.as_deref(), Some("P-0A1B"));
+        assert_eq!(summary.project_name.as_deref(), Some("Maison"));
+        assert_eq!(summary.schema_version, Some(21));
+        assert_eq!(summary.tool_version.as_deref(), Some("6.1.0.5618"));
+        assert!(!summary.password_required);
+        assert_eq!(summary.manufacturers, ["M-0002", "M-0083"]);
+        assert_eq!(summary.attachments, ["plan.pdf"]);
+        let project = summary
+            .entries
+            .iter()
+            .find(|entry| entry.name == "P-0A1B.zip/project.xml")
+            .unwrap();
+        assert_eq!(project.size, PROJECT_XML.len() as u64);
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Bounds on what reading a project archive may cost, checked while entries are decompressed.
+///
+/// The defaults leave room for the largest real projects, whose application programs can reach
+/// a few hundred megabytes, while stopping zip bombs long before they exhaust memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    /// Entries in one archive, nested archives included.
+    pub max_entries: usize,
+    /// Decompressed size of one entry.
+    pub max_entry_size: u64,
+    /// Decompressed size of everything read during one load.
+    pub max_total_size: u64,
+    /// Decompressed size over compressed size of one entry, once it is past
+    /// [`ArchiveLimits::RATIO_THRESHOLD`]. XML rarely compresses better than 100:1.
+    pub max_compression_ratio: u64,
+    /// Archives inside archives; a protected project keeps its files in one nested zip.
+    pub max_nesting_depth: usize,
+    /// Nested archives opened during one load.
+    pub max_nested_archives: usize,
+}
+
+impl ArchiveLimits {
+    /// Entries smaller than this are never rejected for their compression ratio.
+    pub const RATIO_THRESHOLD: u64 = 1024 * 1024;
+
+    /// No limits, for trusted input.
+    pub fn unlimited() -> Self {
+        Self {
+            max_entries: usize::MAX,
+            max_entry_size: u64::MAX,
+            max_total_size: u64::MAX,
+            max_compression_ratio: u64::MAX,
+            max_nesting_depth: usize::MAX,
+            max_nested_archives: usize::MAX,
+        }
+    }
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 50_000,
+            max_entry_size: 512 * 1024 * 1024,
+            max_total_size: 2 * 1024 * 1024 * 1024,
+            max_compression_ratio: 500,
+            max_nesting_depth: 1,
+            max_nested_archives: 8,
+        }
+    }
+}
+
+/// An archive that exceeds its [`ArchiveLimits`] or names a file outside of itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveLimitError {
+    TooManyEntries { entries: usize, max: usize },
+    EntryTooLarge { name: String, max: u64 },
+    TotalTooLarge { max: u64 },
+    CompressionRatio { name: String, max: u64 },
+    NestingTooDeep { name: String, max: usize },
+    TooManyNestedArchives { max: usize },
+    UnsafePath { name: String },
+}
+
+impl fmt::Display for ArchiveLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyEntries { entries, max } => {
+                write!(f, "Archive has {} entries, more than the limit of {}", entries, max)
+            }
+            Self::EntryTooLarge { name, max } => {
+                write!(f, "{} is larger than {} bytes when decompressed", name, max)
+            }
+            Self::TotalTooLarge { max } => {
+                write!(f, "Archive is larger than {} bytes when decompressed", max)
+            }
+            Self::CompressionRatio { name, max } => {
+                write!(f, "{} is compressed more than {}:1, refusing a likely zip bomb", name, max)
+            }
+            Self::NestingTooDeep { name, max } => {
+                write!(f, "{} is nested more than {} archive(s) deep", name, max)
+            }
+            Self::TooManyNestedArchives { max } => {
+                write!(f, "Archive holds more than {} nested archives", max)
+            }
+            Self::UnsafePath { name } => write!(f, "Archive entry {:?} points outside the archive", name),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveLimitError {}
+
+/// What one load has read so far, shared by an archive and the archives nested in it.
+#[derive(Debug)]
+pub(crate) struct ReadBudget {
+    pub(crate) limits: ArchiveLimits,
+    total: AtomicU64,
+    nested: AtomicUsize,
+}
+
+impl ReadBudget {
+    pub(crate) fn new(limits: ArchiveLimits) -> Self {
+        Self {
+            limits,
+            total: AtomicU64::new(0),
+            nested: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn check_entries(&self, entries: usize) -> Result<(), ArchiveLimitError> {
+        if entries > self.limits.max_entries {
+            return Err(ArchiveLimitError::TooManyEntries {
+                entries,
+                max: self.limits.max_entries,
+            });
+        }
+        Ok(())
+    }
+
+    /// Refuse an entry whose size is known up front when reading it would break the limits.
+    pub(crate) fn check_size(&self, name: &str, size: u64) -> Result<(), ArchiveLimitError> {
+        if size > self.limits.max_entry_size {
+            return Err(ArchiveLimitError::EntryTooLarge {
+                name: name.to_string(),
+                max: self.limits.max_entry_size,
+            });
+        }
+        if self.total.load(Ordering::Relaxed).saturating_add(size) > self.limits.max_total_size {
+            return Err(ArchiveLimitError::TotalTooLarge {
+                max: self.limits.max_total_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Account for `read` more decompressed bytes; `so_far` is the entry's size up to now.
+    pub(crate) fn charge(
+        &self,
+        name: &str,
+        read: u64,
+        so_far: u64,
+        compressed: u64,
+    ) -> Result<(), ArchiveLimitError> {
+        if so_far > self.limits.max_entry_size {
+            return Err(ArchiveLimitError::EntryTooLarge {
+                name: name.to_string(),
+                max: self.limits.max_entry_size,
+            });
+        }
+        if so_far > ArchiveLimits::RATIO_THRESHOLD
+            && so_far / compressed.max(1) > self.limits.max_compression_ratio
+        {
+            return Err(ArchiveLimitError::CompressionRatio {
+                name: name.to_string(),
+                max: self.limits.max_compression_ratio,
+            });
+        }
+        let total = self.total.fetch_add(read, Ordering::Relaxed).saturating_add(read);
+        if total > self.limits.max_total_size {
+            return Err(ArchiveLimitError::TotalTooLarge {
+                max: self.limits.max_total_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Account for opening the nested archive `name` at `depth`, the outer archive being 0.
+    pub(crate) fn enter_nested(&self, name: &str, depth: usize) -> Result<(), ArchiveLimitError> {
+        if depth > self.limits.max_nesting_depth {
+            return Err(ArchiveLimitError::NestingTooDeep {
+                name: name.to_string(),
+                max: self.limits.max_nesting_depth,
+            });
+        }
+        if self.nested.fetch_add(1, Ordering::Relaxed) >= self.limits.max_nested_archives {
+            return Err(ArchiveLimitError::TooManyNestedArchives {
+                max: self.limits.max_nested_archives,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether an entry name stays inside the archive when extracted: relative, without `..`,
+/// drive letters or NUL bytes, whichever separator it uses.
+pub(crate) fn is_safe_entry_name(name: &str) -> bool {
+    if name.is_empty() || name.contains('\0') || name.starts_with('/') || name.starts_with('\\') {
+        return false;
+    }
+    let mut components = name.split(['/', '\\']);
+    if components.clone().next().is_some_and(|first| first.contains(':')) {
+        return false;
+    }
+    components.all(|component| component != "..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_safe_entry_name, ArchiveLimitError, ArchiveLimits, ReadBudget};
+
+    #[test]
+    fn budget_enforces_sizes_ratio_and_nesting() {
+        let budget = ReadBudget::new(ArchiveLimits {
+            max_entry_size: 4 * 1024 * 1024,
+            max_total_size: 6 * 1024 * 1024,
+            max_compression_ratio: 10,
+            max_nested_archives: 1,
+            ..ArchiveLimits::default()
+        });
+        let mib = 1024 * 1024;
+        assert!(budget.charge("a.xml", 2 * mib, 2 * mib, mib).is_ok());
+        assert!(matches!(
+            budget.charge("b.xml", mib, 5 * mib, 5 * mib),
+            Err(ArchiveLimitError::EntryTooLarge { .. })
+        ));
+        assert!(matches!(
+            budget.charge("c.xml", 2 * mib, 2 * mib, 1000),
+            Err(ArchiveLimitError::CompressionRatio { .. })
+        ));
+        // Small entries are not judged by their ratio.
+        assert!(budget.charge("d.xml", 1000, 1000, 1).is_ok());
+        assert!(matches!(
+            budget.charge("e.xml", 4 * mib, 4 * mib, 4 * mib),
+            Err(ArchiveLimitError::TotalTooLarge { .. })
+        ));
+        assert!(matches!(
+            budget.check_size("f.xml", 5 * mib),
+            Err(ArchiveLimitError::EntryTooLarge { .. })
+        ));
+        assert!(matches!(
+            budget.check_size("g.xml", 1000),
+            Err(ArchiveLimitError::TotalTooLarge { .. })
+        ));
+
+        assert!(budget.enter_nested("P-0001.zip", 1).is_ok());
+        assert!(matches!(
+            budget.enter_nested("P-0002.zip", 1),
+            Err(ArchiveLimitError::TooManyNestedArchives { max: 1 })
+        ));
+        assert!(matches!(
+            budget.enter_nested("inner.zip", 2),
+            Err(ArchiveLimitError::NestingTooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_names_leaving_the_archive() {
+        for name in ["P-0001/0.xml", "knx_master.xml", "M-0083/M-0083_A-1.xml", "a..b.xml"] {
+            assert!(is_safe_entry_name(name), "{}", name);
+        }
+        for name in ["../evil.xml", "P-0001/../../evil", "/etc/passwd", "\\evil", "..\\evil", "C:/evil", ""] {
+            assert!(!is_safe_entry_name(name), "{}", name);
+        }
+    }
+}
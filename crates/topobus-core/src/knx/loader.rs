@@ -0,0 +1,399 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use crate::knx::adapter::load_archive;
+use crate::knx::address::GroupAddressStyle;
+use crate::knx::app_store::AppProgramStore;
+use crate::knx::archive::{DirectoryArchive, LimitedZip, MemoryArchive, ProjectArchive};
+use crate::knx::limits::ArchiveLimits;
+use crate::knx::model::KnxProjectData;
+use crate::knx::progress::{NoProgress, ProgressObserver};
+
+/// Where a [`ProjectLoader`] reads the project from.
+enum Source<'a> {
+    Path(PathBuf),
+    Bytes(&'a [u8]),
+    Reader(Box<dyn ReadSeek + 'a>),
+    Directory(PathBuf),
+    Files(Vec<(String, Vec<u8>)>),
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Settings of one project load, shared by the extractors.
+pub(crate) struct LoadOptions<'a> {
+    pub(crate) preferred_language: Option<&'a str>,
+    pub(crate) group_address_style: Option<GroupAddressStyle>,
+    pub(crate) app_store: Option<&'a AppProgramStore>,
+    pub(crate) progress: &'a dyn ProgressObserver,
+    pub(crate) skip_parameters: bool,
+    pub(crate) skip_app_programs: bool,
+    pub(crate) skip_locations: bool,
+}
+
+/// Builder for loading a KNX project.
+///
+/// ```no_run
+/// use topobus_core::ProjectLoader;
+///
+/// let project = ProjectLoader::from_path("house.knxproj")
+///     .password("secret")
+///     .language("de-DE")
+///     .load()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Application programs are the bulk of a load. For a quick look at topology and group
+/// addresses, turn them off together with parameters and locations; devices then keep their
+/// names, products and links, without object names or datapoint types from the program.
+pub struct ProjectLoader<'a> {
+    source: Source<'a>,
+    password: Option<&'a str>,
+    limits: ArchiveLimits,
+    options: LoadOptions<'a>,
+}
+
+impl<'a> ProjectLoader<'a> {
+    /// Load a `.knxproj` file, or a project extracted to the directory at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        Self::new(Source::Path(path.as_ref().to_path_buf()))
+    }
+
+    /// Load a `.knxproj` held in memory.
+    pub fn from_bytes(data: &'a [u8]) -> Self {
+        Self::new(Source::Bytes(data))
+    }
+
+    /// Load a `.knxproj` from any seekable reader.
+    pub fn from_reader<R: Read + Seek + 'a>(reader: R) -> Self {
+        Self::new(Source::Reader(Box::new(reader)))
+    }
+
+    /// Load a `.knxproj` that was extracted to a directory.
+    pub fn from_directory(path: impl AsRef<Path>) -> Self {
+        Self::new(Source::Directory(path.as_ref().to_path_buf()))
+    }
+
+    /// Load a project from its files, keyed by their path in the archive, e.g.
+    /// `P-0001/0.xml`. A nested `P-0001.zip` is opened like in a `.knxproj`.
+    pub fn from_files<K: Into<String>>(files: impl IntoIterator<Item = (K, Vec<u8>)>) -> Self {
+        let files = files
+            .into_iter()
+            .map(|(name, contents)| (name.into(), contents))
+            .collect();
+        Self::new(Source::Files(files))
+    }
+
+    fn new(source: Source<'a>) -> Self {
+        Self {
+            source,
+            password: None,
+            limits: ArchiveLimits::default(),
+            options: LoadOptions {
+                preferred_language: None,
+                group_address_style: None,
+                app_store: None,
+                progress: &NoProgress,
+                skip_parameters: false,
+                skip_app_programs: false,
+                skip_locations: false,
+            },
+        }
+    }
+
+    /// Project password, for projects protected in ETS.
+    pub fn password(mut self, password: impl Into<Option<&'a str>>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Limits on entry sizes, compression ratios and nesting while reading the archive.
+    pub fn archive_limits(mut self, limits: ArchiveLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Preferred language for application program texts, e.g. `"de-DE"`.
+    pub fn language(mut self, language: impl Into<Option<&'a str>>) -> Self {
+        self.options.preferred_language = language.into();
+        self
+    }
+
+    /// Format group addresses in this style instead of the one stored in the project.
+    pub fn group_address_style(mut self, style: impl Into<Option<GroupAddressStyle>>) -> Self {
+        self.options.group_address_style = style.into();
+        self
+    }
+
+    /// Report loading phases to `progress`, which may also cancel the load.
+    pub fn progress(mut self, progress: &'a dyn ProgressObserver) -> Self {
+        self.options.progress = progress;
+        self
+    }
+
+    /// Reuse application programs parsed by earlier loads.
+    pub fn app_store(mut self, store: &'a AppProgramStore) -> Self {
+        self.options.app_store = Some(store);
+        self
+    }
+
+    /// Leave device parameters and properties out.
+    pub fn skip_parameters(mut self, skip: bool) -> Self {
+        self.options.skip_parameters = skip;
+        self
+    }
+
+    /// Do not read application programs at all.
+    pub fn skip_app_programs(mut self, skip: bool) -> Self {
+        self.options.skip_app_programs = skip;
+        self
+    }
+
+    /// Leave the building structure out.
+    pub fn skip_locations(mut self, skip: bool) -> Self {
+        self.options.skip_locations = skip;
+        self
+    }
+
+    pub fn load(self) -> Result<KnxProjectData> {
+        let mut archive: Box<dyn ProjectArchive + 'a> = match self.source {
+            Source::Path(path) if path.is_dir() => {
+                log::info!("Loading KNX project from directory: {}", path.display());
+                Box::new(DirectoryArchive::open(&path, self.limits)?)
+            }
+            Source::Path(path) => {
+                log::info!("Loading KNX project from: {}", path.display());
+                let file = File::open(&path).context("Failed to open .knxproj file")?;
+                Box::new(LimitedZip::open(file, self.limits)?)
+            }
+            Source::Bytes(data) => {
+                log::info!("Loading KNX project from bytes ({} bytes)", data.len());
+                Box::new(LimitedZip::open(Cursor::new(data), self.limits)?)
+            }
+            Source::Reader(reader) => {
+                log::info!("Loading KNX project from reader");
+                Box::new(LimitedZip::open(reader, self.limits)?)
+            }
+            Source::Directory(path) => {
+                log::info!("Loading KNX project from directory: {}", path.display());
+                Box::new(DirectoryArchive::open(&path, self.limits)?)
+            }
+            Source::Files(files) => {
+                log::info!("Loading KNX project from {} files in memory", files.len());
+                Box::new(MemoryArchive::new(files, self.limits)?)
+            }
+        };
+        load_archive(archive.as_mut(), self.password, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProjectLoader;
+    use crate::knx::address::GroupAddressStyle;
+    use crate::knx::limits::{ArchiveLimitError, ArchiveLimits};
+    use crate::knx::KnxProjectData;
+    use std::io::Write;
+    use std::path::Path;
+
+    const PROJECT_XML: &str =
+        r#"<KNX><Project Id="P-0001"><ProjectInformation Name="Mini" GroupAddressStyle="ThreeLevel"/></Project></KNX>"#;
+    const DATA_XML: &str = r#"<KNX><Project Id="P-0001"><Installations><Installation>
+        <Topology><Area Id="P-0001-0_A-1" Address="1"><Line Id="P-0001-0_L-1" Address="1"/></Area></Topology>
+        <GroupAddresses><GroupRanges><GroupRange Id="P-0001-0_GR-1" Name="Lights">
+            <GroupAddress Id="P-0001-0_GA-1" Address="2305" Name="Kitchen"/>
+        </GroupRange></GroupRanges></GroupAddresses>
+        <Locations><Space Id="P-0001-0_BP-1" Type="Building" Name="House"/></Locations>
+    </Installation></Installations></Project></KNX>"#;
+
+    fn archive() -> Vec<u8> {
+        zip_files(&[
+            ("P-0001/project.xml".to_string(), PROJECT_XML.as_bytes().to_vec()),
+            ("P-0001/0.xml".to_string(), DATA_XML.as_bytes().to_vec()),
+        ])
+    }
+
+    fn zip_files(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default();
+        for (name, contents) in files {
+            zip.start_file(name.as_str(), options).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn fixture_files(root: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) {
+        for entry in std::fs::read_dir(root).unwrap() {
+            let entry = entry.unwrap();
+            let name = format!("{}{}", prefix, entry.file_name().to_str().unwrap());
+            if entry.file_type().unwrap().is_dir() {
+                fixture_files(&entry.path(), &format!("{}/", name), files);
+            } else {
+                files.push((name, std::fs::read(entry.path()).unwrap()));
+            }
+        }
+    }
+
+    /// What a load produced, in a form that does not depend on hash map ordering.
+    fn outline(project: &KnxProjectData) -> Vec<String> {
+        let mut lines = vec![project.project_name.clone()];
+        for device in &project.devices {
+            lines.push(format!("{} {:?}", device.individual_address, device.product));
+            for link in &device.group_links {
+                lines.push(format!(
+                    "  {} #{:?} {:?} {}",
+                    link.group_address, link.number, link.datapoint_type, link.ets_sending
+                ));
+            }
+        }
+        for ga in &project.group_addresses {
+            lines.push(format!("{} {} {:?}", ga.address, ga.name, ga.datapoint_type));
+        }
+        lines
+    }
+
+    #[test]
+    fn loads_zipped_and_unpacked_projects_alike() {
+        let dir = std::env::temp_dir().join(format!("topobus-loader-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("P-0001")).unwrap();
+        std::fs::write(dir.join("P-0001/project.xml"), PROJECT_XML).unwrap();
+        std::fs::write(dir.join("P-0001/0.xml"), DATA_XML).unwrap();
+
+        let bytes = archive();
+        let zipped = ProjectLoader::from_bytes(&bytes).load().unwrap();
+        let unpacked = ProjectLoader::from_directory(&dir).load();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let unpacked = unpacked.unwrap();
+
+        assert_eq!(zipped.project_name, "Mini");
+        assert_eq!(unpacked.project_name, zipped.project_name);
+        assert_eq!(unpacked.group_addresses[0].address, "1/1/1");
+        assert_eq!(unpacked.locations.len(), 1);
+    }
+
+    #[test]
+    fn applies_style_override_and_skips_locations() {
+        let bytes = archive();
+        let project = ProjectLoader::from_reader(std::io::Cursor::new(bytes))
+            .group_address_style(GroupAddressStyle::TwoLevel)
+            .skip_locations(true)
+            .load()
+            .unwrap();
+        assert_eq!(project.group_addresses[0].address, "1/257");
+        assert!(project.locations.is_empty());
+    }
+
+    #[test]
+    fn loads_every_source_alike() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ets6");
+        let mut files = Vec::new();
+        fixture_files(&root, "", &mut files);
+        let (project_files, other_files): (Vec<_>, Vec<_>) =
+            files.iter().cloned().partition(|(name, _)| name.starts_with("P-0001/"));
+        let mut nested = other_files;
+        nested.push(("P-0001.zip".to_string(), zip_files(&project_files)));
+
+        let expected = outline(&ProjectLoader::from_path(&root).load().unwrap());
+        assert_eq!(expected.len(), 8);
+        let zipped = zip_files(&files);
+        let nested_zip = zip_files(&nested);
+        for (source, loader) in [
+            ("zip", ProjectLoader::from_bytes(&zipped)),
+            ("nested zip", ProjectLoader::from_bytes(&nested_zip)),
+            ("files", ProjectLoader::from_files(files.clone())),
+            ("nested files", ProjectLoader::from_files(nested.clone())),
+        ] {
+            assert_eq!(outline(&loader.load().unwrap()), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn rejects_archives_over_their_limits() {
+        let limit_error = |bytes: &[u8], limits: Option<ArchiveLimits>| {
+            let loader = ProjectLoader::from_bytes(bytes);
+            let loader = match limits {
+                Some(limits) => loader.archive_limits(limits),
+                None => loader,
+            };
+            let err = loader.load().unwrap_err();
+            err.downcast::<ArchiveLimitError>().unwrap()
+        };
+
+        // Whitespace deflates about 1000:1.
+        let padded = format!("{}{}", DATA_XML, " ".repeat(4 * 1024 * 1024));
+        let bomb = zip_files(&[
+            ("P-0001/project.xml".to_string(), PROJECT_XML.as_bytes().to_vec()),
+            ("P-0001/0.xml".to_string(), padded.into_bytes()),
+        ]);
+        assert!(matches!(
+            limit_error(&bomb, None),
+            ArchiveLimitError::CompressionRatio { .. }
+        ));
+        let small = ArchiveLimits {
+            max_entry_size: 64,
+            ..ArchiveLimits::default()
+        };
+        assert!(matches!(
+            limit_error(&archive(), Some(small)),
+            ArchiveLimitError::EntryTooLarge { .. }
+        ));
+
+        // Files handed over one by one count against the same limits.
+        let files = vec![
+            ("P-0001/project.xml".to_string(), PROJECT_XML.as_bytes().to_vec()),
+            ("P-0001/0.xml".to_string(), DATA_XML.as_bytes().to_vec()),
+        ];
+        let files_error = |limits: ArchiveLimits| {
+            let err = ProjectLoader::from_files(files.clone())
+                .archive_limits(limits)
+                .load()
+                .unwrap_err();
+            err.downcast::<ArchiveLimitError>().unwrap()
+        };
+        assert!(matches!(files_error(small), ArchiveLimitError::EntryTooLarge { .. }));
+        let total = ArchiveLimits {
+            max_total_size: (PROJECT_XML.len() + DATA_XML.len()) as u64 - 1,
+            ..ArchiveLimits::default()
+        };
+        assert!(matches!(files_error(total), ArchiveLimitError::TotalTooLarge { .. }));
+        assert!(ProjectLoader::from_files(files.clone()).load().is_ok());
+
+        // Unpacked projects are checked before their files are read.
+        let dir = std::env::temp_dir().join(format!("topobus-limits-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("P-0001")).unwrap();
+        for (name, contents) in &files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        let dir_error = |limits: ArchiveLimits| {
+            let err = ProjectLoader::from_directory(&dir)
+                .archive_limits(limits)
+                .load()
+                .unwrap_err();
+            err.downcast::<ArchiveLimitError>().unwrap()
+        };
+        let errors = (dir_error(small), dir_error(total));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(errors.0, ArchiveLimitError::EntryTooLarge { .. }));
+        assert!(matches!(errors.1, ArchiveLimitError::TotalTooLarge { .. }));
+
+        let mut files = vec![("../../evil.xml".to_string(), b"<KNX/>".to_vec())];
+        files.push(("P-0001/project.xml".to_string(), PROJECT_XML.as_bytes().to_vec()));
+        assert_eq!(
+            limit_error(&zip_files(&files), None),
+            ArchiveLimitError::UnsafePath {
+                name: "../../evil.xml".to_string()
+            }
+        );
+
+        let unlimited = ProjectLoader::from_bytes(&bomb)
+            .archive_limits(ArchiveLimits::unlimited())
+            .load()
+            .unwrap();
+        assert_eq!(unlimited.project_name, "Mini");
+    }
+}
@@ -1,18 +1,33 @@
 pub mod adapter;
+pub mod anonymize;
 pub mod app_model;
+pub mod app_store;
+mod archive;
 pub mod address;
+pub mod fingerprint;
+pub mod inspect;
+pub mod limits;
+mod loader;
 pub mod model;
+mod parallel;
 pub mod parsers;
+pub mod progress;
+pub mod redaction;
+pub mod schema;
 pub mod xml_tags;
+pub mod xml_index;
 pub mod xml_utils;
 pub mod zip_utils;
 
-pub use adapter::{
-    load_knxproj,
-    load_knxproj_bytes,
-    load_knxproj_bytes_with_language,
-    load_knxproj_with_language,
-    InvalidPasswordError,
-    PasswordRequiredError,
-};
+pub use adapter::{InvalidPasswordError, PasswordRequiredError};
+pub use anonymize::anonymize_knxproj;
+pub use address::GroupAddressStyle;
+pub use app_store::{AppProgramStore, AppProgramStoreStats};
+pub use fingerprint::{project_cache_key, sha256_hex};
+pub use inspect::{inspect_knxproj, ArchiveEntry, ProjectSummary};
+pub use limits::{ArchiveLimitError, ArchiveLimits};
+pub use loader::ProjectLoader;
 pub use model::*;
+pub use progress::{NoProgress, ParseCancelledError, ProgressEvent, ProgressObserver};
+pub use redaction::{Redacted, RedactionPolicy, REDACTED};
+pub use schema::{EtsGeneration, UnsupportedSchemaError};
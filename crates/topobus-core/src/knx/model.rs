@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::knx::schema::EtsGeneration;
+
 /// Data extracted from a KNX project
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct KnxProjectData {
     pub project_name: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -23,13 +25,25 @@ pub struct ProjectInfo {
     pub description: Option<String>,
     pub completion_status: Option<String>,
     pub archived_version: Option<String>,
+    #[serde(serialize_with = "crate::knx::redaction::secret")]
     pub project_tracing_password: Option<String>,
     pub security_mode: Option<String>,
+    #[serde(serialize_with = "crate::knx::redaction::secret")]
     pub bcu_key: Option<String>,
     pub codepage: Option<String>,
     pub last_modified: Option<String>,
     pub project_size: Option<String>,
     pub group_address_style: Option<String>,
+    /// Version of the `http://knx.org/xml/project/NN` namespace, e.g. 21.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ets_generation: Option<EtsGeneration>,
+    /// `CreatedBy` and `ToolVersion` of the project document, e.g. `ETS6` and `6.1.0.5618`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_version: Option<String>,
     pub file_size: Option<String>,
     pub file_last_modified: Option<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -95,7 +109,7 @@ pub struct LineInfo {
 }
 
 /// Information about a KNX device
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DeviceInfo {
     /// Unique identifier for the device instance in the KNX project
     pub instance_id: String,
@@ -159,7 +173,7 @@ pub struct DeviceInfo {
 }
 
 /// Link between a device communication object and a group address
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GroupLink {
     /// Unique identifier of the `ComObjectInstanceRef` in the project (when available).
     /// Useful to regroup multiple GroupLink entries that belong to the same communication object.
@@ -254,7 +268,7 @@ pub struct DeviceConfigEntry {
 }
 
 /// Information about a KNX group address
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GroupAddressInfo {
     /// Group address in format "M/S/A"
     pub address: String,
@@ -288,7 +302,7 @@ pub struct GroupAddressInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub security: Option<String>,
     /// Group address security key (base64) if available
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "crate::knx::redaction::secret")]
     pub security_key: Option<String>,
     /// List of device individual addresses linked to this group address
     pub linked_devices: Vec<String>,
@@ -308,7 +322,7 @@ pub struct BuildingDeviceRef {
 }
 
 /// Building structure node (location space)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BuildingSpace {
     /// Unique identifier of the space in the KNX project
     pub id: String,
@@ -0,0 +1,33 @@
+/// Map `items` on the rayon thread pool, preserving their order.
+///
+/// wasm32 builds have no threads, so the items are mapped one after the other there.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn par_map<T, U, F>(items: &[T], map: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync + Send,
+{
+    use rayon::prelude::*;
+    items.par_iter().map(map).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn par_map<T, U, F>(items: &[T], map: F) -> Vec<U>
+where
+    F: Fn(&T) -> U,
+{
+    items.iter().map(map).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::par_map;
+
+    #[test]
+    fn preserves_input_order() {
+        let items: Vec<u32> = (0..1000).collect();
+        let doubled = par_map(&items, |value| value * 2);
+        assert_eq!(doubled, items.iter().map(|value| value * 2).collect::<Vec<_>>());
+    }
+}
@@ -1,9 +1,7 @@
 use std::collections::HashMap;
-use std::io::{Read, Seek};
 
 use anyhow::{Context, Result};
 use roxmltree::Document;
-use zip::ZipArchive;
 
 use crate::knx::app_model::{AllocatorDef, AppProgram, ModuleArgumentInfo, NumericArgDef};
 use crate::knx::parsers::com_objects::{parse_com_object_refs, parse_com_objects};
@@ -14,28 +12,30 @@ use crate::knx::parsers::parameters::{
     parse_parameter_types,
 };
 use crate::knx::parsers::translations::{attr_value_localized, build_translations, strip_prefix};
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::attr_value;
-use crate::knx::zip_utils::{read_zip_entry, strip_bom};
+use crate::knx::zip_utils::strip_bom;
 
-pub(crate) fn load_app_program<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
+/// Archive path of an application program, e.g. `M-0083/M-0083_A-0001-11-ABCD.xml`.
+pub(crate) fn app_program_path(app_id: &str) -> String {
+    let manufacturer = app_id.split('_').next().unwrap_or("");
+    format!("{}/{}.xml", manufacturer, app_id)
+}
+
+pub(crate) fn parse_app_program(
+    xml: &str,
     app_id: &str,
     preferred_language: Option<&str>,
 ) -> Result<AppProgram> {
-    let manufacturer = app_id.split('_').next().unwrap_or("");
-    let path = format!("{}/{}.xml", manufacturer, app_id);
-    let xml = read_zip_entry(zip, &path)?;
-    let doc =
-        Document::parse(strip_bom(&xml)).with_context(|| format!("Failed to parse {}", path))?;
+    let doc = Document::parse(strip_bom(xml))
+        .with_context(|| format!("Failed to parse {}", app_program_path(app_id)))?;
+    let index = XmlIndex::new(&doc);
     let prefix = format!("{}_", app_id);
-    let translations = build_translations(&doc, &prefix, preferred_language);
+    let translations = build_translations(&index, &prefix, preferred_language);
 
     let mut arguments = HashMap::new();
-    for arg in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::ARGUMENT)
-    {
+    for &arg in index.elements(xml_tags::ARGUMENT) {
         let id = match arg.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -47,18 +47,15 @@ pub(crate) fn load_app_program<R: Read + Seek>(
         arguments.insert(strip_prefix(id, &prefix), name.to_string());
     }
 
-    let com_objects = parse_com_objects(&doc, &prefix, &translations);
-    let com_object_refs = parse_com_object_refs(&doc, &prefix, &translations);
-    let parameter_types = parse_parameter_types(&doc, &prefix, &translations);
-    let parameter_refs = parse_parameter_refs(&doc, &prefix, &translations);
-    let parameter_ref_context = parse_parameter_ref_context(&doc, &prefix, &translations);
-    let parameters = parse_parameter_definitions(&doc, &prefix, &translations);
+    let com_objects = parse_com_objects(&index, &prefix, &translations);
+    let com_object_refs = parse_com_object_refs(&index, &prefix, &translations);
+    let parameter_types = parse_parameter_types(&index, &prefix, &translations);
+    let parameter_refs = parse_parameter_refs(&index, &prefix, &translations);
+    let parameter_ref_context = parse_parameter_ref_context(&index, &prefix, &translations);
+    let parameters = parse_parameter_definitions(&index, &prefix, &translations);
 
     let mut allocators = HashMap::new();
-    for allocator in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::ALLOCATOR)
-    {
+    for &allocator in index.elements(xml_tags::ALLOCATOR) {
         let id = match allocator.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -80,10 +77,7 @@ pub(crate) fn load_app_program<R: Read + Seek>(
     }
 
     let mut module_def_arguments = HashMap::new();
-    for arg in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::ARGUMENT)
-    {
+    for &arg in index.elements(xml_tags::ARGUMENT) {
         let id = match arg.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -104,10 +98,7 @@ pub(crate) fn load_app_program<R: Read + Seek>(
     }
 
     let mut numeric_args = HashMap::new();
-    for num in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::NUMERIC_ARG)
-    {
+    for &num in index.elements(xml_tags::NUMERIC_ARG) {
         let ref_id = match num.attribute("RefId") {
             Some(id) => id,
             None => continue,
@@ -123,9 +114,7 @@ pub(crate) fn load_app_program<R: Read + Seek>(
         );
     }
 
-    let app_node = doc
-        .descendants()
-        .find(|node| node.tag_name().name() == xml_tags::APPLICATION_PROGRAM);
+    let app_node = index.elements(xml_tags::APPLICATION_PROGRAM).first().copied();
     let app_name = app_node.and_then(|node| attr_value_localized(&node, "Name", &translations, &prefix));
     let app_version = app_node.and_then(|node| attr_value(&node, "ApplicationVersion"));
     let app_number = app_node.and_then(|node| attr_value(&node, "ApplicationNumber"));
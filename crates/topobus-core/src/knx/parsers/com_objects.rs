@@ -1,9 +1,8 @@
 use std::collections::HashMap;
 
-use roxmltree::Document;
-
 use crate::knx::app_model::{AppProgram, ComObjectDef, ComObjectRefDef, Flags};
 use crate::knx::parsers::translations::{attr_value_localized, strip_prefix};
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::attr_value;
 
@@ -18,15 +17,12 @@ pub(crate) struct ComObjectData {
 }
 
 pub(crate) fn parse_com_objects(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, ComObjectDef> {
     let mut com_objects = HashMap::new();
-    for obj in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::COM_OBJECT)
-    {
+    for &obj in index.elements(xml_tags::COM_OBJECT) {
         let id = match obj.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -50,15 +46,12 @@ pub(crate) fn parse_com_objects(
 }
 
 pub(crate) fn parse_com_object_refs(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, ComObjectRefDef> {
     let mut com_object_refs = HashMap::new();
-    for obj in doc.descendants() {
-        if obj.tag_name().name() != xml_tags::COM_OBJECT_REF {
-            continue;
-        }
+    for &obj in index.elements(xml_tags::COM_OBJECT_REF) {
         let id = match obj.attribute("Id") {
             Some(id) => id,
             None => continue,
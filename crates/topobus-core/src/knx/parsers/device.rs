@@ -1,13 +1,14 @@
-use std::collections::HashMap;
-use std::io::{Read, Seek};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use roxmltree::Document;
-use zip::ZipArchive;
+use roxmltree::{Document, Node};
 
 use crate::knx::app_model::AppProgram;
+use crate::knx::archive::ProjectArchive;
+use crate::knx::loader::LoadOptions;
 use crate::knx::model::{DeviceInfo, GroupAddressInfo, GroupLink};
-use crate::knx::parsers::app_program::load_app_program;
+use crate::knx::parsers::app_program::{app_program_path, parse_app_program};
 use crate::knx::parsers::com_objects::{
     com_object_key,
     compute_object_number,
@@ -17,6 +18,10 @@ use crate::knx::parsers::com_objects::{
     resolve_template,
 };
 use crate::knx::parsers::parameters::extract_device_configuration;
+use crate::knx::parallel::par_map;
+use crate::knx::progress::{report, ProgressEvent};
+use crate::knx::schema::SchemaAdapter;
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::{
     attr_value,
@@ -27,23 +32,21 @@ use crate::knx::xml_utils::{
     required_attribute,
     short_id,
 };
-use crate::knx::zip_utils::{read_zip_entry, strip_bom};
+use crate::knx::zip_utils::strip_bom;
 
-pub(crate) fn extract_devices<R: Read + Seek>(
-    doc: &Document,
-    zip: &mut ZipArchive<R>,
+pub(crate) fn extract_devices(
+    index: &XmlIndex,
+    archive: &mut dyn ProjectArchive,
     group_address_by_id: &HashMap<String, GroupAddressInfo>,
     manufacturer_names: &HashMap<String, String>,
-    preferred_language: Option<&str>,
+    schema: &dyn SchemaAdapter,
+    options: &LoadOptions,
 ) -> Result<Vec<DeviceInfo>> {
     let mut devices = Vec::new();
-    let mut hardware_cache: HashMap<String, HardwareData> = HashMap::new();
-    let mut app_cache: HashMap<String, AppProgram> = HashMap::new();
+    let device_nodes = index.elements(xml_tags::DEVICE_INSTANCE);
+    let catalog = load_product_catalog(archive, device_nodes, options)?;
 
-    for device_node in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::DEVICE_INSTANCE)
-    {
+    for &device_node in device_nodes {
         let device_id = match required_attribute(&device_node, "Id") {
             Ok(value) => value,
             Err(error) => {
@@ -88,9 +91,10 @@ pub(crate) fn extract_devices<R: Read + Seek>(
             .children()
             .find(|node| node.is_element() && node.tag_name().name() == xml_tags::IP_CONFIG)
             .or_else(|| {
-                device_node
-                    .descendants()
-                    .find(|node| node.tag_name().name() == xml_tags::IP_CONFIG)
+                index
+                    .descendants_by_tag(device_node, xml_tags::IP_CONFIG)
+                    .first()
+                    .copied()
             });
         let (ip_assignment, ip_address, ip_subnet_mask, ip_default_gateway, mac_address) =
             if let Some(ip_node) = ip_config {
@@ -127,11 +131,9 @@ pub(crate) fn extract_devices<R: Read + Seek>(
             .and_then(|id| manufacturer_names.get(id))
             .cloned();
 
-        let hardware_data = if let Some(ref manufacturer_id) = manufacturer_id {
-            ensure_hardware_data(zip, manufacturer_id, &mut hardware_cache)?
-        } else {
-            None
-        };
+        let hardware_data = manufacturer_id
+            .as_ref()
+            .and_then(|id| catalog.hardware.get(id));
 
         let is_coupler = hardware_data
             .and_then(|data| {
@@ -184,19 +186,13 @@ pub(crate) fn extract_devices<R: Read + Seek>(
         };
 
         let mut module_args: HashMap<String, HashMap<String, String>> = HashMap::new();
-        for module in device_node
-            .descendants()
-            .filter(|n| n.tag_name().name() == xml_tags::MODULE_INSTANCE)
-        {
+        for &module in index.descendants_by_tag(device_node, xml_tags::MODULE_INSTANCE) {
             let module_id = module.attribute("Id").unwrap_or("").to_string();
             if module_id.is_empty() {
                 continue;
             }
             let mut args = HashMap::new();
-            for arg in module
-                .descendants()
-                .filter(|n| n.tag_name().name() == xml_tags::ARGUMENT)
-            {
+            for &arg in index.descendants_by_tag(module, xml_tags::ARGUMENT) {
                 let ref_id = arg.attribute("RefId").unwrap_or("");
                 let value = arg.attribute("Value").unwrap_or("");
                 if !ref_id.is_empty() {
@@ -208,12 +204,11 @@ pub(crate) fn extract_devices<R: Read + Seek>(
             }
         }
 
-        let app_id = if let Some(hw) = &hardware2program {
-            ensure_app_program(zip, hw, hardware_data, &mut app_cache, preferred_language)?
-        } else {
-            None
-        };
-        let app = app_id.as_ref().and_then(|id| app_cache.get(id));
+        let app = hardware2program
+            .as_ref()
+            .and_then(|hw| hardware_data.and_then(|data| data.hardware2program.get(hw)))
+            .and_then(|app_id| catalog.app_programs.get(app_id))
+            .map(Arc::as_ref);
         let app_program_name = app.and_then(|data| data.name.clone());
         let app_program_version = app.and_then(|data| data.version.clone());
         let app_program_number = app.and_then(|data| data.number.clone());
@@ -221,25 +216,20 @@ pub(crate) fn extract_devices<R: Read + Seek>(
         let app_mask_version = app.and_then(|data| data.mask_version.clone());
 
         let mut group_links = Vec::new();
-        for com_ref in device_node
-            .descendants()
-            .filter(|n| n.tag_name().name() == xml_tags::COM_OBJECT_INSTANCE_REF)
-        {
+        let mut unresolved_objects = 0;
+        for &com_ref in index.descendants_by_tag(device_node, xml_tags::COM_OBJECT_INSTANCE_REF) {
             let ref_id = com_ref.attribute("RefId").unwrap_or("");
             if ref_id.is_empty() {
                 continue;
             }
 
-            let link_attr = com_ref.attribute("Links").unwrap_or("");
-            let mut link_ids: Vec<String> = parse_links_attribute(link_attr);
-            if link_ids.is_empty() {
-                link_ids = extract_connector_links(&com_ref);
-            }
+            let link_ids = schema.com_object_links(com_ref);
             if link_ids.is_empty() {
                 continue;
             }
 
-            let module_id = ref_id.split("_O-").next().unwrap_or("");
+            let local_ref = schema.local_com_object_ref(ref_id);
+            let module_id = local_ref.split("_O-").next().unwrap_or("");
             let arg_values = resolve_module_arguments(module_args.get(module_id), app);
             let base_module_values = module_id
                 .split("_SM-")
@@ -252,8 +242,11 @@ pub(crate) fn extract_devices<R: Read + Seek>(
                     }
                 });
 
-            let com_key = com_object_key(ref_id);
+            let com_key = com_object_key(local_ref);
             let com_def = app.and_then(|program| program.com_object_refs.get(&com_key));
+            if com_def.is_none() {
+                unresolved_objects += 1;
+            }
             let com_obj = com_def
                 .and_then(|def| def.ref_id.as_ref())
                 .and_then(|ref_id| app.and_then(|program| program.com_objects.get(ref_id)));
@@ -264,7 +257,7 @@ pub(crate) fn extract_devices<R: Read + Seek>(
                 module_args.get(module_id),
                 base_module_values,
                 app,
-                ref_id,
+                local_ref,
             );
                 let com_flags = com_data.flags;
             let link_security = attr_value(&com_ref, "Security");
@@ -384,8 +377,19 @@ pub(crate) fn extract_devices<R: Read + Seek>(
                 });
             }
         }
+        if unresolved_objects > 0 && app.is_some() {
+            log::warn!(
+                "Device {}: {} group objects not found in its application program",
+                individual_address,
+                unresolved_objects
+            );
+        }
 
-        let (configuration, configuration_entries) = extract_device_configuration(&device_node, app);
+        let (configuration, configuration_entries) = if options.skip_parameters {
+            Default::default()
+        } else {
+            extract_device_configuration(&device_node, index, app)
+        };
 
         devices.push(DeviceInfo {
             instance_id: device_id.to_string(),
@@ -423,16 +427,6 @@ pub(crate) fn extract_devices<R: Read + Seek>(
     Ok(devices)
 }
 
-fn parse_links_attribute(link_attr: &str) -> Vec<String> {
-    // `Links` is a whitespace-separated list of GroupAddressRefId values.
-    // We also accept commas for robustness (some exports/tools may add them).
-    link_attr
-        .split(|c: char| c.is_whitespace() || c == ',')
-        .filter(|value| !value.is_empty())
-        .map(|value| value.to_string())
-        .collect()
-}
-
 fn build_channel_maps(device_node: &roxmltree::Node) -> (HashMap<String, String>, HashMap<String, String>) {
     let mut by_object = HashMap::new();
     let mut by_channel_id = HashMap::new();
@@ -486,57 +480,6 @@ fn build_channel_maps(device_node: &roxmltree::Node) -> (HashMap<String, String>
     (by_object, by_channel_id)
 }
 
-fn extract_connector_links(com_ref: &roxmltree::Node) -> Vec<String> {
-    let mut ids = Vec::new();
-    let connectors = com_ref
-        .children()
-        .find(|node| node.is_element() && node.tag_name().name() == xml_tags::CONNECTORS);
-    if let Some(connectors) = connectors {
-        for node in connectors
-            .children()
-            .filter(|n| n.is_element() && n.tag_name().name() == xml_tags::SEND)
-        {
-            if let Some(ref_id) = node.attribute("GroupAddressRefId") {
-                let trimmed = ref_id.trim();
-                if !trimmed.is_empty() {
-                    ids.push(short_id(trimmed));
-                }
-            }
-        }
-        for node in connectors
-            .children()
-            .filter(|n| n.is_element() && n.tag_name().name() == xml_tags::RECEIVE)
-        {
-            if let Some(ref_id) = node.attribute("GroupAddressRefId") {
-                let trimmed = ref_id.trim();
-                if !trimmed.is_empty() {
-                    ids.push(short_id(trimmed));
-                }
-            }
-        }
-    }
-    ids
-}
-
-#[cfg(test)]
-mod tests {
-    use super::parse_links_attribute;
-
-    #[test]
-    fn parse_links_attribute_preserves_order_and_splits_whitespace() {
-        let input = " G-1\nG-2\tG-3  G-4 ";
-        let out = parse_links_attribute(input);
-        assert_eq!(out, vec!["G-1", "G-2", "G-3", "G-4"]);
-    }
-
-    #[test]
-    fn parse_links_attribute_accepts_commas_without_reordering() {
-        let input = "G-1,G-2, G-3";
-        let out = parse_links_attribute(input);
-        assert_eq!(out, vec!["G-1", "G-2", "G-3"]);
-    }
-}
-
 fn manufacturer_id_from_ref(value: &str) -> Option<String> {
     let id = value.split('_').next().unwrap_or("");
     if id.starts_with("M-") {
@@ -546,70 +489,125 @@ fn manufacturer_id_from_ref(value: &str) -> Option<String> {
     }
 }
 
-fn ensure_app_program<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-    hardware2program: &str,
-    hardware_data: Option<&HardwareData>,
-    app_cache: &mut HashMap<String, AppProgram>,
-    preferred_language: Option<&str>,
-) -> Result<Option<String>> {
-    let app_id = hardware_data
-        .and_then(|data| data.hardware2program.get(hardware2program))
-        .cloned();
-    let app_id = match app_id {
-        Some(id) => id,
-        None => return Ok(None),
-    };
+/// Hardware catalogs and application programs referenced by the devices of a project.
+struct ProductCatalog {
+    hardware: HashMap<String, HardwareData>,
+    app_programs: HashMap<String, Arc<AppProgram>>,
+}
 
-    if !app_cache.contains_key(&app_id) {
-        let program = match load_app_program(zip, &app_id, preferred_language) {
-            Ok(program) => program,
-            Err(err) => {
-                log::warn!("Missing app program {} ({})", app_id, err);
-                return Ok(None);
+/// Load every catalog entry the devices refer to before walking the devices.
+///
+/// The archive can only be read sequentially, so each entry is decompressed once up front; the
+/// documents are independent of each other and are parsed in parallel.
+fn load_product_catalog(
+    archive: &mut dyn ProjectArchive,
+    device_nodes: &[Node],
+    options: &LoadOptions,
+) -> Result<ProductCatalog> {
+    let mut manufacturers = Vec::new();
+    for device_node in device_nodes {
+        let manufacturer = ["ProductRefId", "Hardware2ProgramRefId"]
+            .iter()
+            .find_map(|attribute| device_node.attribute(*attribute).and_then(manufacturer_id_from_ref));
+        if let Some(manufacturer) = manufacturer {
+            if !manufacturers.contains(&manufacturer) {
+                manufacturers.push(manufacturer);
             }
-        };
-        app_cache.insert(app_id.clone(), program);
+        }
     }
 
-    Ok(Some(app_id))
-}
+    let mut hardware_xml = Vec::new();
+    for manufacturer in manufacturers {
+        report(
+            options.progress,
+            ProgressEvent::Hardware {
+                manufacturer: manufacturer.clone(),
+            },
+        )?;
+        match archive.read_text(&hardware_path(&manufacturer), None) {
+            Ok(xml) => hardware_xml.push((manufacturer, xml)),
+            Err(err) => log::warn!(
+                "Missing hardware data for {} ({}), skipping details",
+                manufacturer,
+                err
+            ),
+        }
+    }
+    let mut hardware = HashMap::new();
+    let parsed = par_map(&hardware_xml, |(manufacturer, xml)| parse_hardware_data(manufacturer, xml));
+    for ((manufacturer, _), data) in hardware_xml.iter().zip(parsed) {
+        match data {
+            Ok(data) => {
+                hardware.insert(manufacturer.clone(), data);
+            }
+            Err(err) => log::warn!(
+                "Invalid hardware data for {} ({}), skipping details",
+                manufacturer,
+                err
+            ),
+        }
+    }
 
-fn ensure_hardware_data<'a, R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-    manufacturer: &str,
-    hardware_cache: &'a mut HashMap<String, HardwareData>,
-) -> Result<Option<&'a HardwareData>> {
-    if manufacturer.trim().is_empty() {
-        return Ok(None);
+    let mut app_ids = Vec::new();
+    let mut seen = HashSet::new();
+    let referencing_devices = if options.skip_app_programs { &[] } else { device_nodes };
+    for device_node in referencing_devices {
+        let app_id = device_node.attribute("Hardware2ProgramRefId").and_then(|hw| {
+            let manufacturer = manufacturer_id_from_ref(hw)?;
+            hardware.get(&manufacturer)?.hardware2program.get(hw)
+        });
+        if let Some(app_id) = app_id {
+            if seen.insert(app_id) {
+                app_ids.push(app_id.clone());
+            }
+        }
     }
 
-    if !hardware_cache.contains_key(manufacturer) {
-        let data = match load_hardware_data(zip, manufacturer) {
-            Ok(map) => map,
-            Err(err) => {
-                log::warn!(
-                    "Missing hardware data for {} ({}), skipping details",
-                    manufacturer,
-                    err
-                );
-                return Ok(None);
+    let mut app_xml = Vec::new();
+    let total = app_ids.len();
+    for (position, app_id) in app_ids.into_iter().enumerate() {
+        report(
+            options.progress,
+            ProgressEvent::AppProgram {
+                app_id: app_id.clone(),
+                loaded: position + 1,
+                total,
+            },
+        )?;
+        match archive.read_text(&app_program_path(&app_id), None) {
+            Ok(xml) => app_xml.push((app_id, xml)),
+            Err(err) => log::warn!("Missing app program {} ({})", app_id, err),
+        }
+    }
+    let (store, preferred_language) = (options.app_store, options.preferred_language);
+    let mut app_programs = HashMap::new();
+    let parsed = par_map(&app_xml, |(app_id, xml)| match store {
+        Some(store) => store.get_or_parse(app_id, preferred_language, xml),
+        None => parse_app_program(xml, app_id, preferred_language).map(Arc::new),
+    });
+    for ((app_id, _), program) in app_xml.iter().zip(parsed) {
+        match program {
+            Ok(program) => {
+                app_programs.insert(app_id.clone(), program);
             }
-        };
-        hardware_cache.insert(manufacturer.to_string(), data);
+            Err(err) => log::warn!("Invalid app program {} ({})", app_id, err),
+        }
     }
 
-    Ok(hardware_cache.get(manufacturer))
+    Ok(ProductCatalog {
+        hardware,
+        app_programs,
+    })
+}
+
+fn hardware_path(manufacturer: &str) -> String {
+    format!("{}/Hardware.xml", manufacturer)
 }
 
-fn load_hardware_data<R: Read + Seek>(
-    zip: &mut ZipArchive<R>,
-    manufacturer: &str,
-) -> Result<HardwareData> {
-    let path = format!("{}/Hardware.xml", manufacturer);
-    let xml = read_zip_entry(zip, &path)?;
+fn parse_hardware_data(manufacturer: &str, xml: &str) -> Result<HardwareData> {
+    let path = hardware_path(manufacturer);
     let doc =
-        Document::parse(strip_bom(&xml)).with_context(|| format!("Failed to parse {}", path))?;
+        Document::parse(strip_bom(xml)).with_context(|| format!("Failed to parse {}", path))?;
 
     let mut hardware2program = HashMap::new();
     let mut products = HashMap::new();
@@ -1,21 +1,20 @@
 use anyhow::Result;
-use roxmltree::Document;
 use std::collections::HashMap;
 
-use crate::knx::address::{parse_group_address_style, GroupAddress, GroupAddressStyle};
+use crate::knx::address::{GroupAddress, GroupAddressStyle};
 use crate::knx::model::GroupAddressInfo;
 use crate::knx::xml_tags;
-use crate::knx::xml_utils::{attr_value, find_elements_by_tag, required_attribute, ParseError, short_id};
+use crate::knx::xml_index::XmlIndex;
+use crate::knx::xml_utils::{attr_value, required_attribute, ParseError, short_id};
 
 pub fn extract_group_addresses(
-    doc: &Document,
-    style: Option<&str>,
+    index: &XmlIndex,
+    style: GroupAddressStyle,
 ) -> Result<(Vec<GroupAddressInfo>, HashMap<String, GroupAddressInfo>)> {
-    let parsed_style = style.map(parse_group_address_style).unwrap_or(GroupAddressStyle::ThreeLevel);
     let mut group_addresses = Vec::new();
     let mut by_id = HashMap::new();
 
-    for group in find_elements_by_tag(doc, xml_tags::GROUP_ADDRESS) {
+    for &group in index.elements(xml_tags::GROUP_ADDRESS) {
         let mut ranges: Vec<_> = group
             .ancestors()
             .filter(|n| n.tag_name().name() == xml_tags::GROUP_RANGE)
@@ -73,7 +72,7 @@ pub fn extract_group_addresses(
                 continue;
             }
         };
-        let address = GroupAddress::with_style(address_value, parsed_style).to_string();
+        let address = GroupAddress::with_style(address_value, style).to_string();
         let name = group.attribute("Name").unwrap_or("").to_string();
         let datapoint_type = group.attribute("DatapointType").map(|s| s.to_string());
         let security = attr_value(&group, "Security");
@@ -108,6 +107,8 @@ pub fn extract_group_addresses(
 #[cfg(test)]
 mod tests {
     use super::extract_group_addresses;
+    use crate::knx::address::GroupAddressStyle;
+    use crate::knx::xml_index::XmlIndex;
 
     #[test]
     fn skips_malformed_group_addresses() -> anyhow::Result<()> {
@@ -121,7 +122,7 @@ mod tests {
         </KNX>
         "#;
         let doc = roxmltree::Document::parse(xml)?;
-        let (groups, by_id) = extract_group_addresses(&doc, None)?;
+        let (groups, by_id) = extract_group_addresses(&XmlIndex::new(&doc), GroupAddressStyle::ThreeLevel)?;
         assert_eq!(groups.len(), 1);
         assert_eq!(by_id.len(), 1);
         assert_eq!(groups[0].name, "Valid");
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use roxmltree::{Document, Node};
+use roxmltree::Node;
 
 use crate::knx::app_model::{
     AppProgram,
@@ -11,19 +11,17 @@ use crate::knx::app_model::{
 };
 use crate::knx::model::DeviceConfigEntry;
 use crate::knx::parsers::translations::{attr_value_localized, strip_prefix};
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::{attr_value, short_id};
 
 pub(crate) fn parse_parameter_definitions(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, ParameterDef> {
     let mut parameters = HashMap::new();
-    for param in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PARAMETER)
-    {
+    for &param in index.elements(xml_tags::PARAMETER) {
         let id = match param.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -43,15 +41,12 @@ pub(crate) fn parse_parameter_definitions(
 }
 
 pub(crate) fn parse_parameter_types(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, ParameterTypeDef> {
     let mut types = HashMap::new();
-    for param_type in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PARAMETER_TYPE)
-    {
+    for &param_type in index.elements(xml_tags::PARAMETER_TYPE) {
         let id = match param_type.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -104,15 +99,12 @@ pub(crate) fn parse_parameter_types(
 }
 
 pub(crate) fn parse_parameter_refs(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, ParameterRefDef> {
     let mut refs = HashMap::new();
-    for param_ref in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PARAMETER_REF)
-    {
+    for &param_ref in index.elements(xml_tags::PARAMETER_REF) {
         let id = match param_ref.attribute("Id") {
             Some(id) => id,
             None => continue,
@@ -139,15 +131,12 @@ pub(crate) fn parse_parameter_refs(
 }
 
 pub(crate) fn parse_parameter_ref_context(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     translations: &HashMap<String, HashMap<String, String>>,
 ) -> HashMap<String, String> {
     let mut contexts = HashMap::new();
-    for node in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PARAMETER_REF_REF)
-    {
+    for &node in index.elements(xml_tags::PARAMETER_REF_REF) {
         let ref_id = match node.attribute("RefId") {
             Some(id) => id,
             None => continue,
@@ -270,15 +259,13 @@ fn lookup_translation_key(
 
 pub(crate) fn extract_device_configuration(
     device_node: &Node<'_, '_>,
+    index: &XmlIndex,
     app: Option<&AppProgram>,
 ) -> (HashMap<String, String>, Vec<DeviceConfigEntry>) {
     let mut configuration = HashMap::new();
     let mut configuration_entries = Vec::new();
 
-    for param_ref in device_node
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PARAMETER_INSTANCE_REF)
-    {
+    for &param_ref in index.descendants_by_tag(*device_node, xml_tags::PARAMETER_INSTANCE_REF) {
         let ref_id = param_ref.attribute("RefId").unwrap_or("");
         let value = param_ref.attribute("Value").unwrap_or("");
         if ref_id.is_empty() || value.is_empty() {
@@ -302,10 +289,7 @@ pub(crate) fn extract_device_configuration(
         });
     }
 
-    for property in device_node
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::PROPERTY)
-    {
+    for &property in index.descendants_by_tag(*device_node, xml_tags::PROPERTY) {
         let id = property.attribute("Id").unwrap_or("");
         let value = property.attribute("Value").unwrap_or("");
 
@@ -1,6 +1,7 @@
 use roxmltree::Document;
 
 use crate::knx::model::{ProjectAttachment, ProjectHistoryEntry, ProjectInfo, ProjectTag};
+use crate::knx::schema::EtsGeneration;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::{attr_value, find_child_element};
 
@@ -13,6 +14,19 @@ pub fn extract_project_name(doc: &Document) -> String {
         .to_string()
 }
 
+/// Schema version of a project document, from the `KNX` root namespace
+/// (`http://knx.org/xml/project/21` is version 21).
+pub fn extract_schema_version(doc: &Document) -> Option<u32> {
+    doc.root_element()
+        .tag_name()
+        .namespace()?
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
 pub fn extract_project_info(doc: &Document) -> Option<ProjectInfo> {
     let mut info = ProjectInfo::default();
     let mut has_any = false;
@@ -100,6 +114,15 @@ pub fn extract_project_info(doc: &Document) -> Option<ProjectInfo> {
         }
     }
 
+    let root = doc.root_element();
+    info.schema_version = extract_schema_version(doc);
+    info.ets_generation = info.schema_version.map(EtsGeneration::from_schema_version);
+    info.created_by = attr_value(&root, "CreatedBy");
+    info.tool_version = attr_value(&root, "ToolVersion");
+    if info.schema_version.is_some() || info.created_by.is_some() || info.tool_version.is_some() {
+        has_any = true;
+    }
+
     if let Some(installation) = doc
         .descendants()
         .find(|node| node.tag_name().name() == xml_tags::INSTALLATION)
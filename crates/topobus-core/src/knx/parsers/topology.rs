@@ -1,15 +1,15 @@
 use anyhow::Result;
-use roxmltree::Document;
 
 use crate::knx::model::{AreaInfo, LineInfo};
 use crate::knx::xml_tags;
-use crate::knx::xml_utils::{attr_value, find_elements_by_tag, medium_name, required_ancestor_address, required_attribute};
+use crate::knx::xml_index::XmlIndex;
+use crate::knx::xml_utils::{attr_value, medium_name, required_ancestor_address, required_attribute};
 
-pub fn extract_topology_metadata(doc: &Document) -> Result<(Vec<AreaInfo>, Vec<LineInfo>)> {
+pub fn extract_topology_metadata(index: &XmlIndex) -> Result<(Vec<AreaInfo>, Vec<LineInfo>)> {
     let mut areas = Vec::new();
     let mut lines = Vec::new();
 
-    for area in find_elements_by_tag(doc, xml_tags::AREA) {
+    for &area in index.elements(xml_tags::AREA) {
         let address = match required_attribute(&area, "Address") {
             Ok(value) => value,
             Err(error) => {
@@ -26,7 +26,7 @@ pub fn extract_topology_metadata(doc: &Document) -> Result<(Vec<AreaInfo>, Vec<L
         });
     }
 
-    for line in find_elements_by_tag(doc, xml_tags::LINE) {
+    for &line in index.elements(xml_tags::LINE) {
         let address = match required_attribute(&line, "Address") {
             Ok(value) => value,
             Err(error) => {
@@ -1,6 +1,6 @@
-use roxmltree::Document;
 use std::collections::HashMap;
 
+use crate::knx::xml_index::XmlIndex;
 use crate::knx::xml_tags;
 use crate::knx::xml_utils::attr_value;
 
@@ -30,29 +30,28 @@ pub(crate) fn attr_value_localized(
 }
 
 pub(crate) fn build_translations(
-    doc: &Document,
+    index: &XmlIndex,
     prefix: &str,
     preferred_language: Option<&str>,
 ) -> HashMap<String, HashMap<String, String>> {
     let mut map = HashMap::new();
-    let language_order = select_language_order(doc, preferred_language);
+    let language_order = select_language_order(index, preferred_language);
     if language_order.is_empty() {
         return map;
     }
 
     for language in language_order {
-        let language_node = doc
-            .descendants()
-            .find(|node| {
-                node.tag_name().name() == xml_tags::LANGUAGE
-                    && node.attribute("Identifier") == Some(language.as_str())
-            });
+        let language_node = index
+            .elements(xml_tags::LANGUAGE)
+            .iter()
+            .copied()
+            .find(|node| node.attribute("Identifier") == Some(language.as_str()));
         let language_node = match language_node {
             Some(node) => node,
             None => continue,
         };
 
-        let lang_map = collect_language_translations(&language_node, prefix);
+        let lang_map = collect_language_translations(index, &language_node, prefix);
         for (key, attrs) in lang_map {
             let entry = map.entry(key).or_insert_with(HashMap::new);
             for (attr_name, text) in attrs {
@@ -65,14 +64,12 @@ pub(crate) fn build_translations(
 }
 
 fn collect_language_translations(
+    index: &XmlIndex,
     language_node: &roxmltree::Node,
     prefix: &str,
 ) -> HashMap<String, HashMap<String, String>> {
     let mut map = HashMap::new();
-    for unit in language_node
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::TRANSLATION_UNIT)
-    {
+    for &unit in index.descendants_by_tag(*language_node, xml_tags::TRANSLATION_UNIT) {
         for element in unit
             .children()
             .filter(|n| n.is_element() && n.tag_name().name() == xml_tags::TRANSLATION_ELEMENT)
@@ -100,12 +97,9 @@ fn collect_language_translations(
     map
 }
 
-fn select_language_order(doc: &Document, preferred_language: Option<&str>) -> Vec<String> {
+fn select_language_order(index: &XmlIndex, preferred_language: Option<&str>) -> Vec<String> {
     let mut ids = Vec::new();
-    for node in doc
-        .descendants()
-        .filter(|n| n.tag_name().name() == xml_tags::LANGUAGE)
-    {
+    for &node in index.elements(xml_tags::LANGUAGE) {
         let id = match node.attribute("Identifier") {
             Some(value) => value.trim(),
             None => continue,
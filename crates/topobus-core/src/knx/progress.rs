@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+/// A step of project loading, reported to a [`ProgressObserver`] as it starts.
+///
+/// Each event but [`ProgressEvent::Hardware`] and [`ProgressEvent::AppProgram`] starts a phase
+/// that lasts until the next one; those two are steps within [`ProgressEvent::Devices`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Deriving the archive password from the project password (PBKDF2).
+    DerivingKey,
+    /// Finding and parsing `project.xml` and `0.xml` inside the archive.
+    LocatingDocuments,
+    /// Reading areas and lines.
+    Topology,
+    /// Reading group addresses.
+    GroupAddresses,
+    /// Reading devices, with their hardware and application programs.
+    Devices,
+    /// Loading the hardware catalog of a manufacturer.
+    Hardware { manufacturer: String },
+    /// Reading an application program; `loaded` counts programs read so far, including this one,
+    /// out of the `total` referenced by the project's devices.
+    AppProgram {
+        app_id: String,
+        loaded: usize,
+        total: usize,
+    },
+    /// Reading the building structure.
+    Locations,
+    /// Loading finished.
+    Done {
+        devices: usize,
+        group_addresses: usize,
+    },
+}
+
+impl ProgressEvent {
+    /// The `phase` this event is serialized with.
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Self::DerivingKey => "deriving_key",
+            Self::LocatingDocuments => "locating_documents",
+            Self::Topology => "topology",
+            Self::GroupAddresses => "group_addresses",
+            Self::Devices => "devices",
+            Self::Hardware { .. } => "hardware",
+            Self::AppProgram { .. } => "app_program",
+            Self::Locations => "locations",
+            Self::Done { .. } => "done",
+        }
+    }
+
+    /// Whether the event starts a phase, rather than a step within the current one.
+    pub fn starts_phase(&self) -> bool {
+        !matches!(self, Self::Hardware { .. } | Self::AppProgram { .. })
+    }
+}
+
+/// Receives [`ProgressEvent`]s while a project is loading.
+///
+/// Closures taking `&ProgressEvent` implement this trait.
+pub trait ProgressObserver {
+    fn on_progress(&self, event: &ProgressEvent);
+
+    /// Return `true` to abort loading at the next phase boundary with [`ParseCancelledError`].
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(&ProgressEvent)> ProgressObserver for F {
+    fn on_progress(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+/// Observer that ignores every event.
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {
+    fn on_progress(&self, _event: &ProgressEvent) {}
+}
+
+#[derive(Debug)]
+pub struct ParseCancelledError;
+
+impl std::fmt::Display for ParseCancelledError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "KNX project loading was cancelled")
+    }
+}
+
+impl std::error::Error for ParseCancelledError {}
+
+/// Report `event` and fail with [`ParseCancelledError`] if the observer asked to stop.
+pub(crate) fn report(observer: &dyn ProgressObserver, event: ProgressEvent) -> anyhow::Result<()> {
+    if observer.is_cancelled() {
+        return Err(ParseCancelledError.into());
+    }
+    observer.on_progress(&event);
+    Ok(())
+}
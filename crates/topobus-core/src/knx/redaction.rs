@@ -0,0 +1,123 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Placeholder for a secret left out of the output.
+pub const REDACTED: &str = "[redacted]";
+
+/// Whether project secrets (tracing password, BCU key, group address keys) leave the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionPolicy {
+    /// Replace secrets with [`REDACTED`], keeping whether the project has them.
+    #[default]
+    Mask,
+    /// Keep secrets as stored in the project.
+    Include,
+}
+
+impl RedactionPolicy {
+    pub fn include_secrets(include: bool) -> Self {
+        if include {
+            Self::Include
+        } else {
+            Self::Mask
+        }
+    }
+}
+
+thread_local! {
+    /// Policy of the innermost [`Redacted`] being serialized on this thread.
+    static POLICY: Cell<RedactionPolicy> = const { Cell::new(RedactionPolicy::Mask) };
+}
+
+/// A value serialized with `policy` applied to the secrets in it.
+///
+/// Secrets are masked whenever a value is serialized outside of this wrapper, so parsed data can
+/// keep them without any output path leaking them by accident.
+#[derive(Debug, Clone, Copy)]
+pub struct Redacted<T> {
+    value: T,
+    policy: RedactionPolicy,
+}
+
+impl<T> Redacted<T> {
+    pub fn new(value: T, policy: RedactionPolicy) -> Self {
+        Self { value, policy }
+    }
+}
+
+impl<T: Serialize> Serialize for Redacted<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let previous = POLICY.replace(self.policy);
+        // Restores the outer policy even if serialization panics.
+        struct Restore(RedactionPolicy);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                POLICY.set(self.0);
+            }
+        }
+        let _restore = Restore(previous);
+        self.value.serialize(serializer)
+    }
+}
+
+/// `serialize_with` for fields holding a secret: replaced with [`REDACTED`], unless serialized
+/// in a [`Redacted`] that includes secrets. Empty values stay empty.
+pub(crate) fn secret<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(secret) if !secret.is_empty() && POLICY.get() == RedactionPolicy::Mask => {
+            serializer.serialize_some(REDACTED)
+        }
+        _ => value.serialize(serializer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Redacted, RedactionPolicy, REDACTED};
+    use crate::knx::model::{GroupAddressInfo, KnxProjectData, ProjectInfo};
+
+    fn project() -> KnxProjectData {
+        KnxProjectData {
+            project_info: Some(ProjectInfo {
+                project_tracing_password: Some("tracing".to_string()),
+                bcu_key: Some(String::new()),
+                description: Some("House".to_string()),
+                ..Default::default()
+            }),
+            group_addresses: vec![
+                GroupAddressInfo {
+                    security_key: Some("c2VjcmV0".to_string()),
+                    ..Default::default()
+                },
+                GroupAddressInfo::default(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn masks_secrets_unless_included() {
+        let project = project();
+        for masked in [
+            serde_json::to_value(&project).unwrap(),
+            serde_json::to_value(Redacted::new(&project, RedactionPolicy::default())).unwrap(),
+        ] {
+            let info = &masked["project_info"];
+            assert_eq!(info["project_tracing_password"], REDACTED);
+            assert_eq!(info["bcu_key"], "");
+            assert_eq!(info["description"], "House");
+            assert_eq!(masked["group_addresses"][0]["security_key"], REDACTED);
+            assert!(masked["group_addresses"][1].get("security_key").is_none());
+        }
+
+        let included = serde_json::to_value(Redacted::new(&project, RedactionPolicy::Include)).unwrap();
+        assert_eq!(included["group_addresses"][0]["security_key"], "c2VjcmV0");
+        assert_eq!(included["project_info"]["project_tracing_password"], "tracing");
+        // The parsed data itself is left complete.
+        assert_eq!(project.group_addresses[0].security_key.as_deref(), Some("c2VjcmV0"));
+        // Leaving the wrapper restores masking.
+        assert_eq!(serde_json::to_value(&project).unwrap()["group_addresses"][0]["security_key"], REDACTED);
+    }
+}
@@ -0,0 +1,250 @@
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+
+use crate::knx::xml_tags;
+use crate::knx::xml_utils::short_id;
+
+/// Newest project schema this crate has been checked against (ETS 6.2).
+const LATEST_KNOWN_SCHEMA: u32 = 23;
+
+/// Oldest project schema this crate can read (ETS 4.0); ETS3 did not write `.knxproj` files.
+const OLDEST_SUPPORTED_SCHEMA: u32 = 11;
+
+/// The project was written with a schema older than any ETS generation this crate reads.
+#[derive(Debug)]
+pub struct UnsupportedSchemaError {
+    pub schema_version: u32,
+}
+
+impl std::fmt::Display for UnsupportedSchemaError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            fmt,
+            "Unsupported project schema {} (ETS 4 or later is required)",
+            self.schema_version
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedSchemaError {}
+
+/// ETS generation that wrote a project, from the version of its `http://knx.org/xml/project/NN`
+/// namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EtsGeneration {
+    /// Schemas 11 and 12 (ETS 4.0 to 4.2).
+    Ets4,
+    /// Schemas 13 to 20 (ETS 5.0 to 5.7).
+    Ets5,
+    /// Schemas 21 and later.
+    Ets6,
+}
+
+impl EtsGeneration {
+    pub fn from_schema_version(version: u32) -> Self {
+        match version {
+            0..=12 => EtsGeneration::Ets4,
+            13..=20 => EtsGeneration::Ets5,
+            _ => EtsGeneration::Ets6,
+        }
+    }
+}
+
+/// Reads the parts of `0.xml` whose vocabulary changed between ETS generations.
+pub(crate) trait SchemaAdapter: Sync {
+    /// Group address IDs a `ComObjectInstanceRef` is linked to, the sending one first.
+    fn com_object_links(&self, com_ref: Node) -> Vec<String>;
+
+    /// `RefId` of a `ComObjectInstanceRef` relative to its application program, e.g. `O-1_R-1`.
+    fn local_com_object_ref<'r>(&self, ref_id: &'r str) -> &'r str {
+        ref_id
+    }
+}
+
+/// ETS4 names each link in a `Connectors` element and refers to group objects by their full ID,
+/// application program included (`M-0083_A-0001-11-ABCD_O-1_R-1`).
+struct Ets4;
+
+/// ETS 5.0 to 5.6 write `Connectors`, ETS 5.7 switched to the `Links` attribute.
+struct Ets5;
+
+/// ETS6 writes the `Links` attribute, but projects upgraded from ETS5 can keep `Connectors`.
+struct Ets6;
+
+impl SchemaAdapter for Ets4 {
+    fn com_object_links(&self, com_ref: Node) -> Vec<String> {
+        connector_links(com_ref)
+    }
+
+    fn local_com_object_ref<'r>(&self, ref_id: &'r str) -> &'r str {
+        let mut parts = ref_id.splitn(3, '_');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(manufacturer), Some(app), Some(local))
+                if manufacturer.starts_with("M-") && app.starts_with("A-") =>
+            {
+                local
+            }
+            _ => ref_id,
+        }
+    }
+}
+
+impl SchemaAdapter for Ets5 {
+    fn com_object_links(&self, com_ref: Node) -> Vec<String> {
+        links_or_connectors(com_ref)
+    }
+}
+
+impl SchemaAdapter for Ets6 {
+    fn com_object_links(&self, com_ref: Node) -> Vec<String> {
+        links_or_connectors(com_ref)
+    }
+}
+
+/// Adapter for a project schema version.
+///
+/// Projects without a recognisable namespace are read as ETS5, which accepts both link
+/// vocabularies.
+pub(crate) fn schema_adapter(
+    schema_version: Option<u32>,
+) -> Result<&'static dyn SchemaAdapter, UnsupportedSchemaError> {
+    let Some(version) = schema_version else {
+        log::warn!("Unknown project schema version, reading it as ETS5");
+        return Ok(&Ets5);
+    };
+    if version < OLDEST_SUPPORTED_SCHEMA {
+        return Err(UnsupportedSchemaError {
+            schema_version: version,
+        });
+    }
+    if version > LATEST_KNOWN_SCHEMA {
+        log::warn!(
+            "Project schema {} is newer than the latest known ({}), reading it as ETS6",
+            version,
+            LATEST_KNOWN_SCHEMA
+        );
+    }
+    Ok(match EtsGeneration::from_schema_version(version) {
+        EtsGeneration::Ets4 => &Ets4,
+        EtsGeneration::Ets5 => &Ets5,
+        EtsGeneration::Ets6 => &Ets6,
+    })
+}
+
+fn links_or_connectors(com_ref: Node) -> Vec<String> {
+    let links = links_attribute(com_ref);
+    if links.is_empty() {
+        connector_links(com_ref)
+    } else {
+        links
+    }
+}
+
+fn links_attribute(com_ref: Node) -> Vec<String> {
+    parse_links_attribute(com_ref.attribute("Links").unwrap_or(""))
+}
+
+fn parse_links_attribute(link_attr: &str) -> Vec<String> {
+    // `Links` is a whitespace-separated list of GroupAddressRefId values.
+    // We also accept commas for robustness (some exports/tools may add them).
+    link_attr
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .collect()
+}
+
+fn connector_links(com_ref: Node) -> Vec<String> {
+    let Some(connectors) = com_ref
+        .children()
+        .find(|node| node.is_element() && node.tag_name().name() == xml_tags::CONNECTORS)
+    else {
+        return Vec::new();
+    };
+    let mut ids = Vec::new();
+    for tag in [xml_tags::SEND, xml_tags::RECEIVE] {
+        for node in connectors
+            .children()
+            .filter(|n| n.is_element() && n.tag_name().name() == tag)
+        {
+            if let Some(ref_id) = node.attribute("GroupAddressRefId") {
+                let trimmed = ref_id.trim();
+                if !trimmed.is_empty() {
+                    ids.push(short_id(trimmed));
+                }
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_links_attribute, schema_adapter, EtsGeneration};
+    use crate::knx::ProjectLoader;
+    use std::path::Path;
+
+    #[test]
+    fn maps_schema_versions_to_generations() {
+        assert_eq!(EtsGeneration::from_schema_version(11), EtsGeneration::Ets4);
+        assert_eq!(EtsGeneration::from_schema_version(20), EtsGeneration::Ets5);
+        assert_eq!(EtsGeneration::from_schema_version(23), EtsGeneration::Ets6);
+        assert_eq!(EtsGeneration::from_schema_version(24), EtsGeneration::Ets6);
+
+        let ets4 = schema_adapter(Some(12)).unwrap();
+        assert_eq!(ets4.local_com_object_ref("M-0083_A-0001-11-ABCD_O-1_R-1"), "O-1_R-1");
+        assert_eq!(ets4.local_com_object_ref("O-1_R-1"), "O-1_R-1");
+        assert_eq!(schema_adapter(Some(10)).err().unwrap().schema_version, 10);
+    }
+
+    #[test]
+    fn reads_links_of_every_ets_generation() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        for (generation, schema) in [("ets4", 12), ("ets5", 14), ("ets6", 21), ("ets6-connectors", 21)] {
+            let project = ProjectLoader::from_directory(fixtures.join(generation))
+                .load()
+                .unwrap();
+            let info = project.project_info.as_ref().unwrap();
+            assert_eq!(info.schema_version, Some(schema), "{}", generation);
+            assert_eq!(info.group_address_style.as_deref(), Some("ThreeLevel"));
+
+            let links: Vec<_> = project.devices[0]
+                .group_links
+                .iter()
+                .map(|link| {
+                    (
+                        link.group_address.as_str(),
+                        link.number,
+                        link.datapoint_type.as_deref(),
+                        link.ets_sending,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                links,
+                [
+                    ("1/0/1", Some(1), Some("DPST-1-1"), true),
+                    ("1/0/2", Some(1), Some("DPST-1-1"), false),
+                    ("1/0/3", Some(2), Some("DPST-1-1"), true),
+                ],
+                "{}",
+                generation
+            );
+        }
+    }
+
+    #[test]
+    fn parse_links_attribute_preserves_order_and_splits_whitespace() {
+        let input = " G-1\nG-2\tG-3  G-4 ";
+        let out = parse_links_attribute(input);
+        assert_eq!(out, vec!["G-1", "G-2", "G-3", "G-4"]);
+    }
+
+    #[test]
+    fn parse_links_attribute_accepts_commas_without_reordering() {
+        let input = "G-1,G-2, G-3";
+        let out = parse_links_attribute(input);
+        assert_eq!(out, vec!["G-1", "G-2", "G-3"]);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+/// Element lookup tables built in a single pass over a document.
+///
+/// Extractors used to call `doc.descendants()` once per tag they needed, and once more per
+/// device for its module instances, group object references and parameters. The index walks the
+/// tree once; per-tag lists are in document order, so the elements of any subtree form a
+/// contiguous slice that [`XmlIndex::descendants_by_tag`] finds by binary search.
+pub struct XmlIndex<'a, 'input> {
+    by_tag: HashMap<&'a str, Vec<Node<'a, 'input>>>,
+    by_id: HashMap<&'a str, Node<'a, 'input>>,
+}
+
+impl<'a, 'input: 'a> XmlIndex<'a, 'input> {
+    pub fn new(doc: &'a Document<'input>) -> Self {
+        let mut by_tag: HashMap<&'a str, Vec<Node<'a, 'input>>> = HashMap::new();
+        let mut by_id = HashMap::new();
+        for node in doc.descendants().filter(Node::is_element) {
+            by_tag.entry(node.tag_name().name()).or_default().push(node);
+            if let Some(id) = node.attribute("Id") {
+                by_id.entry(id).or_insert(node);
+            }
+        }
+        Self { by_tag, by_id }
+    }
+
+    /// All elements with the given local name, in document order.
+    pub fn elements(&self, tag: &str) -> &[Node<'a, 'input>] {
+        self.by_tag.get(tag).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The first element carrying `Id="{id}"`.
+    pub fn element_by_id(&self, id: &str) -> Option<Node<'a, 'input>> {
+        self.by_id.get(id).copied()
+    }
+
+    /// Elements with the given local name strictly inside `node`, in document order.
+    pub fn descendants_by_tag(&self, node: Node<'a, 'input>, tag: &str) -> &[Node<'a, 'input>] {
+        let elements = self.elements(tag);
+        // Node ids follow document order, so a subtree spans the ids from the node itself to
+        // its deepest last descendant.
+        let first = node.id().get();
+        let last = last_descendant(node).id().get();
+        let start = elements.partition_point(|item| item.id().get() <= first);
+        let end = elements.partition_point(|item| item.id().get() <= last);
+        &elements[start..end.max(start)]
+    }
+}
+
+fn last_descendant<'a, 'input>(mut node: Node<'a, 'input>) -> Node<'a, 'input> {
+    while let Some(child) = node.last_child() {
+        node = child;
+    }
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XmlIndex;
+
+    const XML: &str = r#"<KNX>
+        <Device Id="D-1"><Ref Id="R-1"/><Group><Ref Id="R-2"/></Group></Device>
+        <Device Id="D-2"><Ref Id="R-3"/>text</Device>
+        <Device Id="D-3"/>
+        <Ref Id="R-4"/>
+    </KNX>"#;
+
+    fn ids(nodes: &[roxmltree::Node]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|node| node.attribute("Id").unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn matches_subtree_traversal() {
+        let doc = roxmltree::Document::parse(XML).unwrap();
+        let index = XmlIndex::new(&doc);
+        assert_eq!(ids(index.elements("Ref")), ["R-1", "R-2", "R-3", "R-4"]);
+        for device in index.elements("Device") {
+            let expected: Vec<_> = device
+                .descendants()
+                .filter(|node| node.tag_name().name() == "Ref")
+                .collect();
+            assert_eq!(index.descendants_by_tag(*device, "Ref"), expected.as_slice());
+        }
+        assert!(index.descendants_by_tag(doc.root_element(), "Missing").is_empty());
+        assert_eq!(
+            index.element_by_id("R-3").unwrap().parent().unwrap().attribute("Id"),
+            Some("D-2")
+        );
+    }
+}
@@ -125,6 +125,18 @@ pub fn format_individual_address(
     }
 }
 
+
+pub fn find_elements_by_tag<'a>(doc: &'a Document, tag: &str) -> impl Iterator<Item = Node<'a, 'a>> {
+    let tag_name = tag.to_string();
+    doc.descendants()
+        .filter(move |node| node.tag_name().name() == tag_name.as_str())
+}
+
+pub fn find_child_element<'a>(node: &Node<'a, 'a>, tag: &str) -> Option<Node<'a, 'a>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == tag)
+}
+
 #[cfg(test)]
 mod tests {
     use super::format_individual_address;
@@ -165,14 +177,3 @@ mod tests {
         assert_eq!(value, "1.-.2");
     }
 }
-
-pub fn find_elements_by_tag<'a>(doc: &'a Document, tag: &str) -> impl Iterator<Item = Node<'a, 'a>> {
-    let tag_name = tag.to_string();
-    doc.descendants()
-        .filter(move |node| node.tag_name().name() == tag_name.as_str())
-}
-
-pub fn find_child_element<'a>(node: &Node<'a, 'a>, tag: &str) -> Option<Node<'a, 'a>> {
-    node.children()
-        .find(|child| child.is_element() && child.tag_name().name() == tag)
-}
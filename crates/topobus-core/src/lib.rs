@@ -1,18 +1,34 @@
+pub mod export;
 pub mod graph;
 pub mod knx;
 
+pub use export::{render_csv, render_html_report, CsvOptions, CsvTable};
+#[cfg(feature = "sqlite")]
+pub use export::export_sqlite;
 pub use graph::{
     generate_group_address_graph, generate_topology_graph, Edge, EdgeKind, GraphModel, Node,
     NodeKind,
 };
 pub use knx::{
-    load_knxproj,
-    load_knxproj_bytes,
-    load_knxproj_bytes_with_language,
-    load_knxproj_with_language,
+    anonymize_knxproj,
+    inspect_knxproj,
+    project_cache_key,
+    AppProgramStore,
+    ArchiveLimitError,
+    ArchiveLimits,
+    GroupAddressStyle,
     InvalidPasswordError,
     KnxProjectData,
+    NoProgress,
+    ParseCancelledError,
     PasswordRequiredError,
+    ProgressEvent,
+    ProgressObserver,
+    ProjectLoader,
+    ProjectSummary,
+    Redacted,
+    RedactionPolicy,
+    UnsupportedSchemaError,
 };
 
 use serde::{Deserialize, Serialize};
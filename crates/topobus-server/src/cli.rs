@@ -1,16 +1,158 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct Args {
-    /// Path to .knxproj file (optional, can be uploaded via web interface)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub serve: ServeArgs,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the web server (default when no command is given)
+    Serve(ServeArgs),
+    /// Export a .knxproj file to another format
+    Export(ExportArgs),
+    /// Write a copy of a .knxproj file with names, comments, addresses and keys replaced, e.g.
+    /// to attach to a bug report
+    Anonymize(AnonymizeArgs),
+    /// Inspect the server configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration and report problems in it
+    Check {
+        /// Config file to check (defaults to $TOPOBUS_CONFIG, then ./topobus.toml)
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Read a password from stdin and print its hash for `[[auth.users]]`
+    HashPassword,
+}
+
+#[derive(ClapArgs, Debug, Clone, Default)]
+pub struct ServeArgs {
+    /// Path to a .knxproj file to load at startup (optional, projects can be uploaded via the web
+    /// interface)
     pub knxproj_path: Option<String>,
 
-    /// Port to serve on
-    #[arg(short, long, default_value_t = 8080)]
-    pub port: u16,
+    /// Config file (defaults to $TOPOBUS_CONFIG, then ./topobus.toml when present)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Address to listen on; repeat to listen on several [default: 127.0.0.1]
+    #[arg(long, value_name = "ADDRESS")]
+    pub bind: Vec<String>,
+
+    /// Port to serve on [default: 8080]
+    #[arg(short, long)]
+    pub port: Option<u16>,
 
     /// Do not auto-open the browser
     #[arg(long)]
     pub no_browser: bool,
+
+    /// Allow listening on non-loopback addresses without authentication
+    #[arg(long)]
+    pub allow_unauthenticated: bool,
+
+    /// Include project secrets (tracing password, BCU key, group address keys) in API responses
+    #[arg(long)]
+    pub include_secrets: bool,
+
+    /// Serve the .knxproj files in this directory as a library, re-parsing them when they change
+    #[arg(long, value_name = "DIR")]
+    pub watch: Option<PathBuf>,
+
+    /// Keep uploaded projects in this directory, encrypted, in a store shared by all users
+    #[arg(long, value_name = "DIR")]
+    pub store: Option<PathBuf>,
+
+    /// Serve HTTPS with this PEM certificate chain (requires --tls-key)
+    #[arg(long, value_name = "PEM")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Private key of the --tls-cert certificate, in PEM
+    #[arg(long, value_name = "PEM")]
+    pub tls_key: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ExportArgs {
+    /// Path to the .knxproj file or extracted project directory to export, or '-' to read the
+    /// archive from stdin
+    pub knxproj_path: String,
+
+    /// Output format
+    #[arg(short, long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Output file (defaults to the project file name with the format extension)
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Project password for encrypted projects
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Preferred language for application program texts (e.g. "de-DE")
+    #[arg(long)]
+    pub language: Option<String>,
+
+    /// CSV only: export a single table instead of all of them
+    #[arg(long, value_enum)]
+    pub table: Option<CsvTableArg>,
+
+    /// CSV only: field separator (use ';' for French/German Excel)
+    #[arg(long, default_value_t = ',')]
+    pub separator: char,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct AnonymizeArgs {
+    /// Path to the .knxproj file to anonymize, or '-' to read the archive from stdin
+    pub input: String,
+
+    /// Anonymized .knxproj file to write
+    pub output: PathBuf,
+
+    /// Project password for encrypted projects; the copy is written without one
+    #[arg(long)]
+    pub password: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Printable single-file HTML report
+    Html,
+    /// Normalized SQLite database
+    Sqlite,
+    /// Flat CSV tables (devices, group addresses, associations, parameters)
+    Csv,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvTableArg {
+    Devices,
+    GroupAddresses,
+    Associations,
+    Parameters,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Sqlite => "sqlite",
+            ExportFormat::Csv => "csv",
+        }
+    }
 }
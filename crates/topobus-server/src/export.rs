@@ -0,0 +1,120 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use topobus_core::{
+    anonymize_knxproj,
+    export_sqlite,
+    render_csv,
+    render_html_report,
+    CsvOptions,
+    CsvTable,
+    KnxProjectData,
+    ProjectLoader,
+};
+
+use crate::cli::{AnonymizeArgs, CsvTableArg, ExportArgs, ExportFormat};
+
+/// Input path that stands for stdin.
+const STDIN_PATH: &str = "-";
+
+pub fn run_export(args: ExportArgs) -> Result<()> {
+    let stdin_data;
+    let loader = if args.knxproj_path == STDIN_PATH {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read project from stdin")?;
+        stdin_data = data;
+        ProjectLoader::from_bytes(&stdin_data)
+    } else {
+        ProjectLoader::from_path(&args.knxproj_path)
+    };
+    let project = loader
+        .password(args.password.as_deref())
+        .language(args.language.as_deref())
+        .load()?;
+    let output = args
+        .output
+        .clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| default_output_path(&args.knxproj_path, args.format));
+
+    match args.format {
+        ExportFormat::Html => write_file(&output, render_html_report(&project))?,
+        ExportFormat::Sqlite => export_sqlite(&project, &output)?,
+        ExportFormat::Csv => export_csv(&project, &args, &output)?,
+    }
+
+    log::info!("Exported {} to {}", args.knxproj_path, output.display());
+    Ok(())
+}
+
+pub fn run_anonymize(args: AnonymizeArgs) -> Result<()> {
+    let data = if args.input == STDIN_PATH {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read project from stdin")?;
+        data
+    } else {
+        std::fs::read(&args.input).with_context(|| format!("Failed to read {}", args.input))?
+    };
+    let anonymized = anonymize_knxproj(&data, args.password.as_deref())?;
+    std::fs::write(&args.output, anonymized)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    log::info!("Anonymized {} to {}", args.input, args.output.display());
+    Ok(())
+}
+
+fn export_csv(project: &KnxProjectData, args: &ExportArgs, output: &Path) -> Result<()> {
+    let options = CsvOptions {
+        separator: args.separator,
+        ..CsvOptions::default()
+    };
+    match args.table {
+        Some(table) => {
+            let table = csv_table(table);
+            let path = if args.output.is_some() {
+                output.to_path_buf()
+            } else {
+                table_path(output, table)
+            };
+            write_file(&path, render_csv(project, table, &options))
+        }
+        None => {
+            for table in CsvTable::ALL {
+                write_file(&table_path(output, table), render_csv(project, table, &options))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn csv_table(table: CsvTableArg) -> CsvTable {
+    match table {
+        CsvTableArg::Devices => CsvTable::Devices,
+        CsvTableArg::GroupAddresses => CsvTable::GroupAddresses,
+        CsvTableArg::Associations => CsvTable::Associations,
+        CsvTableArg::Parameters => CsvTable::Parameters,
+    }
+}
+
+/// `project.csv` -> `project_devices.csv`
+fn table_path(output: &Path, table: CsvTable) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".to_string());
+    output.with_file_name(format!("{}_{}.csv", stem, table.file_stem()))
+}
+
+fn write_file(path: &Path, contents: String) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn default_output_path(input: &str, format: ExportFormat) -> PathBuf {
+    let input = if input == STDIN_PATH { "project" } else { input };
+    Path::new(input.trim_end_matches(['/', '\\'])).with_extension(format.extension())
+}
@@ -0,0 +1,38 @@
+//! The TopoBus web server: HTTP API and bundled frontend.
+//!
+//! The `topobus` binary serves [`router`] on its own, but it can also be mounted in another
+//! axum application, behind that application's own layers:
+//!
+//! ```no_run
+//! use axum::Router;
+//! use topobus_server::ServerConfig;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let project = topobus_core::ProjectLoader::from_path("house.knxproj").load()?;
+//! let config = ServerConfig::builder()
+//!     .max_sessions(32)
+//!     .project("house", project)
+//!     .build()?;
+//! let app = Router::new().nest("/topobus", topobus_server::router(config)?);
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//! axum::serve(listener, app).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The frontend uses relative URLs; the mount point itself (`/topobus`) redirects to
+//! `/topobus/index.html`.
+//!
+//! The router only authenticates requests when the config has tokens or users, and adds no
+//! request ids or tracing spans; those are up to the application around it. Its auth layer can
+//! insert a [`Principal`] in the request extensions to tell TopoBus who the user is.
+
+pub mod cli;
+pub mod export;
+mod server;
+
+pub use server::{
+    check_config, hash_password, router, start_server, AuthConfig, AuthUser, LoadedProject,
+    PreloadProject, Principal, ServerConfig, ServerConfigBuilder, StoreConfig, TlsConfig,
+    WatchPasswords,
+};
@@ -1,17 +1,40 @@
-mod cli;
-mod server;
+use std::io::IsTerminal;
 
 use anyhow::Result;
 use clap::Parser;
-use env_logger::Env;
+use tracing_subscriber::EnvFilter;
+use topobus_server::cli::{self, Command, ConfigCommand, ServeArgs};
+use topobus_server::export;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    // `RUST_LOG` as before; `log` records, e.g. from topobus-core, are forwarded to tracing so
+    // they carry the span of the request they belong to. Logs go to stderr: exports may use stdout.
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
 
     let args = cli::Args::parse();
 
-    log::info!("Starting TopoBus on port {}", args.port);
+    match args.command {
+        Some(Command::Export(export_args)) => export::run_export(export_args),
+        Some(Command::Anonymize(anonymize_args)) => export::run_anonymize(anonymize_args),
+        Some(Command::Serve(serve_args)) => serve(serve_args).await,
+        Some(Command::Config(ConfigCommand::Check { config })) => {
+            topobus_server::check_config(ServeArgs {
+                config,
+                ..ServeArgs::default()
+            })
+        }
+        Some(Command::Config(ConfigCommand::HashPassword)) => topobus_server::hash_password(),
+        None => serve(args.serve).await,
+    }
+}
+
+async fn serve(args: ServeArgs) -> Result<()> {
+    log::info!("Starting TopoBus");
 
-    server::start_server(args).await
+    topobus_server::start_server(args).await
 }
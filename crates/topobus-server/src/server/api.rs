@@ -1,29 +1,124 @@
-use axum::{extract::{Multipart, State}, http::StatusCode, Json};
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 
 use topobus_core::{
     build_project_graphs,
-    load_knxproj_bytes_with_language,
-    InvalidPasswordError,
+    ArchiveLimitError,
+    ArchiveLimits,
+    inspect_knxproj,
+    project_cache_key,
+    KnxProjectData,
     PasswordRequiredError,
-    ProjectGraphs,
+    ProgressEvent,
+    ProjectLoader,
+    ProjectSummary,
+    Redacted,
 };
-use crate::server::config::ServerConfig;
+use crate::server::auth::Principal;
+use crate::server::error::{ApiError, ErrorCode};
+use crate::server::jobs::{is_valid_job_id, JobEvent, JobObserver};
+use crate::server::metrics::PhaseTimer;
+use crate::server::parse_pool::PoolError;
+use crate::server::state::AppState;
 use crate::server::validation::{FileValidator, ValidationError};
 
+/// Delay suggested to clients rejected because the parse queue is full.
+const RETRY_AFTER_SECS: u64 = 5;
+
 pub async fn health_check() -> &'static str {
     "OK"
 }
 
 pub async fn handle_upload(
-    State(config): State<ServerConfig>,
-    mut multipart: Multipart,
-) -> Result<Json<ProjectGraphs>, (StatusCode, String)> {
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    multipart: Multipart,
+) -> Result<Response, ApiError> {
     log::info!("Received file upload request");
 
+    let principal = principal.map(|Extension(principal)| principal);
+    let form = read_upload_form(&state, principal.as_ref(), multipart).await?;
+    if !state.graph_cache.is_enabled() {
+        let project_data = parse_upload(&state, form).await?;
+        let graphs = build_project_graphs(&project_data);
+        return Ok(Json(Redacted::new(graphs, state.config.redaction)).into_response());
+    }
+
+    // Hashing and key derivation are CPU-bound, like a disk cache lookup is blocking.
+    let lookup = {
+        let cache = state.graph_cache.clone();
+        let data = form.data.clone();
+        let password = form.password.clone();
+        let language = form.preferred_language.clone();
+        tokio::task::spawn_blocking(move || {
+            let key = project_cache_key(&data, password.as_deref(), language.as_deref());
+            let hit = cache.get(&key);
+            (key, hit)
+        })
+    };
+    let (key, hit) = lookup.await.map_err(|_| {
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Cache lookup failed")
+    })?;
+    state.metrics.record_graph_cache(hit.is_some());
+
+    if let Some(graphs) = hit {
+        log::info!("Serving {} from project cache", form.filename);
+        state.metrics.record_upload("cached", form.data.len());
+        if let Some(id) = form.job_id.as_deref() {
+            state.jobs.publish(
+                id,
+                JobEvent::Progress(ProgressEvent::Done {
+                    devices: graphs.devices.len(),
+                    group_addresses: graphs.group_addresses.len(),
+                }),
+            );
+        }
+        return Ok(Json(Redacted::new(graphs.as_ref(), state.config.redaction)).into_response());
+    }
+
+    let project_data = parse_upload(&state, form).await?;
+    let graphs = Arc::new(build_project_graphs(&project_data));
+    let cache = state.graph_cache.clone();
+    let stored = graphs.clone();
+    // Best effort: a failed cache write must not fail the upload.
+    tokio::task::spawn_blocking(move || cache.insert(&key, stored));
+
+    Ok(Json(Redacted::new(graphs.as_ref(), state.config.redaction)).into_response())
+}
+
+/// Fields of the multipart upload form shared by the upload endpoints.
+pub struct UploadForm {
+    pub filename: String,
+    pub data: axum::body::Bytes,
+    pub password: Option<String>,
+    pub preferred_language: Option<String>,
+    /// Job from `POST /api/jobs` to stream progress on `/api/jobs/{id}/events`.
+    pub job_id: Option<String>,
+    /// Comma-separated `tags` fields, for `/api/store`.
+    pub tags: Vec<String>,
+}
+
+/// Read the upload form; the configured default language applies when the form names none.
+///
+/// A `job_id` must be a job `principal` created.
+pub async fn read_upload_form(
+    state: &AppState,
+    principal: Option<&Principal>,
+    mut multipart: Multipart,
+) -> Result<UploadForm, ApiError> {
     let mut filename = None;
     let mut data = None;
     let mut password: Option<String> = None;
     let mut preferred_language: Option<String> = None;
+    let mut job_id: Option<String> = None;
+    let mut tags: Vec<String> = Vec::new();
 
     while let Some(field) = multipart.next_field().await.map_err(|e| {
         (
@@ -62,6 +157,32 @@ pub async fn handle_upload(
             if !value.is_empty() {
                 preferred_language = Some(value);
             }
+        } else if name == "job_id" {
+            let value = field.text().await.map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read job id: {}", e),
+                )
+            })?;
+            let value = value.trim().to_string();
+            if !is_valid_job_id(&value) || !state.jobs.is_owned_by(&value, principal) {
+                return Err(ApiError::new(StatusCode::BAD_REQUEST, "Invalid job id"));
+            }
+            job_id = Some(value);
+        } else if name == "tags" {
+            let value = field.text().await.map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read tags: {}", e),
+                )
+            })?;
+            tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string),
+            );
         }
     }
 
@@ -71,8 +192,79 @@ pub async fn handle_upload(
         "No file content in request".to_string(),
     ))?;
 
-    log::info!("Uploading file: {} ({} bytes)", filename, data.len());
-    match password.as_ref() {
+    Ok(UploadForm {
+        filename,
+        data,
+        password,
+        preferred_language: preferred_language.or_else(|| state.config.default_language.clone()),
+        job_id,
+        tags,
+    })
+}
+
+/// Summarise an uploaded project without parsing it, so clients can ask for a password first.
+pub async fn handle_inspect(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    multipart: Multipart,
+) -> Result<Json<ProjectSummary>, ApiError> {
+    let principal = principal.map(|Extension(principal)| principal);
+    let form = read_upload_form(&state, principal.as_ref(), multipart).await?;
+    validate_form(&state, &form)?;
+    Ok(Json(inspect_upload(&form).await?))
+}
+
+fn validate_form(state: &AppState, form: &UploadForm) -> Result<(), ValidationError> {
+    let validator = FileValidator::new(
+        state.config.max_upload_size_bytes,
+        state.config.max_uncompressed_size_bytes,
+    );
+    validator
+        .validate_upload(&form.filename, form.data.as_ref())
+        .inspect_err(|error| log::warn!("Upload validation failed: {}", error))
+}
+
+async fn inspect_upload(form: &UploadForm) -> Result<ProjectSummary, ApiError> {
+    // Nested project archives are decompressed, so keep this off the async runtime too.
+    let data = form.data.clone();
+    tokio::task::spawn_blocking(move || inspect_knxproj(data.as_ref()))
+        .await
+        .map_err(|_| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Project inspection failed"))?
+        .map_err(|e| {
+            log::warn!("Project inspection failed: {:?}", e);
+            if e.downcast_ref::<ArchiveLimitError>().is_some() {
+                return ApiError::from_load_error(&e);
+            }
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Failed to read KNX project: {}", e),
+            )
+            .with_code(ErrorCode::InvalidArchive)
+            .with_diagnostics(e.chain().skip(1).map(ToString::to_string).collect())
+        })
+}
+
+/// Validate an uploaded project and parse it on the parse pool.
+///
+/// Failures are also published to the upload's job stream, if it has one.
+pub async fn parse_upload(state: &AppState, form: UploadForm) -> Result<KnxProjectData, ApiError> {
+    let job_id = form.job_id.clone();
+    let result = validate_and_parse(state, form).await;
+    if let (Some(id), Err(error)) = (job_id.as_deref(), &result) {
+        state.jobs.publish(
+            id,
+            JobEvent::Failed {
+                code: error.code,
+                message: error.message.clone(),
+            },
+        );
+    }
+    result
+}
+
+async fn validate_and_parse(state: &AppState, form: UploadForm) -> Result<KnxProjectData, ApiError> {
+    log::info!("Uploading file: {} ({} bytes)", form.filename, form.data.len());
+    match form.password.as_ref() {
         Some(value) => {
             log::info!("Password provided (len={})", value.len());
         }
@@ -81,49 +273,96 @@ pub async fn handle_upload(
         }
     }
 
-    let validator = FileValidator::new(
-        config.max_upload_size_bytes,
-        config.max_uncompressed_size_bytes,
-    );
-    if let Err(error) = validator.validate_upload(&filename, data.as_ref()) {
-        let status = match error {
-            ValidationError::FileTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
-            ValidationError::InvalidFileFormat { .. } => StatusCode::BAD_REQUEST,
-            ValidationError::InvalidArchive => StatusCode::BAD_REQUEST,
-            ValidationError::UncompressedTooLarge { .. } => StatusCode::BAD_REQUEST,
-            ValidationError::ArchiveError(_) => StatusCode::BAD_REQUEST,
-        };
-        log::warn!("Upload validation failed: {}", error);
-        return Err((status, error.to_string()));
+    let size = form.data.len();
+    if let Err(error) = validate_form(state, &form) {
+        state.metrics.record_upload(error.kind(), size);
+        return Err(error.into());
     }
+    let result = parse_validated(state, form).await;
+    let outcome = match &result {
+        Ok(_) => "success",
+        Err(error) => error.code.as_str(),
+    };
+    state.metrics.record_upload(outcome, size);
+    result
+}
 
-    // Parse the KNX project
-    let project_data = load_knxproj_bytes_with_language(
-        data.as_ref(),
-        password.as_deref(),
-        preferred_language.as_deref(),
-    )
-    .map_err(|e| {
-        log::warn!("KNX parse error: {:?}", e);
-        if e.downcast_ref::<PasswordRequiredError>().is_some() {
-            (
-                StatusCode::BAD_REQUEST,
-                "Encrypted KNX project: password required".to_string(),
-            )
-        } else if e.downcast_ref::<InvalidPasswordError>().is_some() {
-            (
-                StatusCode::BAD_REQUEST,
-                "Invalid password for KNX project".to_string(),
-            )
-        } else {
-            (
+async fn parse_validated(state: &AppState, form: UploadForm) -> Result<KnxProjectData, ApiError> {
+    // Ask for the password before queueing a parse that could only fail late. Other inspection
+    // failures are left to the parser, which reports them in more detail.
+    if form.password.is_none()
+        && inspect_upload(&form)
+            .await
+            .is_ok_and(|summary| summary.password_required)
+    {
+        log::warn!("Encrypted project uploaded without password");
+        return Err(ApiError::from_load_error(&PasswordRequiredError.into()));
+    }
+
+    // The validator only trusts declared sizes; the loader checks what actually inflates.
+    let limits = ArchiveLimits {
+        max_total_size: state.config.max_uncompressed_size_bytes as u64,
+        ..ArchiveLimits::default()
+    };
+
+    // Parse the KNX project off the async runtime
+    let jobs = state.jobs.clone();
+    let app_programs = state.app_programs.clone();
+    let metrics = state.metrics.clone();
+    // The parser logs from a pool thread; keep those records in the request's span.
+    let span = tracing::Span::current();
+    let result = state
+        .parse_pool
+        .run(move |cancelled| {
+            let _span = span.enter();
+            let started = Instant::now();
+            let job = JobObserver {
+                jobs: &jobs,
+                id: form.job_id.as_deref(),
+                cancelled,
+            };
+            let observer = PhaseTimer::new(&metrics, &job);
+            let result = ProjectLoader::from_bytes(form.data.as_ref())
+                .password(form.password.as_deref())
+                .language(form.preferred_language.as_deref())
+                .archive_limits(limits)
+                .app_store(&app_programs)
+                .progress(&observer)
+                .load();
+            if let Ok(project) = &result {
+                metrics.record_parse(project, started.elapsed());
+            }
+            result
+        })
+        .await
+        .map_err(|error| match error {
+            PoolError::Busy => {
+                log::warn!("Parse queue full, rejecting upload");
+                ApiError::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Server busy parsing other projects, retry later",
+                )
+                .with_retry_after(RETRY_AFTER_SECS)
+            }
+            PoolError::TimedOut(limit) => {
+                log::warn!("Parse timed out after {:?}", limit);
+                ApiError::new(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    format!("Parsing took longer than {} seconds", limit.as_secs()),
+                )
+            }
+            PoolError::Panicked => ApiError::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to parse KNX project: {}", e),
-            )
-        }
+                "Failed to parse KNX project: parser crashed",
+            ),
+        })?;
+
+    let project_data = result.map_err(|e| {
+        log::warn!("KNX parse error: {:?}", e);
+        ApiError::from_load_error(&e)
     })?;
 
     log::info!("Project parsed successfully");
 
-    Ok(Json(build_project_graphs(&project_data)))
+    Ok(project_data)
 }
@@ -1,7 +1,8 @@
 use axum::{
     body::Body,
+    extract::OriginalUri,
     http::{header, Response, StatusCode, Uri},
-    response::IntoResponse,
+    response::{IntoResponse, Redirect},
 };
 use rust_embed::Embed;
 
@@ -33,7 +34,12 @@ pub async fn serve_sw() -> impl IntoResponse {
     }
 }
 
-pub async fn serve_assets(uri: Uri) -> impl IntoResponse {
+pub async fn serve_assets(OriginalUri(original): OriginalUri, uri: Uri) -> Response<Body> {
+    // Nested under e.g. `/topobus`, the router only sees the mount point without its trailing
+    // slash, where the frontend's relative URLs would resolve against the parent path.
+    if uri.path() == "/" && !original.path().ends_with('/') {
+        return Redirect::to(&format!("{}/index.html", original.path())).into_response();
+    }
     let path = uri.path().trim_start_matches('/');
 
     // Default to index.html for root
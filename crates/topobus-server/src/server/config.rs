@@ -1,41 +1,1138 @@
-use crate::cli::Args;
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::{anyhow, bail, Context, Result};
+use axum::http::HeaderValue;
+use serde::Deserialize;
+use topobus_core::{KnxProjectData, ProjectLoader, RedactionPolicy};
+
+use crate::cli::ServeArgs;
+use crate::server::auth::PasswordHash;
+
+/// Config file read from the working directory when neither `--config` nor `TOPOBUS_CONFIG`
+/// names one.
+const DEFAULT_CONFIG_FILE: &str = "topobus.toml";
+
+const DEFAULT_SELF_SIGNED_CERT: &str = "topobus-cert.pem";
+const DEFAULT_SELF_SIGNED_KEY: &str = "topobus-key.pem";
+const DEFAULT_STORE_KEY: &str = "topobus-store.key";
+
+/// Shortest accepted bearer token; shorter ones are guessable.
+const MIN_TOKEN_LEN: usize = 16;
+
+/// Effective server settings.
+///
+/// For `topobus serve`, each setting comes from the first source that has it: command line,
+/// environment (`TOPOBUS_*`), config file, built-in default. Applications embedding the
+/// [`router`](crate::router) start from [`ServerConfig::builder`] instead.
 #[derive(Clone, Debug)]
 pub struct ServerConfig {
-    pub bind_address: String,
+    /// Config file the settings were read from, if any.
+    pub config_file: Option<PathBuf>,
+    pub bind_addresses: Vec<IpAddr>,
     pub port: u16,
-    pub enable_cors: bool,
+    pub open_browser: bool,
+    /// Origins allowed to call the API from another site; `*` allows any. Empty disables CORS,
+    /// which is all the bundled frontend needs.
+    pub cors_allowed_origins: Vec<String>,
     pub max_upload_size_bytes: usize,
     pub max_uncompressed_size_bytes: usize,
+    pub session_ttl_secs: u64,
+    pub max_sessions: usize,
+    pub max_concurrent_parses: usize,
+    pub max_queued_parses: usize,
+    pub parse_timeout_secs: u64,
+    /// Language for application program texts when an upload does not ask for one.
+    pub default_language: Option<String>,
+    /// Whether API responses keep the project tracing password, BCU key and group address keys.
+    pub redaction: RedactionPolicy,
+    pub cache_entries: usize,
+    pub cache_dir: Option<PathBuf>,
+    /// Files kept in `cache_dir`, the least recently used removed beyond it.
+    pub cache_disk_entries: usize,
+    pub app_program_cache_entries: usize,
+    /// Projects parsed at startup and kept for the lifetime of the server.
+    pub preload: Vec<PreloadProject>,
+    /// Projects already parsed, served like preloaded ones.
+    pub projects: Vec<LoadedProject>,
+    /// Directory whose `.knxproj` files are served on `/api/library` and re-parsed on change.
+    pub watch_dir: Option<PathBuf>,
+    pub watch_passwords: WatchPasswords,
+    pub store: StoreConfig,
+    pub auth: AuthConfig,
+    pub tls: TlsConfig,
 }
 
-impl ServerConfig {
-    pub fn from_args(args: &Args) -> Self {
-        let bind_address = std::env::var("TOPOBUS_BIND_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
-        let port = std::env::var("TOPOBUS_PORT")
-            .ok()
-            .and_then(|value| value.parse::<u16>().ok())
-            .unwrap_or(args.port);
-        let enable_cors = std::env::var("TOPOBUS_ENABLE_CORS")
-            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
-            .unwrap_or(true);
-        let max_upload_size_bytes = read_mb_env("TOPOBUS_MAX_UPLOAD_MB", 200);
-        let max_uncompressed_size_bytes = read_mb_env("TOPOBUS_MAX_UNCOMPRESSED_MB", 600);
+/// HTTPS settings. Browsers only allow service workers and app install on HTTPS, or on
+/// `localhost`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain.
+    pub cert: Option<PathBuf>,
+    /// PEM private key.
+    pub key: Option<PathBuf>,
+    /// Generate a self-signed certificate at `cert` and `key` when they do not exist yet, and
+    /// keep using it afterwards.
+    pub self_signed: bool,
+    /// Extra names for the self-signed certificate besides `localhost`, e.g. `topobus.office.lan`
+    /// or the server's LAN address.
+    pub hostnames: Vec<String>,
+}
+
+impl TlsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.cert.is_some() || self.self_signed
+    }
+
+    /// Put a self-signed certificate next to the config file unless paths are given.
+    fn default_paths(&mut self, base_dir: &Path) {
+        if self.self_signed {
+            self.cert
+                .get_or_insert_with(|| base_dir.join(DEFAULT_SELF_SIGNED_CERT));
+            self.key.get_or_insert_with(|| base_dir.join(DEFAULT_SELF_SIGNED_KEY));
+        }
+    }
+}
+
+/// Where uploaded projects are kept for everyone, on `/api/store`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StoreConfig {
+    /// Directory of the stored projects; there is no store without one.
+    pub dir: Option<PathBuf>,
+    /// Key the projects are encrypted with, generated when the file does not exist. Whoever has
+    /// it and a copy of `dir` can read the projects, so keep it out of backups of `dir`.
+    pub key_file: Option<PathBuf>,
+}
+
+impl StoreConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Put the key next to the config file unless a path is given.
+    fn default_paths(&mut self, base_dir: &Path) {
+        if self.dir.is_some() {
+            self.key_file
+                .get_or_insert_with(|| base_dir.join(DEFAULT_STORE_KEY));
+        }
+    }
+}
+
+/// Who may call the API. Authentication is on as soon as a token or a user is configured.
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Tokens accepted as `Authorization: Bearer <token>` or on the login page.
+    pub tokens: Vec<String>,
+    /// Users for basic auth and the login page.
+    pub users: Vec<AuthUser>,
+    /// Listen on non-loopback addresses even though authentication is off.
+    pub allow_unauthenticated: bool,
+    /// How long a login from the login page lasts.
+    pub login_ttl_minutes: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthUser {
+    pub name: String,
+    /// Output of `topobus config hash-password`.
+    pub password_hash: String,
+}
+
+impl AuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty() || !self.users.is_empty()
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            tokens: Vec::new(),
+            users: Vec::new(),
+            allow_unauthenticated: false,
+            login_ttl_minutes: 12 * 60,
+        }
+    }
+}
+
+// Keeps tokens out of `topobus config check` output and logs.
+impl std::fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("tokens", &format_args!("[{} hidden]", self.tokens.len()))
+            .field("users", &self.users)
+            .field("allow_unauthenticated", &self.allow_unauthenticated)
+            .field("login_ttl_minutes", &self.login_ttl_minutes)
+            .finish()
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreloadProject {
+    /// Session id the project is served under, e.g. `/api/projects/{id}`. Defaults to the file
+    /// name without extension.
+    pub id: Option<String>,
+    /// `.knxproj` file or extracted project directory.
+    pub path: PathBuf,
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for PreloadProject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreloadProject")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .field("password", &self.password.as_ref().map(|_| format_args!("[hidden]")))
+            .finish()
+    }
+}
+
+impl PreloadProject {
+    pub fn session_id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| {
+            self.path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "project".to_string())
+        })
+    }
+}
+
+/// Passwords of protected projects in the watched directory, by file name, e.g. `house.knxproj`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct WatchPasswords(pub BTreeMap<String, String>);
+
+impl WatchPasswords {
+    pub fn get(&self, file_name: &str) -> Option<&str> {
+        self.0.get(file_name).map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for WatchPasswords {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.keys().map(|name| (name, format_args!("[hidden]"))))
+            .finish()
+    }
+}
+
+/// A parsed project handed to the server, served as `/api/projects/{id}`.
+#[derive(Clone)]
+pub struct LoadedProject {
+    pub id: String,
+    pub project: Arc<KnxProjectData>,
+}
+
+// Project data is far too long for `topobus config check` output and logs.
+impl std::fmt::Debug for LoadedProject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadedProject")
+            .field("id", &self.id)
+            .field("project_name", &self.project.project_name)
+            .finish()
+    }
+}
+
+/// Layout of `topobus.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    server: ServerSection,
+    limits: LimitsSection,
+    parsing: ParsingSection,
+    output: OutputSection,
+    cache: CacheSection,
+    preload: Vec<PreloadProject>,
+    watch: WatchSection,
+    store: StoreConfig,
+    auth: AuthConfig,
+    tls: TlsConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerSection {
+    bind: Option<Vec<String>>,
+    port: Option<u16>,
+    open_browser: Option<bool>,
+    cors_allowed_origins: Option<Vec<String>>,
+}
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LimitsSection {
+    max_upload_mb: Option<usize>,
+    max_uncompressed_mb: Option<usize>,
+    session_ttl_minutes: Option<u64>,
+    max_sessions: Option<usize>,
+    max_concurrent_parses: Option<usize>,
+    max_queued_parses: Option<usize>,
+    parse_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ParsingSection {
+    default_language: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OutputSection {
+    include_secrets: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WatchSection {
+    dir: Option<PathBuf>,
+    passwords: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CacheSection {
+    entries: Option<usize>,
+    dir: Option<PathBuf>,
+    disk_entries: Option<usize>,
+    app_program_entries: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let default_parses = std::thread::available_parallelism()
+            .map(|count| count.get().min(4))
+            .unwrap_or(2);
         Self {
-            bind_address,
-            port,
-            enable_cors,
-            max_upload_size_bytes,
-            max_uncompressed_size_bytes,
+            config_file: None,
+            bind_addresses: vec![IpAddr::from([127, 0, 0, 1])],
+            port: 8080,
+            open_browser: true,
+            cors_allowed_origins: Vec::new(),
+            max_upload_size_bytes: 200 * 1024 * 1024,
+            max_uncompressed_size_bytes: 600 * 1024 * 1024,
+            session_ttl_secs: 60 * 60,
+            max_sessions: 8,
+            max_concurrent_parses: default_parses,
+            max_queued_parses: 8,
+            parse_timeout_secs: 300,
+            default_language: None,
+            redaction: RedactionPolicy::Mask,
+            cache_entries: 16,
+            cache_dir: None,
+            cache_disk_entries: 256,
+            app_program_cache_entries: 64,
+            preload: Vec::new(),
+            projects: Vec::new(),
+            watch_dir: None,
+            watch_passwords: WatchPasswords::default(),
+            store: StoreConfig::default(),
+            auth: AuthConfig::default(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Settings built in code, starting from the defaults; nothing is read from the environment.
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder {
+            config: Self::default(),
+        }
+    }
+
+    /// Settings of `topobus serve`, from `args`, the environment and the config file.
+    pub fn load(args: &ServeArgs) -> Result<Self> {
+        Self::resolve(args, &|key| std::env::var(key).ok())
+    }
+
+    fn resolve(args: &ServeArgs, env: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut config = Self::default();
+        let path = args
+            .config
+            .clone()
+            .or_else(|| env("TOPOBUS_CONFIG").filter(|value| !value.is_empty()).map(PathBuf::from))
+            .or_else(|| {
+                let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+                default.is_file().then_some(default)
+            });
+        if let Some(path) = path {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?;
+            let file: FileConfig = toml::from_str(&text)
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+            config
+                .apply_file(file, path.parent().unwrap_or(Path::new("")))
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+            config.config_file = Some(path);
+        }
+        config.apply_env(env)?;
+        config.apply_args(args)?;
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, file: FileConfig, base_dir: &Path) -> Result<()> {
+        let server = file.server;
+        if let Some(bind) = server.bind {
+            self.bind_addresses = bind
+                .iter()
+                .map(|address| {
+                    address
+                        .parse()
+                        .map_err(|_| anyhow!("server.bind: '{}' is not an IP address", address))
+                })
+                .collect::<Result<_>>()?;
+        }
+        set(&mut self.port, server.port);
+        set(&mut self.open_browser, server.open_browser);
+        set(&mut self.cors_allowed_origins, server.cors_allowed_origins);
+
+        let limits = file.limits;
+        set(&mut self.max_upload_size_bytes, limits.max_upload_mb.map(megabytes));
+        set(
+            &mut self.max_uncompressed_size_bytes,
+            limits.max_uncompressed_mb.map(megabytes),
+        );
+        set(
+            &mut self.session_ttl_secs,
+            limits.session_ttl_minutes.map(|minutes| minutes.saturating_mul(60)),
+        );
+        set(&mut self.max_sessions, limits.max_sessions);
+        set(&mut self.max_concurrent_parses, limits.max_concurrent_parses);
+        set(&mut self.max_queued_parses, limits.max_queued_parses);
+        set(&mut self.parse_timeout_secs, limits.parse_timeout_secs);
+
+        if file.parsing.default_language.is_some() {
+            self.default_language = file.parsing.default_language;
+        }
+
+        if let Some(include) = file.output.include_secrets {
+            self.redaction = RedactionPolicy::include_secrets(include);
+        }
+
+        let cache = file.cache;
+        set(&mut self.cache_entries, cache.entries);
+        if let Some(dir) = cache.dir {
+            self.cache_dir = Some(base_dir.join(dir));
+        }
+        set(&mut self.cache_disk_entries, cache.disk_entries);
+        set(&mut self.app_program_cache_entries, cache.app_program_entries);
+
+        self.preload = file
+            .preload
+            .into_iter()
+            .map(|project| PreloadProject {
+                path: base_dir.join(&project.path),
+                ..project
+            })
+            .collect();
+        if let Some(dir) = file.watch.dir {
+            self.watch_dir = Some(base_dir.join(dir));
+        }
+        self.watch_passwords = WatchPasswords(file.watch.passwords);
+        self.store = StoreConfig {
+            dir: file.store.dir.map(|dir| base_dir.join(dir)),
+            key_file: file.store.key_file.map(|path| base_dir.join(path)),
+        };
+        self.store.default_paths(base_dir);
+        self.auth = file.auth;
+        self.tls = TlsConfig {
+            cert: file.tls.cert.map(|path| base_dir.join(path)),
+            key: file.tls.key.map(|path| base_dir.join(path)),
+            ..file.tls
+        };
+        self.tls.default_paths(base_dir);
+        Ok(())
+    }
+
+    fn apply_env(&mut self, env: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+        let var = |key: &str| env(key).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+
+        if let Some(value) = var("TOPOBUS_BIND_ADDRESS") {
+            self.bind_addresses = parse_list(&value)
+                .map(|address| parse_env(address, "TOPOBUS_BIND_ADDRESS"))
+                .collect::<Result<_>>()?;
+        }
+        if let Some(value) = var("TOPOBUS_PORT") {
+            self.port = parse_env(&value, "TOPOBUS_PORT")?;
+        }
+        if let Some(value) = var("TOPOBUS_CORS_ORIGINS") {
+            self.cors_allowed_origins = parse_list(&value).map(str::to_string).collect();
+        } else if let Some(value) = var("TOPOBUS_ENABLE_CORS") {
+            // Older switch: allow any origin.
+            if parse_flag(&value) {
+                self.cors_allowed_origins = vec!["*".to_string()];
+            }
+        }
+        if let Some(value) = var("TOPOBUS_MAX_UPLOAD_MB") {
+            self.max_upload_size_bytes = megabytes(parse_env(&value, "TOPOBUS_MAX_UPLOAD_MB")?);
+        }
+        if let Some(value) = var("TOPOBUS_MAX_UNCOMPRESSED_MB") {
+            self.max_uncompressed_size_bytes =
+                megabytes(parse_env(&value, "TOPOBUS_MAX_UNCOMPRESSED_MB")?);
+        }
+        if let Some(value) = var("TOPOBUS_SESSION_TTL_MINUTES") {
+            self.session_ttl_secs =
+                parse_env::<u64>(&value, "TOPOBUS_SESSION_TTL_MINUTES")?.saturating_mul(60);
+        }
+        if let Some(value) = var("TOPOBUS_MAX_SESSIONS") {
+            self.max_sessions = parse_env(&value, "TOPOBUS_MAX_SESSIONS")?;
+        }
+        if let Some(value) = var("TOPOBUS_MAX_CONCURRENT_PARSES") {
+            self.max_concurrent_parses = parse_env(&value, "TOPOBUS_MAX_CONCURRENT_PARSES")?;
+        }
+        if let Some(value) = var("TOPOBUS_MAX_QUEUED_PARSES") {
+            self.max_queued_parses = parse_env(&value, "TOPOBUS_MAX_QUEUED_PARSES")?;
+        }
+        if let Some(value) = var("TOPOBUS_PARSE_TIMEOUT_SECS") {
+            self.parse_timeout_secs = parse_env(&value, "TOPOBUS_PARSE_TIMEOUT_SECS")?;
+        }
+        if let Some(value) = var("TOPOBUS_DEFAULT_LANGUAGE") {
+            self.default_language = Some(value);
+        }
+        if let Some(value) = var("TOPOBUS_INCLUDE_SECRETS") {
+            self.redaction = RedactionPolicy::include_secrets(parse_flag(&value));
+        }
+        if let Some(value) = var("TOPOBUS_CACHE_ENTRIES") {
+            self.cache_entries = parse_env(&value, "TOPOBUS_CACHE_ENTRIES")?;
+        }
+        if let Some(value) = var("TOPOBUS_CACHE_DIR") {
+            self.cache_dir = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TOPOBUS_CACHE_DISK_ENTRIES") {
+            self.cache_disk_entries = parse_env(&value, "TOPOBUS_CACHE_DISK_ENTRIES")?;
+        }
+        if let Some(value) = var("TOPOBUS_APP_CACHE_ENTRIES") {
+            self.app_program_cache_entries = parse_env(&value, "TOPOBUS_APP_CACHE_ENTRIES")?;
+        }
+        if let Some(value) = var("TOPOBUS_WATCH_DIR") {
+            self.watch_dir = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TOPOBUS_STORE_KEY_FILE") {
+            self.store.key_file = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TOPOBUS_STORE_DIR") {
+            self.store.dir = Some(PathBuf::from(value));
+            self.store.default_paths(Path::new(""));
+        }
+        if let Some(value) = var("TOPOBUS_AUTH_TOKENS") {
+            self.auth.tokens = parse_list(&value).map(str::to_string).collect();
+        }
+        if let Some(value) = var("TOPOBUS_ALLOW_UNAUTHENTICATED") {
+            self.auth.allow_unauthenticated = parse_flag(&value);
+        }
+        if let Some(value) = var("TOPOBUS_TLS_CERT") {
+            self.tls.cert = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TOPOBUS_TLS_KEY") {
+            self.tls.key = Some(PathBuf::from(value));
+        }
+        if let Some(value) = var("TOPOBUS_TLS_SELF_SIGNED") {
+            self.tls.self_signed = parse_flag(&value);
+            self.tls.default_paths(Path::new(""));
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: &ServeArgs) -> Result<()> {
+        if !args.bind.is_empty() {
+            self.bind_addresses = args
+                .bind
+                .iter()
+                .map(|address| {
+                    address
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid --bind address: {}", address))
+                })
+                .collect::<Result<_>>()?;
+        }
+        set(&mut self.port, args.port);
+        if args.no_browser {
+            self.open_browser = false;
+        }
+        if args.include_secrets {
+            self.redaction = RedactionPolicy::Include;
+        }
+        if args.allow_unauthenticated {
+            self.auth.allow_unauthenticated = true;
+        }
+        if let Some(dir) = &args.watch {
+            self.watch_dir = Some(dir.clone());
+        }
+        if let Some(dir) = &args.store {
+            self.store.dir = Some(dir.clone());
+            self.store.default_paths(Path::new(""));
+        }
+        if let Some(cert) = &args.tls_cert {
+            self.tls.cert = Some(cert.clone());
+        }
+        if let Some(key) = &args.tls_key {
+            self.tls.key = Some(key.clone());
+        }
+        if let Some(path) = &args.knxproj_path {
+            self.preload.push(PreloadProject {
+                id: None,
+                path: PathBuf::from(path),
+                password: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Problems that would stop the server from starting or make it misbehave.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.bind_addresses.is_empty() {
+            problems.push("server.bind: no address to listen on".to_string());
+        }
+        for origin in &self.cors_allowed_origins {
+            if origin != "*" && !is_valid_origin(origin) {
+                problems.push(format!(
+                    "server.cors_allowed_origins: '{}' is not an origin like https://example.com",
+                    origin
+                ));
+            }
+        }
+        for (name, value) in [
+            ("limits.max_upload_mb", self.max_upload_size_bytes),
+            ("limits.max_uncompressed_mb", self.max_uncompressed_size_bytes),
+            ("limits.max_sessions", self.max_sessions),
+            ("limits.max_concurrent_parses", self.max_concurrent_parses),
+        ] {
+            if value == 0 {
+                problems.push(format!("{} must be greater than 0", name));
+            }
+        }
+        if self.parse_timeout_secs == 0 {
+            problems.push("limits.parse_timeout_secs must be greater than 0".to_string());
+        }
+        if let Some(dir) = &self.cache_dir {
+            if dir.exists() && !dir.is_dir() {
+                problems.push(format!("cache.dir: {} is not a directory", dir.display()));
+            }
+        }
+        if let Some(dir) = &self.watch_dir {
+            if !dir.is_dir() {
+                problems.push(format!("watch.dir: {} is not a directory", dir.display()));
+            }
+        }
+        for file_name in self.watch_passwords.0.keys() {
+            let is_project = crate::server::library::strip_extension(file_name).is_some();
+            if !is_project || file_name.contains(['/', '\\']) {
+                problems.push(format!(
+                    "watch.passwords: '{}' is not the name of a .knxproj file",
+                    file_name
+                ));
+            }
+        }
+        match (&self.store.dir, &self.store.key_file) {
+            (Some(dir), _) if dir.exists() && !dir.is_dir() => {
+                problems.push(format!("store.dir: {} is not a directory", dir.display()));
+            }
+            (Some(_), None) => problems.push("store: key_file is required".to_string()),
+            (Some(_), Some(key)) if key.exists() => {
+                if let Err(err) = crate::server::store::read_key(key) {
+                    problems.push(format!("store.key_file: {:#}", err));
+                }
+            }
+            _ => {}
+        }
+        if !self.auth.is_enabled() && !self.auth.allow_unauthenticated {
+            for address in self.bind_addresses.iter().filter(|address| !address.is_loopback()) {
+                problems.push(format!(
+                    "server.bind: {} is reachable from other hosts but [auth] has no tokens or \
+                     users; add some, or set auth.allow_unauthenticated = true",
+                    address
+                ));
+            }
+        }
+        if self.auth.tokens.iter().any(|token| token.len() < MIN_TOKEN_LEN) {
+            problems.push(format!(
+                "auth.tokens: tokens must be at least {} characters long",
+                MIN_TOKEN_LEN
+            ));
+        }
+        let mut names = std::collections::HashSet::new();
+        for user in &self.auth.users {
+            if user.name.is_empty() || user.name.contains(':') {
+                problems.push(format!("auth.users: '{}' is not a valid user name", user.name));
+            }
+            if !names.insert(user.name.as_str()) {
+                problems.push(format!("auth.users: user '{}' is defined twice", user.name));
+            }
+            if let Err(err) = PasswordHash::parse(&user.password_hash) {
+                problems.push(format!("auth.users: password_hash of '{}': {}", user.name, err));
+            }
+        }
+        if self.auth.login_ttl_minutes == 0 {
+            problems.push("auth.login_ttl_minutes must be greater than 0".to_string());
+        }
+        match (&self.tls.cert, &self.tls.key) {
+            (Some(_), None) | (None, Some(_)) => {
+                problems.push("tls: set both cert and key".to_string());
+            }
+            (Some(cert), Some(key)) => {
+                let missing: Vec<_> = [cert, key].into_iter().filter(|path| !path.is_file()).collect();
+                // A self-signed pair is generated when both files are missing.
+                let generated = self.tls.self_signed && missing.len() == 2;
+                if !generated {
+                    for path in missing {
+                        problems.push(format!("tls: {} does not exist", path.display()));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+
+        let mut ids = std::collections::HashSet::new();
+        for project in &self.preload {
+            if !project.path.exists() {
+                problems.push(format!("preload: {} does not exist", project.path.display()));
+            }
+            let id = project.session_id();
+            if !ids.insert(id.clone()) {
+                problems.push(format!("preload: id '{}' is used twice", id));
+            }
+        }
+        for project in &self.projects {
+            if !ids.insert(project.id.clone()) {
+                problems.push(format!("preload: id '{}' is used twice", project.id));
+            }
+        }
+        problems
+    }
+
+    /// Parse the projects in `preload` into `projects`.
+    pub async fn load_preloaded(&mut self) -> Result<()> {
+        for project in std::mem::take(&mut self.preload) {
+            let id = project.session_id();
+            let path = project.path.clone();
+            let language = self.default_language.clone();
+            let loaded = tokio::task::spawn_blocking(move || {
+                ProjectLoader::from_path(&path)
+                    .password(project.password.as_deref())
+                    .language(language.as_deref())
+                    .load()
+            })
+            .await?
+            .with_context(|| format!("Failed to preload {}", project.path.display()))?;
+            log::info!("Preloaded {} as /api/projects/{}", project.path.display(), id);
+            self.projects.push(LoadedProject {
+                id,
+                project: Arc::new(loaded),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Builder for a [`ServerConfig`] made in code.
+///
+/// ```
+/// use std::time::Duration;
+/// use topobus_server::ServerConfig;
+///
+/// let config = ServerConfig::builder()
+///     .max_upload_size(50 * 1024 * 1024)
+///     .session_ttl(Duration::from_secs(15 * 60))
+///     .default_language("de-DE")
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct ServerConfigBuilder {
+    config: ServerConfig,
+}
+
+impl ServerConfigBuilder {
+    /// Largest accepted upload, in bytes.
+    pub fn max_upload_size(mut self, bytes: usize) -> Self {
+        self.config.max_upload_size_bytes = bytes;
+        self
+    }
+
+    /// Largest size an upload may decompress to, in bytes.
+    pub fn max_uncompressed_size(mut self, bytes: usize) -> Self {
+        self.config.max_uncompressed_size_bytes = bytes;
+        self
+    }
+
+    /// How long an unused project session is kept.
+    pub fn session_ttl(mut self, ttl: Duration) -> Self {
+        self.config.session_ttl_secs = ttl.as_secs();
+        self
+    }
+
+    pub fn max_sessions(mut self, sessions: usize) -> Self {
+        self.config.max_sessions = sessions;
+        self
+    }
+
+    /// Projects parsed at the same time, and how many more may wait for a turn.
+    pub fn parses(mut self, concurrent: usize, queued: usize) -> Self {
+        self.config.max_concurrent_parses = concurrent;
+        self.config.max_queued_parses = queued;
+        self
+    }
+
+    pub fn parse_timeout(mut self, timeout: Duration) -> Self {
+        self.config.parse_timeout_secs = timeout.as_secs();
+        self
+    }
+
+    /// Language for application program texts when an upload does not ask for one.
+    pub fn default_language(mut self, language: impl Into<String>) -> Self {
+        self.config.default_language = Some(language.into());
+        self
+    }
+
+    /// Keep the project tracing password, BCU key and group address keys in responses.
+    pub fn include_secrets(mut self, include: bool) -> Self {
+        self.config.redaction = RedactionPolicy::include_secrets(include);
+        self
+    }
+
+    /// Parsed graphs kept in memory, and optionally on disk in `dir`.
+    pub fn cache(mut self, entries: usize, dir: Option<PathBuf>) -> Self {
+        self.config.cache_entries = entries;
+        self.config.cache_dir = dir;
+        self
+    }
+
+    /// Files kept in the cache directory before the least recently used are removed.
+    pub fn cache_disk_entries(mut self, entries: usize) -> Self {
+        self.config.cache_disk_entries = entries;
+        self
+    }
+
+    pub fn app_program_cache_entries(mut self, entries: usize) -> Self {
+        self.config.app_program_cache_entries = entries;
+        self
+    }
+
+    /// Origins allowed to call the API from another site; `*` allows any.
+    pub fn cors_allowed_origins(mut self, origins: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config.cors_allowed_origins = origins.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Serve the `.knxproj` files in `dir` on `/api/library`, re-parsing them when they change.
+    /// The router then needs a Tokio runtime to watch from.
+    pub fn watch_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.watch_dir = Some(dir.into());
+        self
+    }
+
+    /// Password of the protected project `file_name` in the watched directory.
+    pub fn watch_password(mut self, file_name: impl Into<String>, password: impl Into<String>) -> Self {
+        self.config.watch_passwords.0.insert(file_name.into(), password.into());
+        self
+    }
+
+    /// Keep uploads posted to `/api/store` in `dir`, encrypted with the key in `key_file`.
+    /// The key file is generated when it does not exist.
+    pub fn store(mut self, dir: impl Into<PathBuf>, key_file: impl Into<PathBuf>) -> Self {
+        self.config.store = StoreConfig {
+            dir: Some(dir.into()),
+            key_file: Some(key_file.into()),
+        };
+        self
+    }
+
+    /// Require credentials for the API. Leave this out when the embedding application
+    /// authenticates requests itself.
+    pub fn auth(mut self, auth: AuthConfig) -> Self {
+        self.config.auth = auth;
+        self
+    }
+
+    /// Serve an already parsed project as `/api/projects/{id}`, for as long as the server runs.
+    pub fn project(mut self, id: impl Into<String>, project: impl Into<Arc<KnxProjectData>>) -> Self {
+        self.config.projects.push(LoadedProject {
+            id: id.into(),
+            project: project.into(),
+        });
+        self
+    }
+
+    /// Parse a project file with [`ServerConfig::load_preloaded`] and serve it like
+    /// [`ServerConfigBuilder::project`].
+    pub fn preload(mut self, project: PreloadProject) -> Self {
+        self.config.preload.push(project);
+        self
+    }
+
+    /// The settings, or the problems [`ServerConfig::validate`] found in them.
+    pub fn build(self) -> Result<ServerConfig> {
+        let problems = self.config.validate();
+        if !problems.is_empty() {
+            bail!("Invalid configuration:\n  {}", problems.join("\n  "));
         }
+        Ok(self.config)
+    }
+}
+
+fn set<T>(target: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *target = value;
     }
 }
 
-fn read_mb_env(key: &str, fallback_mb: usize) -> usize {
-    let value = std::env::var(key)
-        .ok()
-        .and_then(|raw| raw.parse::<usize>().ok())
-        .unwrap_or(fallback_mb);
+fn megabytes(value: usize) -> usize {
     value.saturating_mul(1024 * 1024)
 }
+
+fn parse_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+fn parse_env<T: std::str::FromStr>(value: &str, key: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid value for {}: {}", key, value))
+}
+
+fn parse_flag(value: &str) -> bool {
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
+
+fn is_valid_origin(origin: &str) -> bool {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+    matches!(scheme, "http" | "https")
+        && !host.is_empty()
+        && !host.contains('/')
+        && HeaderValue::from_str(origin).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerConfig;
+    use crate::cli::ServeArgs;
+    use topobus_core::RedactionPolicy;
+    use std::collections::HashMap;
+    use std::net::IpAddr;
+    use std::path::PathBuf;
+
+    const CONFIG: &str = r#"
+[server]
+bind = ["127.0.0.1", "::1"]
+port = 9000
+cors_allowed_origins = ["https://knx.example.com"]
+
+[limits]
+max_upload_mb = 50
+session_ttl_minutes = 10
+
+[parsing]
+default_language = "de-DE"
+
+[output]
+include_secrets = true
+
+[cache]
+dir = "cache"
+disk_entries = 100
+
+[watch]
+dir = "exports"
+passwords = { "office.knxproj" = "hunter2watch" }
+
+[store]
+dir = "store"
+
+[[preload]]
+id = "house"
+path = "projects/house.knxproj"
+password = "hunter2secret"
+"#;
+
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("topobus-config-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("topobus.toml");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn resolve(args: &ServeArgs, env: &[(&str, &str)]) -> anyhow::Result<ServerConfig> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        ServerConfig::resolve(args, &|key| env.get(key).cloned())
+    }
+
+    #[test]
+    fn command_line_beats_env_beats_file() {
+        let path = write_config("precedence", CONFIG);
+        let dir = path.parent().unwrap().to_path_buf();
+        let args = ServeArgs {
+            config: Some(path.clone()),
+            port: Some(9100),
+            ..ServeArgs::default()
+        };
+        let config = resolve(
+            &args,
+            &[
+                ("TOPOBUS_PORT", "9050"),
+                ("TOPOBUS_MAX_UPLOAD_MB", "20"),
+                ("TOPOBUS_INCLUDE_SECRETS", "false"),
+            ],
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.config_file.as_deref(), Some(path.as_path()));
+        assert_eq!(config.port, 9100);
+        assert_eq!(config.max_upload_size_bytes, 20 * 1024 * 1024);
+        assert_eq!(config.session_ttl_secs, 600);
+        assert_eq!(
+            config.bind_addresses,
+            ["127.0.0.1".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]
+        );
+        assert_eq!(config.cors_allowed_origins, ["https://knx.example.com"]);
+        assert_eq!(config.default_language.as_deref(), Some("de-DE"));
+        assert_eq!(config.redaction, RedactionPolicy::Mask);
+        assert_eq!(config.cache_dir, Some(dir.join("cache")));
+        assert_eq!(config.cache_disk_entries, 100);
+        assert_eq!(config.watch_dir, Some(dir.join("exports")));
+        assert_eq!(config.store.dir, Some(dir.join("store")));
+        assert_eq!(config.store.key_file, Some(dir.join("topobus-store.key")));
+        assert_eq!(config.preload[0].session_id(), "house");
+        assert_eq!(config.preload[0].path, dir.join("projects/house.knxproj"));
+        // Untouched settings keep their defaults.
+        assert_eq!(config.max_sessions, ServerConfig::default().max_sessions);
+    }
+
+    #[test]
+    fn hides_preload_passwords() {
+        let path = write_config("secrets", CONFIG);
+        let args = ServeArgs {
+            config: Some(path.clone()),
+            ..ServeArgs::default()
+        };
+        let config = resolve(&args, &[]).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(config.preload[0].password.as_deref(), Some("hunter2secret"));
+        assert_eq!(config.watch_passwords.get("office.knxproj"), Some("hunter2watch"));
+        // `topobus config check` prints the config like this.
+        let rendered = format!("{:#?}", config);
+        assert!(!rendered.contains("hunter2secret"), "{}", rendered);
+        assert!(!rendered.contains("hunter2watch"), "{}", rendered);
+        assert!(rendered.contains("office.knxproj"));
+        assert!(rendered.contains("[hidden]"));
+    }
+
+    #[test]
+    fn reads_config_path_from_env() {
+        let path = write_config("env", "[server]\nport = 9200\n");
+        let config = resolve(
+            &ServeArgs::default(),
+            &[("TOPOBUS_CONFIG", path.to_str().unwrap()), ("TOPOBUS_ENABLE_CORS", "true")],
+        )
+        .unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(config.port, 9200);
+        assert_eq!(config.cors_allowed_origins, ["*"]);
+        assert_eq!(config.redaction, RedactionPolicy::Mask);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        let path = write_config("unknown", "[server]\nprot = 9000\n");
+        let args = ServeArgs {
+            config: Some(path.clone()),
+            ..ServeArgs::default()
+        };
+        let error = resolve(&args, &[]).unwrap_err();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(format!("{:#}", error).contains("unknown field `prot`"), "{:#}", error);
+
+        assert!(resolve(&ServeArgs::default(), &[("TOPOBUS_PORT", "http")]).is_err());
+        let args = ServeArgs {
+            bind: vec!["localhost".to_string()],
+            ..ServeArgs::default()
+        };
+        assert!(resolve(&args, &[]).is_err());
+    }
+
+    #[test]
+    fn reports_invalid_settings() {
+        let mut config = ServerConfig {
+            cors_allowed_origins: vec!["knx.example.com".to_string()],
+            max_sessions: 0,
+            ..ServerConfig::default()
+        };
+        config.preload = resolve(
+            &ServeArgs {
+                knxproj_path: Some("/nonexistent/house.knxproj".to_string()),
+                ..ServeArgs::default()
+            },
+            &[],
+        )
+        .unwrap()
+        .preload;
+
+        let problems = config.validate();
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems[0].contains("knx.example.com"));
+        assert!(problems[1].starts_with("limits.max_sessions"));
+        assert!(problems[2].contains("does not exist"));
+        assert!(ServerConfig::default().validate().is_empty());
+    }
+
+    #[test]
+    fn refuses_remote_binds_without_auth() {
+        let args = ServeArgs {
+            bind: vec!["0.0.0.0".to_string(), "127.0.0.1".to_string()],
+            ..ServeArgs::default()
+        };
+        let config = resolve(&args, &[]).unwrap();
+        let problems = config.validate();
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].contains("0.0.0.0"));
+
+        let tokens = resolve(&args, &[("TOPOBUS_AUTH_TOKENS", "0123456789abcdef-token")]).unwrap();
+        assert!(tokens.validate().is_empty());
+        assert!(!format!("{:?}", tokens.auth).contains("0123456789abcdef"));
+        let overridden = resolve(&args, &[("TOPOBUS_ALLOW_UNAUTHENTICATED", "true")]).unwrap();
+        assert!(overridden.validate().is_empty());
+
+        let short = resolve(&args, &[("TOPOBUS_AUTH_TOKENS", "secret")]).unwrap();
+        assert!(short.validate()[0].starts_with("auth.tokens"));
+    }
+
+    #[test]
+    fn places_self_signed_certificate_next_to_config() {
+        let path = write_config("tls", "[tls]\nself_signed = true\n");
+        let dir = path.parent().unwrap().to_path_buf();
+        let args = ServeArgs {
+            config: Some(path),
+            ..ServeArgs::default()
+        };
+        let config = resolve(&args, &[]).unwrap();
+        let problems = config.validate();
+        std::fs::write(dir.join("topobus-cert.pem"), "").unwrap();
+        let half_pair = config.validate();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(config.tls.is_enabled());
+        assert_eq!(config.tls.cert, Some(dir.join("topobus-cert.pem")));
+        assert_eq!(config.tls.key, Some(dir.join("topobus-key.pem")));
+        assert!(problems.is_empty(), "{:?}", problems);
+        // A pair is only generated when both halves are missing.
+        assert!(half_pair[0].contains("topobus-key.pem"), "{:?}", half_pair);
+    }
+}
@@ -0,0 +1,363 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+use topobus_core::{ArchiveLimitError, InvalidPasswordError, PasswordRequiredError, UnsupportedSchemaError};
+
+use crate::server::validation::ValidationError;
+
+/// Largest plain-text error body [`json_errors`] turns into a message.
+const MAX_PLAIN_ERROR_LEN: usize = 4096;
+
+/// Stable, machine-readable reason of an API error. Clients branch on these, never on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    AuthRequired,
+    InvalidCredentials,
+    TooManyAttempts,
+    NotFound,
+    ProjectPinned,
+    InvalidFileFormat,
+    InvalidArchive,
+    ArchiveTooLarge,
+    PasswordRequired,
+    InvalidPassword,
+    UnsupportedSchema,
+    ParseFailed,
+    ServerBusy,
+    ParseTimeout,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 16] = [
+        ErrorCode::BadRequest,
+        ErrorCode::AuthRequired,
+        ErrorCode::InvalidCredentials,
+        ErrorCode::TooManyAttempts,
+        ErrorCode::NotFound,
+        ErrorCode::ProjectPinned,
+        ErrorCode::InvalidFileFormat,
+        ErrorCode::InvalidArchive,
+        ErrorCode::ArchiveTooLarge,
+        ErrorCode::PasswordRequired,
+        ErrorCode::InvalidPassword,
+        ErrorCode::UnsupportedSchema,
+        ErrorCode::ParseFailed,
+        ErrorCode::ServerBusy,
+        ErrorCode::ParseTimeout,
+        ErrorCode::InternalError,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::AuthRequired => "auth_required",
+            ErrorCode::InvalidCredentials => "invalid_credentials",
+            ErrorCode::TooManyAttempts => "too_many_attempts",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::ProjectPinned => "project_pinned",
+            ErrorCode::InvalidFileFormat => "invalid_file_format",
+            ErrorCode::InvalidArchive => "invalid_archive",
+            ErrorCode::ArchiveTooLarge => "archive_too_large",
+            ErrorCode::PasswordRequired => "password_required",
+            ErrorCode::InvalidPassword => "invalid_password",
+            ErrorCode::UnsupportedSchema => "unsupported_schema",
+            ErrorCode::ParseFailed => "parse_failed",
+            ErrorCode::ServerBusy => "server_busy",
+            ErrorCode::ParseTimeout => "parse_timeout",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+
+    /// What the code means, for the OpenAPI document.
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "The request is malformed, e.g. a missing form field.",
+            ErrorCode::AuthRequired => "The API requires credentials and none valid were sent.",
+            ErrorCode::InvalidCredentials => "Login with a wrong user name, password or token.",
+            ErrorCode::TooManyAttempts => {
+                "Too many wrong passwords from this address or for this user; retry after `Retry-After` seconds."
+            }
+            ErrorCode::NotFound => "No such project session, library or stored project, device, group address or route.",
+            ErrorCode::ProjectPinned => {
+                "The project was preloaded or passed in by the embedding application and cannot be deleted."
+            }
+            ErrorCode::InvalidFileFormat => "The upload is not a .knxproj file.",
+            ErrorCode::InvalidArchive => "The upload is not a readable ZIP archive.",
+            ErrorCode::ArchiveTooLarge => {
+                "The upload, or its uncompressed content, exceeds the server limits (`details.size`, `details.max`)."
+            }
+            ErrorCode::PasswordRequired => "The project is protected; resend it with a password.",
+            ErrorCode::InvalidPassword => "The project password is wrong.",
+            ErrorCode::UnsupportedSchema => {
+                "The project was written by an ETS version TopoBus cannot read (`details.schema_version`)."
+            }
+            ErrorCode::ParseFailed => "The project could not be parsed; `diagnostics` lists the causes.",
+            ErrorCode::ServerBusy => "The parse queue is full; retry after `Retry-After` seconds.",
+            ErrorCode::ParseTimeout => "Parsing took longer than the server allows.",
+            ErrorCode::InternalError => "Unexpected server failure.",
+        }
+    }
+
+    /// Code for errors that only have a status, e.g. extractor rejections.
+    fn for_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::AuthRequired,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::TooManyAttempts,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::CONFLICT => ErrorCode::ProjectPinned,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::ArchiveTooLarge,
+            StatusCode::SERVICE_UNAVAILABLE => ErrorCode::ServerBusy,
+            StatusCode::GATEWAY_TIMEOUT => ErrorCode::ParseTimeout,
+            status if status.is_server_error() => ErrorCode::InternalError,
+            _ => ErrorCode::BadRequest,
+        }
+    }
+}
+
+/// Error returned by API handlers, sent as a JSON [`ErrorBody`].
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub message: String,
+    /// Structured data about the error, e.g. the limit an upload exceeded.
+    pub details: Option<Value>,
+    /// Underlying causes, most specific last, for bug reports.
+    pub diagnostics: Vec<String>,
+    /// Seconds the client should wait before retrying, sent as `Retry-After`.
+    pub retry_after: Option<u64>,
+}
+
+/// JSON body of every API error response.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody<'a> {
+    pub code: ErrorCode,
+    pub message: &'a str,
+    pub details: Option<&'a Value>,
+    pub diagnostics: &'a [String],
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code: ErrorCode::for_status(status),
+            message: message.into(),
+            details: None,
+            diagnostics: Vec::new(),
+            retry_after: None,
+        }
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<String>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    /// Map a project loading failure to its code.
+    pub fn from_load_error(error: &anyhow::Error) -> Self {
+        let diagnostics = error.chain().skip(1).map(ToString::to_string).collect();
+        if error.downcast_ref::<PasswordRequiredError>().is_some() {
+            ApiError::new(StatusCode::BAD_REQUEST, "Encrypted KNX project: password required")
+                .with_code(ErrorCode::PasswordRequired)
+        } else if error.downcast_ref::<InvalidPasswordError>().is_some() {
+            ApiError::new(StatusCode::BAD_REQUEST, "Invalid password for KNX project")
+                .with_code(ErrorCode::InvalidPassword)
+        } else if let Some(unsupported) = error.downcast_ref::<UnsupportedSchemaError>() {
+            ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, unsupported.to_string())
+                .with_code(ErrorCode::UnsupportedSchema)
+                .with_details(serde_json::json!({ "schema_version": unsupported.schema_version }))
+        } else if let Some(limit) = error.downcast_ref::<ArchiveLimitError>() {
+            match limit {
+                ArchiveLimitError::UnsafePath { .. } => {
+                    ApiError::new(StatusCode::BAD_REQUEST, limit.to_string()).with_code(ErrorCode::InvalidArchive)
+                }
+                _ => ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, limit.to_string())
+                    .with_code(ErrorCode::ArchiveTooLarge),
+            }
+        } else {
+            ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to parse KNX project: {}", error),
+            )
+            .with_code(ErrorCode::ParseFailed)
+            .with_diagnostics(diagnostics)
+        }
+    }
+}
+
+impl From<ValidationError> for ApiError {
+    fn from(error: ValidationError) -> Self {
+        let message = error.to_string();
+        match error {
+            ValidationError::FileTooLarge { size, max } => {
+                ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, message)
+                    .with_code(ErrorCode::ArchiveTooLarge)
+                    .with_details(serde_json::json!({ "size": size, "max": max }))
+            }
+            ValidationError::UncompressedTooLarge { size, max } => {
+                ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, message)
+                    .with_code(ErrorCode::ArchiveTooLarge)
+                    .with_details(serde_json::json!({ "size": size, "max": max, "uncompressed": true }))
+            }
+            ValidationError::InvalidFileFormat { .. } => ApiError::new(StatusCode::BAD_REQUEST, message)
+                .with_code(ErrorCode::InvalidFileFormat),
+            ValidationError::InvalidArchive => {
+                ApiError::new(StatusCode::BAD_REQUEST, message).with_code(ErrorCode::InvalidArchive)
+            }
+            ValidationError::ArchiveError(cause) => ApiError::new(StatusCode::BAD_REQUEST, message)
+                .with_code(ErrorCode::InvalidArchive)
+                .with_diagnostics(vec![cause]),
+        }
+    }
+}
+
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        Self::new(status, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            message: &self.message,
+            details: self.details.as_ref(),
+            diagnostics: &self.diagnostics,
+        };
+        let mut response = (self.status, Json(body)).into_response();
+        if let Some(seconds) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, seconds.into());
+        }
+        response
+    }
+}
+
+/// Wrap API errors that did not come from an [`ApiError`], like extractor rejections and
+/// unknown routes, in the same JSON envelope.
+pub async fn json_errors(request: Request, next: Next) -> Response {
+    let is_api = request.uri().path().starts_with("/api/");
+    let response = next.run(request).await;
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if !is_api || !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let text = axum::body::to_bytes(body, MAX_PLAIN_ERROR_LEN)
+        .await
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .unwrap_or_default();
+    let message = if text.is_empty() {
+        status.canonical_reason().unwrap_or("Request failed").to_string()
+    } else {
+        text
+    };
+    let error = ApiError::new(status, message);
+    let body = ErrorBody {
+        code: error.code,
+        message: &error.message,
+        details: None,
+        diagnostics: &[],
+    };
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(
+        parts,
+        Body::from(serde_json::to_vec(&body).unwrap_or_default()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_errors, ApiError, ErrorCode};
+    use crate::server::validation::ValidationError;
+    use axum::{body::Body, http::Request, http::StatusCode, middleware, response::IntoResponse, routing::get, Router};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn body_json(response: axum::response::Response) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn renders_envelope_with_stable_codes() {
+        let error = ApiError::from(ValidationError::FileTooLarge { size: 20, max: 10 });
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = body_json(response).await;
+        assert_eq!(body["code"], "archive_too_large");
+        assert_eq!(body["details"]["max"], 10);
+        assert_eq!(body["diagnostics"], serde_json::json!([]));
+
+        let error = anyhow::Error::new(topobus_core::PasswordRequiredError);
+        let body = body_json(ApiError::from_load_error(&error).into_response()).await;
+        assert_eq!(body["code"], "password_required");
+        assert!(body["details"].is_null());
+
+        let error = anyhow::Error::new(topobus_core::ArchiveLimitError::TotalTooLarge { max: 10 })
+            .context("Failed to read P-0001/0.xml");
+        let response = ApiError::from_load_error(&error).into_response();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body_json(response).await["code"], "archive_too_large");
+
+        for code in ErrorCode::ALL {
+            assert_eq!(serde_json::to_value(code).unwrap(), code.as_str());
+        }
+    }
+
+    #[tokio::test]
+    async fn wraps_plain_api_errors() {
+        let app = Router::new()
+            .route("/api/plain", get(|| async { (StatusCode::BAD_REQUEST, "Missing field") }))
+            .route("/page", get(|| async { (StatusCode::BAD_REQUEST, "Not API") }))
+            .layer(middleware::from_fn(json_errors));
+
+        let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let body = body_json(app.clone().oneshot(request("/api/plain")).await.unwrap()).await;
+        assert_eq!(body["code"], "bad_request");
+        assert_eq!(body["message"], "Missing field");
+
+        let body = body_json(app.clone().oneshot(request("/api/missing")).await.unwrap()).await;
+        assert_eq!(body["code"], "not_found");
+
+        let page = app.oneshot(request("/page")).await.unwrap();
+        let text = axum::body::to_bytes(page.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&text[..], b"Not API");
+    }
+}
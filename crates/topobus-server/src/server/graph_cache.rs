@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use topobus_core::{ProjectGraphs, Redacted, RedactionPolicy};
+
+/// Parsed project graphs keyed by [`topobus_core::project_cache_key`].
+///
+/// Entries live in memory (least recently used evicted beyond `max_entries`) and, when a
+/// directory is configured, as `<key>.json` files that survive restarts (the oldest by
+/// modification time removed beyond `max_disk_entries`). The files hold decrypted project
+/// content in plain JSON, secrets included, so the directory and the files are private to the
+/// server user.
+pub struct GraphCache {
+    max_entries: usize,
+    dir: Option<PathBuf>,
+    max_disk_entries: usize,
+    inner: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    tick: u64,
+}
+
+struct CacheEntry {
+    graphs: Arc<ProjectGraphs>,
+    last_used: u64,
+}
+
+impl GraphCache {
+    pub fn new(max_entries: usize, dir: Option<PathBuf>, max_disk_entries: usize) -> Self {
+        let dir = dir.filter(|_| max_disk_entries > 0);
+        let dir = dir.and_then(|dir| match create_private_dir(&dir) {
+            Ok(()) => Some(dir),
+            Err(err) => {
+                log::warn!("Disabling on-disk project cache at {}: {}", dir.display(), err);
+                None
+            }
+        });
+        Self {
+            max_entries,
+            dir,
+            max_disk_entries,
+            inner: Mutex::new(CacheState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_entries > 0 || self.dir.is_some()
+    }
+
+    /// Look up `key` in memory, then on disk. Blocking: call from a blocking context.
+    pub fn get(&self, key: &str) -> Option<Arc<ProjectGraphs>> {
+        {
+            let mut state = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+            state.tick += 1;
+            let tick = state.tick;
+            if let Some(entry) = state.entries.get_mut(key) {
+                entry.last_used = tick;
+                return Some(entry.graphs.clone());
+            }
+        }
+
+        let path = self.entry_path(key)?;
+        let bytes = fs::read(&path).ok()?;
+        match serde_json::from_slice::<ProjectGraphs>(&bytes) {
+            Ok(graphs) => {
+                // Entries read back count as recently used when the directory is pruned.
+                if let Err(err) = fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+                {
+                    log::debug!("Failed to touch cache entry {}: {}", path.display(), err);
+                }
+                let graphs = Arc::new(graphs);
+                self.remember(key, graphs.clone());
+                Some(graphs)
+            }
+            Err(err) => {
+                log::warn!("Ignoring unreadable cache entry {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Store `graphs` under `key`. Blocking: call from a blocking context.
+    pub fn insert(&self, key: &str, graphs: Arc<ProjectGraphs>) {
+        if let Some(path) = self.entry_path(key) {
+            if let Err(err) = write_entry(&path, &graphs) {
+                log::warn!("Failed to write cache entry {}: {}", path.display(), err);
+            }
+            if let Some(dir) = &self.dir {
+                if let Err(err) = prune_dir(dir, self.max_disk_entries) {
+                    log::warn!("Failed to prune cache directory {}: {}", dir.display(), err);
+                }
+            }
+        }
+        self.remember(key, graphs);
+    }
+
+    fn remember(&self, key: &str, graphs: Arc<ProjectGraphs>) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut state = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        state.tick += 1;
+        let tick = state.tick;
+        if !state.entries.contains_key(key) {
+            while state.entries.len() >= self.max_entries {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                match oldest {
+                    Some(oldest) => {
+                        state.entries.remove(&oldest);
+                    }
+                    None => break,
+                }
+            }
+        }
+        state.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                graphs,
+                last_used: tick,
+            },
+        );
+    }
+
+    fn entry_path(&self, key: &str) -> Option<PathBuf> {
+        // Keys are hex digests; refuse anything else so a key can never escape the directory.
+        if !key.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.json", key)))
+    }
+}
+
+fn write_entry(path: &Path, graphs: &ProjectGraphs) -> anyhow::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    let mut options = fs::File::options();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    let json = serde_json::to_vec(&Redacted::new(graphs, RedactionPolicy::Include))?;
+    std::io::Write::write_all(&mut file, &json)?;
+    drop(file);
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Remove the least recently modified entries of `dir` until at most `max_entries` remain.
+fn prune_dir(dir: &Path, max_entries: usize) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let modified = entry.metadata()?.modified()?;
+            entries.push((modified, path));
+        }
+    }
+    if entries.len() <= max_entries {
+        return Ok(());
+    }
+    entries.sort();
+    let excess = entries.len() - max_entries;
+    for (_, path) in entries.into_iter().take(excess) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphCache;
+    use std::sync::Arc;
+    use topobus_core::{build_project_graphs, KnxProjectData};
+
+    fn graphs(name: &str) -> Arc<topobus_core::ProjectGraphs> {
+        Arc::new(build_project_graphs(&KnxProjectData {
+            project_name: name.to_string(),
+            ..KnxProjectData::default()
+        }))
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let cache = GraphCache::new(2, None, 0);
+        cache.insert("aa", graphs("a"));
+        cache.insert("bb", graphs("b"));
+        assert!(cache.get("aa").is_some());
+        cache.insert("cc", graphs("c"));
+        assert!(cache.get("bb").is_none());
+        assert_eq!(cache.get("aa").unwrap().project_name, "a");
+    }
+
+    #[test]
+    fn reloads_entries_from_disk() {
+        let dir = std::env::temp_dir().join(format!("topobus-cache-test-{}", std::process::id()));
+        GraphCache::new(4, Some(dir.clone()), 4).insert("ab12", graphs("stored"));
+
+        let reopened = GraphCache::new(4, Some(dir.clone()), 4);
+        assert_eq!(reopened.get("ab12").unwrap().project_name, "stored");
+        assert!(reopened.get("../ab12").is_none());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.join("ab12.json")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn removes_oldest_files_beyond_the_disk_limit() {
+        let dir = std::env::temp_dir().join(format!("topobus-cache-prune-{}", std::process::id()));
+        let cache = GraphCache::new(0, Some(dir.clone()), 2);
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        for (key, age) in [("aa", 2), ("bb", 1)] {
+            cache.insert(key, graphs(key));
+            let file = std::fs::File::options().write(true).open(dir.join(format!("{}.json", key)));
+            file.unwrap().set_modified(old - std::time::Duration::from_secs(age)).unwrap();
+        }
+        // Reading "aa" back makes "bb" the least recently used file.
+        assert!(cache.get("aa").is_some());
+        cache.insert("cc", graphs("cc"));
+
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(files, ["aa.json", "cc.json"]);
+    }
+}
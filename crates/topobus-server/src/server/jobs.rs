@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use topobus_core::{ProgressEvent, ProgressObserver};
+
+use crate::server::auth::Principal;
+use crate::server::error::{ApiError, ErrorCode};
+use crate::server::state::AppState;
+
+/// How long a job is kept after its last event, so late subscribers still get the outcome.
+const JOB_TTL: Duration = Duration::from_secs(10 * 60);
+const CHANNEL_CAPACITY: usize = 64;
+const MAX_JOB_ID_LEN: usize = 64;
+/// Jobs kept at once; each is cheap, but creating them must not exhaust memory.
+const MAX_JOBS: usize = 1024;
+
+/// Event published on a job stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    Progress(ProgressEvent),
+    Failed { code: ErrorCode, message: String },
+}
+
+impl JobEvent {
+    fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobEvent::Progress(ProgressEvent::Done { .. }) | JobEvent::Failed { .. }
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            JobEvent::Progress(_) => "progress",
+            JobEvent::Failed { .. } => "error",
+        }
+    }
+}
+
+/// Parse jobs, created by `POST /api/jobs` under a random id.
+///
+/// Uploads tag themselves with a `job_id` form field; `GET /api/jobs/{id}/events` may connect
+/// before or after the upload starts, so every job keeps its event history for replay. Only the
+/// principal that created a job can upload with it or follow it.
+pub struct JobRegistry {
+    inner: Mutex<HashMap<String, Job>>,
+}
+
+struct Job {
+    /// Who created the job; `None` when the server does not authenticate.
+    owner: Option<String>,
+    sender: broadcast::Sender<JobEvent>,
+    history: Vec<JobEvent>,
+    last_event: Instant,
+}
+
+impl Job {
+    fn new(owner: Option<String>) -> Self {
+        Self {
+            owner,
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            history: Vec::new(),
+            last_event: Instant::now(),
+        }
+    }
+
+    fn is_owned_by(&self, principal: Option<&Principal>) -> bool {
+        self.owner.as_deref() == principal.map(|principal| principal.0.as_str())
+    }
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start a job for `principal` and return its id; `None` when too many jobs are open.
+    pub fn create(&self, principal: Option<&Principal>) -> Option<String> {
+        let mut jobs = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        purge_stale(&mut jobs);
+        if jobs.len() >= MAX_JOBS {
+            return None;
+        }
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        jobs.insert(id.clone(), Job::new(principal.map(|principal| principal.0.clone())));
+        Some(id)
+    }
+
+    /// Whether `id` is a job `principal` created.
+    pub fn is_owned_by(&self, id: &str, principal: Option<&Principal>) -> bool {
+        let jobs = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        jobs.get(id).is_some_and(|job| job.is_owned_by(principal))
+    }
+
+    pub fn publish(&self, id: &str, event: JobEvent) {
+        let mut jobs = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        purge_stale(&mut jobs);
+        let Some(job) = jobs.get_mut(id) else {
+            return;
+        };
+        job.last_event = Instant::now();
+        job.history.push(event.clone());
+        // No receivers is fine: nobody is listening yet.
+        let _ = job.sender.send(event);
+    }
+
+    /// Return the events published so far and a receiver for the following ones, if `id` is
+    /// a job `principal` created.
+    pub fn subscribe(
+        &self,
+        id: &str,
+        principal: Option<&Principal>,
+    ) -> Option<(Vec<JobEvent>, broadcast::Receiver<JobEvent>)> {
+        let mut jobs = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        purge_stale(&mut jobs);
+        let job = jobs.get(id).filter(|job| job.is_owned_by(principal))?;
+        Some((job.history.clone(), job.sender.subscribe()))
+    }
+}
+
+fn purge_stale(jobs: &mut HashMap<String, Job>) {
+    jobs.retain(|_, job| job.last_event.elapsed() < JOB_TTL);
+}
+
+/// Reject ids that cannot be ones [`JobRegistry::create`] returned before looking them up.
+pub fn is_valid_job_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_JOB_ID_LEN
+        && id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Forwards core loader progress to a job and stops the loader once the request was abandoned.
+pub struct JobObserver<'a> {
+    pub jobs: &'a JobRegistry,
+    pub id: Option<&'a str>,
+    pub cancelled: &'a AtomicBool,
+}
+
+impl ProgressObserver for JobObserver<'_> {
+    fn on_progress(&self, event: &ProgressEvent) {
+        if let Some(id) = self.id {
+            self.jobs.publish(id, JobEvent::Progress(event.clone()));
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedJob {
+    pub id: String,
+}
+
+/// Start a job, to pass as `job_id` to an upload and follow on `/api/jobs/{id}/events`.
+pub async fn create_job(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+) -> Result<(StatusCode, Json<CreatedJob>), ApiError> {
+    let principal = principal.map(|Extension(principal)| principal);
+    let id = state.jobs.create(principal.as_ref()).ok_or_else(|| {
+        ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Too many open jobs, retry later")
+    })?;
+    Ok((StatusCode::CREATED, Json(CreatedJob { id })))
+}
+
+pub async fn job_events(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    if !is_valid_job_id(&id) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "Invalid job id"));
+    }
+    let principal = principal.map(|Extension(principal)| principal);
+    let (history, receiver) = state
+        .jobs
+        .subscribe(&id, principal.as_ref())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "No such job"))?;
+    let finished = history.iter().any(JobEvent::is_terminal);
+
+    let replay = stream::iter(history);
+    let live = stream::unfold(
+        (receiver, finished),
+        |(mut receiver, finished)| async move {
+            if finished {
+                return None;
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let finished = event.is_terminal();
+                        return Some((event, (receiver, finished)));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::debug!("Job stream lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    let events = replay.chain(live).map(|event| {
+        Ok(Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_valid_job_id, JobEvent, JobRegistry};
+    use crate::server::auth::Principal;
+    use crate::server::error::ErrorCode;
+    use topobus_core::ProgressEvent;
+
+    #[test]
+    fn replays_history_to_late_subscribers() {
+        let jobs = JobRegistry::new();
+        let id = jobs.create(None).unwrap();
+        jobs.publish(&id, JobEvent::Progress(ProgressEvent::LocatingDocuments));
+        let (history, mut receiver) = jobs.subscribe(&id, None).unwrap();
+        assert_eq!(history.len(), 1);
+
+        jobs.publish(
+            &id,
+            JobEvent::Failed {
+                code: ErrorCode::ParseFailed,
+                message: "boom".to_string(),
+            },
+        );
+        assert!(receiver.try_recv().unwrap().is_terminal());
+    }
+
+    #[test]
+    fn only_the_creator_follows_a_job() {
+        let jobs = JobRegistry::new();
+        let alice = Principal("alice".to_string());
+        let bob = Principal("bob".to_string());
+        let id = jobs.create(Some(&alice)).unwrap();
+        assert!(is_valid_job_id(&id));
+        assert!(jobs.is_owned_by(&id, Some(&alice)));
+        assert!(!jobs.is_owned_by(&id, Some(&bob)));
+        assert!(!jobs.is_owned_by(&id, None));
+        assert!(jobs.subscribe(&id, Some(&bob)).is_none());
+        assert!(jobs.subscribe(&id, Some(&alice)).is_some());
+
+        // Unknown ids are neither created by subscribing nor by publishing.
+        assert!(jobs.subscribe("guessed", None).is_none());
+        jobs.publish("guessed", JobEvent::Progress(ProgressEvent::LocatingDocuments));
+        assert!(!jobs.is_owned_by("guessed", None));
+    }
+
+    #[test]
+    fn validates_job_ids() {
+        assert!(is_valid_job_id("3f2c9a1e-5b7d-4e8f-9a0b-1c2d3e4f5a6b"));
+        assert!(!is_valid_job_id(""));
+        assert!(!is_valid_job_id("../etc"));
+    }
+}
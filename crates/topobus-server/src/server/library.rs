@@ -0,0 +1,472 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use topobus_core::{
+    build_project_graphs, ArchiveLimits, NoProgress, ProjectGraphs, ProjectLoader, Redacted,
+};
+
+use crate::server::error::{ApiError, ErrorCode};
+use crate::server::metrics::PhaseTimer;
+use crate::server::parse_pool::PoolError;
+use crate::server::state::AppState;
+
+/// How often the watched directory is listed. Polling, unlike file system notifications, also
+/// works on network shares, where ETS exports usually end up.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const CHANNEL_CAPACITY: usize = 64;
+
+/// Size and modification time of a file, to notice that it changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryStatus {
+    /// Found or changed, waiting for the file to settle and be parsed.
+    Loading,
+    Ready,
+    /// Protected, and no password for it in `[watch] passwords`.
+    Locked,
+    Failed,
+}
+
+/// A project of the watched directory, as listed on `/api/library`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryEntry {
+    /// File name without `.knxproj`.
+    pub id: String,
+    pub file_name: String,
+    pub size: u64,
+    /// Modification time of the file, in seconds since the Unix epoch.
+    pub modified: Option<u64>,
+    pub status: LibraryStatus,
+    /// Counts successful parses, so clients can tell whether they show the latest one.
+    pub revision: u64,
+    pub project_name: Option<String>,
+    pub devices: usize,
+    pub group_addresses: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<LibraryError>,
+    #[serde(skip)]
+    graphs: Option<Arc<ProjectGraphs>>,
+    /// File state of the last parse.
+    #[serde(skip)]
+    parsed: Option<FileStamp>,
+    /// File state seen on the last poll; a file is parsed once it stays the same for a poll,
+    /// so a half-written export is not picked up.
+    #[serde(skip)]
+    seen: Option<FileStamp>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Event published on `/api/library/events`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LibraryEvent {
+    /// A project was parsed, for the first time or again after its file changed.
+    Changed { id: String, revision: u64 },
+    Failed { id: String, code: ErrorCode, message: String },
+    Removed { id: String },
+}
+
+impl LibraryEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            LibraryEvent::Changed { .. } => "changed",
+            LibraryEvent::Failed { .. } => "failed",
+            LibraryEvent::Removed { .. } => "removed",
+        }
+    }
+}
+
+/// The `.knxproj` files of the directory given to `--watch`, parsed and kept up to date.
+pub struct Library {
+    dir: PathBuf,
+    entries: Mutex<BTreeMap<String, LibraryEntry>>,
+    events: broadcast::Sender<LibraryEvent>,
+}
+
+impl Library {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            entries: Mutex::new(BTreeMap::new()),
+            events: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+
+    pub fn entries(&self) -> Vec<LibraryEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.values().cloned().collect()
+    }
+
+    fn entry(&self, id: &str) -> Option<LibraryEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.get(id).cloned()
+    }
+
+    fn publish(&self, event: LibraryEvent) {
+        // No receivers is fine: no browser is connected.
+        let _ = self.events.send(event);
+    }
+
+    /// Record the files found by one poll and return the ids of those ready to be parsed.
+    fn update(&self, files: BTreeMap<String, (String, FileStamp)>) -> Vec<String> {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        let removed: Vec<String> = entries
+            .keys()
+            .filter(|id| !files.contains_key(*id))
+            .cloned()
+            .collect();
+        for id in removed {
+            log::info!("Library project {} was removed", id);
+            entries.remove(&id);
+            self.publish(LibraryEvent::Removed { id });
+        }
+
+        let mut settled = Vec::new();
+        for (id, (file_name, stamp)) in files {
+            let entry = entries.entry(id.clone()).or_insert_with(|| LibraryEntry {
+                id: id.clone(),
+                file_name,
+                size: stamp.size,
+                modified: unix_seconds(stamp.modified),
+                status: LibraryStatus::Loading,
+                revision: 0,
+                project_name: None,
+                devices: 0,
+                group_addresses: 0,
+                error: None,
+                graphs: None,
+                parsed: None,
+                seen: None,
+            });
+            if entry.parsed == Some(stamp) {
+                continue;
+            }
+            if entry.seen == Some(stamp) {
+                settled.push(id);
+            } else {
+                entry.seen = Some(stamp);
+                entry.size = stamp.size;
+                entry.modified = unix_seconds(stamp.modified);
+                entry.status = LibraryStatus::Loading;
+            }
+        }
+        settled
+    }
+
+    fn finish(&self, id: &str, stamp: FileStamp, result: Result<Arc<ProjectGraphs>, ApiError>) {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        let Some(entry) = entries.get_mut(id) else {
+            return;
+        };
+        entry.parsed = Some(stamp);
+        match result {
+            Ok(graphs) => {
+                entry.status = LibraryStatus::Ready;
+                entry.revision += 1;
+                entry.project_name = Some(graphs.project_name.clone());
+                entry.devices = graphs.devices.len();
+                entry.group_addresses = graphs.group_addresses.len();
+                entry.error = None;
+                entry.graphs = Some(graphs);
+                log::info!("Library project {} loaded (revision {})", id, entry.revision);
+                self.publish(LibraryEvent::Changed {
+                    id: id.to_string(),
+                    revision: entry.revision,
+                });
+            }
+            Err(error) => {
+                // Keep serving the previous parse, if any; the next export usually fixes it.
+                entry.status = if error.code == ErrorCode::PasswordRequired {
+                    LibraryStatus::Locked
+                } else {
+                    LibraryStatus::Failed
+                };
+                log::warn!("Library project {} failed to load: {}", id, error.message);
+                entry.error = Some(LibraryError {
+                    code: error.code,
+                    message: error.message.clone(),
+                });
+                self.publish(LibraryEvent::Failed {
+                    id: id.to_string(),
+                    code: error.code,
+                    message: error.message,
+                });
+            }
+        }
+    }
+}
+
+/// Poll the watched directory for as long as the server runs.
+pub async fn watch(state: AppState, library: Arc<Library>) {
+    log::info!("Watching {} for projects", library.dir.display());
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let dir = library.dir.clone();
+        let files = match tokio::task::spawn_blocking(move || list_projects(&dir)).await {
+            Ok(Ok(files)) => files,
+            Ok(Err(err)) => {
+                log::warn!("Unable to list {} ({})", library.dir.display(), err);
+                continue;
+            }
+            Err(_) => continue,
+        };
+        let stamps: BTreeMap<String, FileStamp> =
+            files.iter().map(|(id, (_, stamp))| (id.clone(), *stamp)).collect();
+        for id in library.update(files) {
+            let stamp = stamps[&id];
+            let file_name = library.entry(&id).map(|entry| entry.file_name).unwrap_or_default();
+            let password = state.config.watch_passwords.get(&file_name).map(str::to_string);
+            match parse(&state, library.dir.join(&file_name), password).await {
+                // Queue full: try again on the next poll.
+                Err(error) if error.code == ErrorCode::ServerBusy => {}
+                result => library.finish(&id, stamp, result),
+            }
+        }
+    }
+}
+
+async fn parse(
+    state: &AppState,
+    path: PathBuf,
+    password: Option<String>,
+) -> Result<Arc<ProjectGraphs>, ApiError> {
+    let app_programs = state.app_programs.clone();
+    let language = state.config.default_language.clone();
+    let limits = ArchiveLimits {
+        max_total_size: state.config.max_uncompressed_size_bytes as u64,
+        ..ArchiveLimits::default()
+    };
+    let metrics = state.metrics.clone();
+    let result = state
+        .parse_pool
+        .run(move |_cancelled| {
+            let started = Instant::now();
+            let observer = PhaseTimer::new(&metrics, &NoProgress);
+            let result = ProjectLoader::from_path(&path)
+                .password(password.as_deref())
+                .language(language.as_deref())
+                .archive_limits(limits)
+                .app_store(&app_programs)
+                .progress(&observer)
+                .load();
+            if let Ok(project) = &result {
+                metrics.record_parse(project, started.elapsed());
+            }
+            result
+        })
+        .await
+        .map_err(|error| match error {
+            PoolError::Busy => ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "Parse queue full"),
+            PoolError::TimedOut(limit) => ApiError::new(
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Parsing took longer than {} seconds", limit.as_secs()),
+            ),
+            PoolError::Panicked => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to parse KNX project: parser crashed",
+            ),
+        })?;
+    let project = result.map_err(|err| ApiError::from_load_error(&err))?;
+    Ok(Arc::new(build_project_graphs(&project)))
+}
+
+/// `.knxproj` files directly in `dir`, by id.
+fn list_projects(dir: &FsPath) -> std::io::Result<BTreeMap<String, (String, FileStamp)>> {
+    let mut files = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(file_name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some(id) = strip_extension(&file_name) else {
+            continue;
+        };
+        // Office lock files and ETS temporary files.
+        if id.starts_with('.') || id.starts_with('~') {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let stamp = FileStamp {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        };
+        files.insert(id.to_string(), (file_name, stamp));
+    }
+    Ok(files)
+}
+
+pub(crate) fn strip_extension(file_name: &str) -> Option<&str> {
+    let split = file_name.len().checked_sub(".knxproj".len())?;
+    let (stem, extension) = (file_name.get(..split)?, file_name.get(split..)?);
+    (extension.eq_ignore_ascii_case(".knxproj") && !stem.is_empty()).then_some(stem)
+}
+
+fn unix_seconds(time: Option<SystemTime>) -> Option<u64> {
+    time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_secs())
+}
+
+fn library(state: &AppState) -> Result<&Arc<Library>, ApiError> {
+    state.library.as_ref().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "No project library; start the server with --watch <dir>",
+        )
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryListing {
+    pub projects: Vec<LibraryEntry>,
+}
+
+pub async fn list_library(State(state): State<AppState>) -> Result<Json<LibraryListing>, ApiError> {
+    Ok(Json(LibraryListing {
+        projects: library(&state)?.entries(),
+    }))
+}
+
+/// Graphs of a library project, like `/api/upload` returns them.
+pub async fn get_library_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let entry = library(&state)?
+        .entry(&id)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("No library project {}", id)))?;
+    if let Some(graphs) = entry.graphs {
+        return Ok(Json(Redacted::new(graphs.as_ref(), state.config.redaction)).into_response());
+    }
+    Err(match entry.error {
+        Some(error) => ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, error.message).with_code(error.code),
+        None => ApiError::new(StatusCode::SERVICE_UNAVAILABLE, format!("{} is still loading", id))
+            .with_retry_after(POLL_INTERVAL.as_secs()),
+    })
+}
+
+pub async fn library_events(
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let receiver = library(&state)?.events.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::debug!("Library stream lagged, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .map(|event| {
+        Ok(Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_extension, FileStamp, Library, LibraryEvent, LibraryStatus};
+    use crate::server::error::{ApiError, ErrorCode};
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use topobus_core::{build_project_graphs, KnxProjectData, PasswordRequiredError};
+
+    fn poll(stamps: &[(&str, u64)]) -> BTreeMap<String, (String, FileStamp)> {
+        stamps
+            .iter()
+            .map(|(id, size)| {
+                let stamp = FileStamp {
+                    size: *size,
+                    modified: None,
+                };
+                (id.to_string(), (format!("{}.knxproj", id), stamp))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_files_once_they_settle() {
+        let library = Library::new(PathBuf::from("exports"));
+        let mut events = library.events.subscribe();
+
+        // A new file is parsed once it is unchanged for a poll.
+        assert!(library.update(poll(&[("house", 10)])).is_empty());
+        assert_eq!(library.entries()[0].status, LibraryStatus::Loading);
+        assert_eq!(library.update(poll(&[("house", 10)])), ["house"]);
+        let graphs = build_project_graphs(&KnxProjectData {
+            project_name: "House".to_string(),
+            ..Default::default()
+        });
+        library.finish("house", poll(&[("house", 10)])["house"].1, Ok(Arc::new(graphs)));
+        assert!(matches!(events.try_recv().unwrap(), LibraryEvent::Changed { revision: 1, .. }));
+        assert!(library.update(poll(&[("house", 10)])).is_empty());
+
+        // A new export is parsed again, while the old one is still served.
+        assert!(library.update(poll(&[("house", 12)])).is_empty());
+        let entry = &library.entries()[0];
+        assert_eq!(entry.status, LibraryStatus::Loading);
+        assert!(entry.graphs.is_some());
+        assert_eq!(library.update(poll(&[("house", 12)])), ["house"]);
+
+        assert!(library.update(poll(&[])).is_empty());
+        assert!(library.entries().is_empty());
+        assert!(matches!(events.try_recv().unwrap(), LibraryEvent::Removed { .. }));
+    }
+
+    #[test]
+    fn lists_protected_files_without_password_as_locked() {
+        let library = Library::new(PathBuf::from("exports"));
+        library.update(poll(&[("office", 10)]));
+        assert_eq!(library.update(poll(&[("office", 10)])), ["office"]);
+        let error = ApiError::from_load_error(&PasswordRequiredError.into());
+        library.finish("office", poll(&[("office", 10)])["office"].1, Err(error));
+
+        let entry = &library.entries()[0];
+        assert_eq!(entry.status, LibraryStatus::Locked);
+        assert_eq!(entry.error.as_ref().unwrap().code, ErrorCode::PasswordRequired);
+        // Not parsed again until the file changes.
+        assert!(library.update(poll(&[("office", 10)])).is_empty());
+    }
+
+    #[test]
+    fn recognises_project_files() {
+        assert_eq!(strip_extension("Haus Müller.knxproj"), Some("Haus Müller"));
+        assert_eq!(strip_extension("house.KNXPROJ"), Some("house"));
+        assert_eq!(strip_extension(".knxproj"), None);
+        assert_eq!(strip_extension("house.zip"), None);
+    }
+}
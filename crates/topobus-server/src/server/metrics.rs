@@ -0,0 +1,254 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue},
+    response::IntoResponse,
+};
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, Opts,
+    Registry, TextEncoder,
+};
+use topobus_core::knx::AppProgramStoreStats;
+use topobus_core::{KnxProjectData, ProgressEvent, ProgressObserver};
+
+use crate::server::state::AppState;
+
+const DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+const COUNT_BUCKETS: &[f64] = &[10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 25000.0];
+
+/// Counters and histograms served on `/metrics`, in the Prometheus text format.
+pub struct Metrics {
+    registry: Registry,
+    uploads: IntCounterVec,
+    upload_size: Histogram,
+    parse_duration: Histogram,
+    parse_phase_duration: HistogramVec,
+    project_devices: Histogram,
+    project_group_addresses: Histogram,
+    cache_lookups: IntCounterVec,
+    /// Application program store counters already added to `cache_lookups`.
+    app_programs_seen: Mutex<AppProgramStoreStats>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            uploads: IntCounterVec::new(
+                Opts::new(
+                    "topobus_uploads_total",
+                    "Uploaded projects by outcome: success, cached, the rejecting validation check \
+                     or the error code.",
+                ),
+                &["outcome"],
+            )
+            .expect("valid metric"),
+            upload_size: Histogram::with_opts(
+                HistogramOpts::new("topobus_upload_size_bytes", "Size of uploaded project files.")
+                    .buckets(exponential_buckets(64.0 * 1024.0, 4.0, 8).expect("valid buckets")),
+            )
+            .expect("valid metric"),
+            parse_duration: Histogram::with_opts(
+                HistogramOpts::new("topobus_parse_duration_seconds", "Time to parse a project.")
+                    .buckets(DURATION_BUCKETS.to_vec()),
+            )
+            .expect("valid metric"),
+            parse_phase_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "topobus_parse_phase_duration_seconds",
+                    "Time spent in each phase of parsing a project.",
+                )
+                .buckets(DURATION_BUCKETS.to_vec()),
+                &["phase"],
+            )
+            .expect("valid metric"),
+            project_devices: Histogram::with_opts(
+                HistogramOpts::new("topobus_project_devices", "Devices per parsed project.")
+                    .buckets(COUNT_BUCKETS.to_vec()),
+            )
+            .expect("valid metric"),
+            project_group_addresses: Histogram::with_opts(
+                HistogramOpts::new(
+                    "topobus_project_group_addresses",
+                    "Group addresses per parsed project.",
+                )
+                .buckets(COUNT_BUCKETS.to_vec()),
+            )
+            .expect("valid metric"),
+            cache_lookups: IntCounterVec::new(
+                Opts::new(
+                    "topobus_cache_lookups_total",
+                    "Lookups in the parsed project (graphs) and application program caches.",
+                ),
+                &["cache", "result"],
+            )
+            .expect("valid metric"),
+            app_programs_seen: Mutex::new(AppProgramStoreStats::default()),
+            registry,
+        };
+        for collector in [
+            Box::new(metrics.uploads.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.upload_size.clone()),
+            Box::new(metrics.parse_duration.clone()),
+            Box::new(metrics.parse_phase_duration.clone()),
+            Box::new(metrics.project_devices.clone()),
+            Box::new(metrics.project_group_addresses.clone()),
+            Box::new(metrics.cache_lookups.clone()),
+        ] {
+            metrics.registry.register(collector).expect("metric names are unique");
+        }
+        metrics
+    }
+
+    /// Count an upload of `size` bytes that ended with `outcome`.
+    pub fn record_upload(&self, outcome: &str, size: usize) {
+        self.uploads.with_label_values(&[outcome]).inc();
+        self.upload_size.observe(size as f64);
+    }
+
+    pub fn record_parse(&self, project: &KnxProjectData, duration: Duration) {
+        self.parse_duration.observe(duration.as_secs_f64());
+        self.project_devices.observe(project.devices.len() as f64);
+        self.project_group_addresses.observe(project.group_addresses.len() as f64);
+    }
+
+    pub fn record_graph_cache(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.cache_lookups.with_label_values(&["graphs", result]).inc();
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub fn render(&self, app_programs: AppProgramStoreStats) -> String {
+        {
+            // The store keeps its own totals; add what happened since the last scrape.
+            let mut seen = self.app_programs_seen.lock().unwrap_or_else(|err| err.into_inner());
+            let lookups = self.cache_lookups.clone();
+            lookups
+                .with_label_values(&["app_programs", "hit"])
+                .inc_by(app_programs.hits.saturating_sub(seen.hits));
+            lookups
+                .with_label_values(&["app_programs", "miss"])
+                .inc_by(app_programs.misses.saturating_sub(seen.misses));
+            *seen = app_programs;
+        }
+        let mut buffer = Vec::new();
+        if let Err(err) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            log::warn!("Failed to encode metrics: {}", err);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Progress observer timing the phases of one parse, and passing events on to `inner`.
+///
+/// A phase runs from the progress event that starts it to the next one, and is named after the
+/// event's `phase`. Steps within a phase, like each application program read, do not end it.
+pub struct PhaseTimer<'a> {
+    metrics: &'a Metrics,
+    inner: &'a dyn ProgressObserver,
+    current: Cell<(&'static str, Instant)>,
+}
+
+impl<'a> PhaseTimer<'a> {
+    pub fn new(metrics: &'a Metrics, inner: &'a dyn ProgressObserver) -> Self {
+        Self {
+            metrics,
+            inner,
+            current: Cell::new(("open_archive", Instant::now())),
+        }
+    }
+}
+
+impl ProgressObserver for PhaseTimer<'_> {
+    fn on_progress(&self, event: &ProgressEvent) {
+        if event.starts_phase() {
+            let (phase, started) = self.current.replace((event.phase(), Instant::now()));
+            self.metrics
+                .parse_phase_duration
+                .with_label_values(&[phase])
+                .observe(started.elapsed().as_secs_f64());
+        }
+        self.inner.on_progress(event);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+}
+
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        state.metrics.render(state.app_programs.stats()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, PhaseTimer};
+    use std::time::Duration;
+    use topobus_core::knx::AppProgramStoreStats;
+    use topobus_core::{KnxProjectData, NoProgress, ProgressEvent, ProgressObserver};
+
+    #[test]
+    fn renders_recorded_values() {
+        let metrics = Metrics::new();
+        metrics.record_upload("success", 2048);
+        metrics.record_upload("invalid_file_format", 10);
+        metrics.record_parse(&KnxProjectData::default(), Duration::from_millis(20));
+        metrics.record_graph_cache(false);
+
+        let timer = PhaseTimer::new(&metrics, &NoProgress);
+        for event in [
+            ProgressEvent::LocatingDocuments,
+            ProgressEvent::Topology,
+            ProgressEvent::GroupAddresses,
+            ProgressEvent::Devices,
+            ProgressEvent::Hardware {
+                manufacturer: "M-0083".to_string(),
+            },
+            ProgressEvent::Locations,
+            ProgressEvent::Done {
+                devices: 0,
+                group_addresses: 0,
+            },
+        ] {
+            timer.on_progress(&event);
+        }
+
+        let stats = |hits, misses| AppProgramStoreStats {
+            entries: 1,
+            hits,
+            misses,
+        };
+        metrics.render(stats(3, 1));
+        let text = metrics.render(stats(5, 1));
+        for line in [
+            "topobus_uploads_total{outcome=\"success\"} 1",
+            "topobus_uploads_total{outcome=\"invalid_file_format\"} 1",
+            "topobus_upload_size_bytes_count 2",
+            "topobus_parse_duration_seconds_count 1",
+            "topobus_project_devices_bucket{le=\"10\"} 1",
+            "topobus_parse_phase_duration_seconds_count{phase=\"devices\"} 1",
+            "topobus_parse_phase_duration_seconds_count{phase=\"locations\"} 1",
+            "topobus_cache_lookups_total{cache=\"graphs\",result=\"miss\"} 1",
+            "topobus_cache_lookups_total{cache=\"app_programs\",result=\"hit\"} 5",
+        ] {
+            assert!(text.contains(line), "missing {} in\n{}", line, text);
+        }
+        assert!(!text.contains("phase=\"done\""));
+        assert!(!text.contains("phase=\"hardware\""));
+    }
+}
@@ -1,52 +1,396 @@
 mod api;
 mod assets;
+mod auth;
 mod config;
+mod error;
+mod graph_cache;
+mod jobs;
+mod library;
+mod metrics;
+mod openapi;
+mod parse_pool;
+mod projects;
+mod sessions;
+mod state;
+mod store;
+mod tls;
 mod validation;
 
-use crate::cli::Args;
-use anyhow::Result;
+use crate::cli::ServeArgs;
+use anyhow::{bail, Context, Result};
 use axum::{
     extract::DefaultBodyLimit,
+    http::{HeaderName, HeaderValue, Method, Request},
+    middleware,
     routing::{get, post},
     Router,
 };
-use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
-use config::ServerConfig;
+use axum_server::tls_rustls::RustlsConfig;
+use futures_util::future::try_join_all;
+use std::net::{IpAddr, SocketAddr};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use state::AppState;
+
+pub use auth::Principal;
+pub use config::{
+    AuthConfig, AuthUser, LoadedProject, PreloadProject, ServerConfig, ServerConfigBuilder,
+    StoreConfig, TlsConfig, WatchPasswords,
+};
+
+const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The HTTP API and the bundled frontend, ready to serve or to nest in another router.
+///
+/// Projects in `config.projects` are served from the start. Files in `config.preload` are not
+/// read here; parse them first with [`ServerConfig::load_preloaded`]. With a `watch_dir`, this
+/// spawns the task watching it, so it must be called from a Tokio runtime.
+///
+/// The router turns errors into JSON, limits upload sizes, and adds CORS for
+/// `cors_allowed_origins`. It only checks credentials when `config.auth` has tokens or users.
+/// Request ids, tracing spans and TLS are left to the caller, like `topobus serve` adds them.
+/// An application authenticating requests itself can insert a [`Principal`] in their
+/// extensions, to record who stored a project and to keep upload progress private.
+///
+/// Fails when the project store in `config.store` cannot be opened.
+pub fn router(mut config: ServerConfig) -> Result<Router> {
+    if !config.preload.is_empty() {
+        log::warn!(
+            "{} preload project(s) were not loaded; call ServerConfig::load_preloaded first",
+            config.preload.len()
+        );
+    }
+    let projects = std::mem::take(&mut config.projects);
+    let state = AppState::new(config.clone())?;
+    for loaded in projects {
+        state.sessions.insert_pinned(loaded.id, loaded.project);
+    }
+    if let Some(library) = &state.library {
+        tokio::spawn(library::watch(state.clone(), library.clone()));
+    }
 
-pub async fn start_server(args: Args) -> Result<()> {
-    let config = ServerConfig::from_args(&args);
     let app = Router::new()
         // API routes
         .route("/api/upload", post(api::handle_upload))
+        .route("/api/inspect", post(api::handle_inspect))
         .route("/api/health", get(api::health_check))
+        .route("/api/openapi.json", get(openapi::openapi))
+        .route("/api/auth", get(auth::auth_status))
+        .route("/api/login", post(auth::login))
+        .route("/api/logout", post(auth::logout))
+        .route("/api/jobs", post(jobs::create_job))
+        .route("/api/jobs/{id}/events", get(jobs::job_events))
+        .route("/api/library", get(library::list_library))
+        .route("/api/library/events", get(library::library_events))
+        .route("/api/library/{id}", get(library::get_library_project))
+        .route("/api/store", get(store::list_store).post(store::create_stored_project))
+        .route(
+            "/api/store/{id}",
+            get(store::get_stored_project).delete(store::delete_stored_project),
+        )
+        .route("/api/store/{id}/graphs", get(store::get_stored_graphs))
+        .route("/api/store/{id}/download", get(store::download_stored_project))
+        .route("/api/projects", post(projects::create_project))
+        .route(
+            "/api/projects/{id}",
+            get(projects::get_project).delete(projects::delete_project),
+        )
+        .route("/api/projects/{id}/devices", get(projects::list_devices))
+        .route("/api/projects/{id}/devices/{address}", get(projects::get_device))
+        .route(
+            "/api/projects/{id}/group-addresses",
+            get(projects::list_group_addresses),
+        )
+        .route(
+            "/api/projects/{id}/group-addresses/{address}",
+            get(projects::get_group_address),
+        )
+        .route("/metrics", get(metrics::metrics))
         // PWA: serve SW with explicit no-cache + injected version
         .route("/sw.js", get(assets::serve_sw))
         .layer(DefaultBodyLimit::max(config.max_upload_size_bytes))
-        .with_state(config.clone())
+        .with_state(state.clone())
         // Static assets
         .fallback(assets::serve_assets);
-
-    let app = if config.enable_cors {
-        app.layer(CorsLayer::permissive())
+    let app = if config.auth.is_enabled() {
+        app.layer(middleware::from_fn_with_state(state, auth::require_auth))
     } else {
         app
     };
+    let app = app.layer(middleware::from_fn(error::json_errors));
+
+    Ok(match cors_layer(&config.cors_allowed_origins) {
+        Some(cors) => app.layer(cors),
+        None => app,
+    })
+}
+
+/// Give every request an `X-Request-Id`, unless the client or a proxy sent one, and log its
+/// records in a span carrying it. The id is echoed in the response.
+fn with_request_tracing(app: Router) -> Router {
+    app.layer(
+        TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
+            let id = request
+                .headers()
+                .get(REQUEST_ID)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("");
+            tracing::info_span!("request", id, method = %request.method(), path = request.uri().path())
+        }),
+    )
+    .layer(PropagateRequestIdLayer::new(REQUEST_ID))
+    .layer(SetRequestIdLayer::new(REQUEST_ID, MakeRequestUuid))
+}
+
+pub async fn start_server(args: ServeArgs) -> Result<()> {
+    let mut config = ServerConfig::load(&args)?;
+    let problems = config.validate();
+    if !problems.is_empty() {
+        bail!("Invalid configuration:\n  {}", problems.join("\n  "));
+    }
+    if let Some(path) = &config.config_file {
+        log::info!("Using config file {}", path.display());
+    }
+
+    if config.auth.is_enabled() {
+        log::info!("API authentication enabled");
+    } else if config.auth.allow_unauthenticated {
+        log::warn!("API authentication disabled: anyone who can reach the server can use it");
+    }
+    config.load_preloaded().await?;
+    let app = with_request_tracing(router(config.clone())?);
+
+    let tls = if config.tls.is_enabled() {
+        Some(tls::rustls_config(&config.tls, &config.bind_addresses).await?)
+    } else {
+        None
+    };
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
-    let addr = SocketAddr::from((config.bind_address.parse::<std::net::IpAddr>()?, config.port));
-    log::info!("Server listening on http://{}", addr);
+    let mut listeners = Vec::with_capacity(config.bind_addresses.len());
+    for address in &config.bind_addresses {
+        let addr = SocketAddr::new(*address, config.port);
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to listen on {}", addr))?;
+        log::info!("Server listening on {}://{}", scheme, addr);
+        listeners.push(listener);
+    }
 
-    // Open browser unless --no-browser is specified
-    if !args.no_browser {
-        let url = format!("http://{}", addr);
+    if config.open_browser {
+        let url = browser_url(scheme, config.bind_addresses[0], config.port);
         log::info!("Opening browser at {}", url);
         if let Err(e) = open::that(&url) {
             log::warn!("Failed to open browser: {}", e);
         }
     }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    try_join_all(
+        listeners
+            .into_iter()
+            .map(|listener| serve(listener, app.clone(), tls.clone())),
+    )
+    .await?;
 
     Ok(())
 }
+
+/// Print the effective configuration of `topobus serve` and fail when it has problems.
+pub fn check_config(args: ServeArgs) -> Result<()> {
+    let config = ServerConfig::load(&args)?;
+    match &config.config_file {
+        Some(path) => println!("Config file: {}", path.display()),
+        None => println!("Config file: none, using defaults and environment"),
+    }
+    println!("{:#?}", config);
+
+    let mut problems = config.validate();
+    // The store can only be checked against its key when both exist; serving creates them.
+    if let (Some(dir), Some(key_file)) = (&config.store.dir, &config.store.key_file) {
+        if problems.is_empty() && dir.is_dir() && key_file.exists() {
+            if let Err(err) = store::ProjectStore::open(dir.clone(), key_file) {
+                problems.push(format!("store: {:#}", err));
+            }
+        }
+    }
+    if problems.is_empty() {
+        println!("Configuration OK");
+        return Ok(());
+    }
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+    bail!("{} problem(s) found in the configuration", problems.len())
+}
+
+pub fn hash_password() -> Result<()> {
+    auth::print_password_hash()
+}
+
+/// CORS for the configured origins; `None` when cross-origin calls are not allowed.
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers(Any);
+    if origins.iter().any(|origin| origin == "*") {
+        return Some(cors.allow_origin(Any));
+    }
+    // Origins were checked by `ServerConfig::validate`.
+    let origins = origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok());
+    Some(cors.allow_origin(AllowOrigin::list(origins)))
+}
+
+async fn serve(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+) -> std::io::Result<()> {
+    // Client addresses are used to throttle wrong passwords.
+    match tls {
+        None => {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+        }
+        Some(tls) => {
+            axum_server::from_tcp_rustls(listener.into_std()?, tls)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+        }
+    }
+}
+
+fn browser_url(scheme: &str, address: IpAddr, port: u16) -> String {
+    if address.is_unspecified() {
+        format!("{}://localhost:{}", scheme, port)
+    } else {
+        format!("{}://{}", scheme, SocketAddr::new(address, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{router, Principal, ServerConfig};
+    use axum::{body::Body, http::Request, http::StatusCode, Extension, Router};
+    use topobus_core::knx::GroupAddressInfo;
+    use topobus_core::KnxProjectData;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn serves_api_and_frontend_under_a_prefix() {
+        let project = KnxProjectData {
+            project_name: "House".to_string(),
+            group_addresses: vec![GroupAddressInfo {
+                address: "1/1/1".to_string(),
+                security_key: Some("c2VjcmV0".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let config = ServerConfig::builder().project("house", project).build().unwrap();
+        let app = Router::new().nest("/topobus", router(config).unwrap());
+
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(get("/topobus/api/projects/house")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let session: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(session["project_name"], "House");
+
+        let response = app
+            .clone()
+            .oneshot(get("/topobus/api/projects/house/group-addresses/1-1-1"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let address: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(address["security_key"], topobus_core::knx::REDACTED);
+
+        let response = app
+            .clone()
+            .oneshot(get("/topobus/api/projects/house/group-addresses?main=1&middle=1"))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let page: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(page["total"], 1);
+        let response = app
+            .clone()
+            .oneshot(get("/topobus/api/projects/house/group-addresses?middle=1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "bad_request");
+
+        let page = app.clone().oneshot(get("/topobus")).await.unwrap();
+        assert_eq!(page.headers()["location"], "/topobus/index.html");
+        let page = app.clone().oneshot(get("/topobus/index.html")).await.unwrap();
+        assert_eq!(page.status(), StatusCode::OK);
+        let delete = Request::delete("/topobus/api/projects/house").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(delete).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["code"], "project_pinned");
+        let response = app.clone().oneshot(get("/topobus/api/projects/house")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let missing = app.oneshot(get("/topobus/api/nothing")).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        // Request ids are the embedding application's business.
+        assert!(missing.headers().get("x-request-id").is_none());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_start_without_its_store() {
+        let dir = std::env::temp_dir().join(format!("topobus-router-store-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // The store directory cannot be created under a file.
+        std::fs::write(dir.join("file"), "").unwrap();
+        let config = ServerConfig::builder()
+            .store(dir.join("file/store"), dir.join("store.key"))
+            .build()
+            .unwrap();
+        let result = router(config);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn uses_principals_of_the_embedding_application() {
+        let config = ServerConfig::builder().build().unwrap();
+        let as_user = |name: &str| {
+            Router::new()
+                .nest("/topobus", router(config.clone()).unwrap())
+                .layer(Extension(Principal(name.to_string())))
+        };
+        let post = Request::post("/topobus/api/jobs").body(Body::empty()).unwrap();
+        let response = as_user("alice").oneshot(post).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let events = format!("/topobus/api/jobs/{}/events", job["id"].as_str().unwrap());
+        let get = || Request::get(events.as_str()).body(Body::empty()).unwrap();
+        let response = as_user("bob").oneshot(get()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_settings() {
+        let error = ServerConfig::builder().max_sessions(0).build().unwrap_err();
+        assert!(error.to_string().contains("limits.max_sessions"));
+        let error = ServerConfig::builder()
+            .project("house", KnxProjectData::default())
+            .project("house", KnxProjectData::default())
+            .build()
+            .unwrap_err();
+        assert!(error.to_string().contains("'house' is used twice"));
+    }
+}
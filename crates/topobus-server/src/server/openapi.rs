@@ -0,0 +1,447 @@
+use axum::Json;
+use serde_json::{json, Map, Value};
+
+use crate::server::error::ErrorCode;
+
+/// OpenAPI description of the HTTP API, served at `/api/openapi.json`.
+pub async fn openapi() -> Json<Value> {
+    Json(document())
+}
+
+fn document() -> Value {
+    let upload_form = json!({
+        "required": true,
+        "content": {
+            "multipart/form-data": {
+                "schema": {
+                    "type": "object",
+                    "required": ["file"],
+                    "properties": {
+                        "file": { "type": "string", "format": "binary", "description": "The .knxproj archive." },
+                        "password": { "type": "string", "description": "Project password, for protected projects." },
+                        "product_language": { "type": "string", "description": "Language for application program texts, e.g. `de-DE`." },
+                        "job_id": { "type": "string", "description": "Id from `POST /api/jobs`, to follow progress on `/api/jobs/{id}/events`." }
+                    }
+                }
+            }
+        }
+    });
+    let mut store_form = upload_form.clone();
+    store_form["content"]["multipart/form-data"]["schema"]["properties"]["tags"] =
+        json!({ "type": "string", "description": "Comma-separated tags; the field may be repeated." });
+    let stored_id = path_parameter("id", "Stored project id.");
+    let id = path_parameter("id", "Project session id.");
+    let address = path_parameter("address", "Individual address `1.1.4`, or group address `1/2/3` (also `1-2-3`).");
+    let paging = [
+        query_parameter("offset", "integer", "Items to skip."),
+        query_parameter("limit", "integer", "Items per page, at most 1000."),
+    ];
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "TopoBus API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Errors are sent as an `Error` object with a stable `code`; see the `ErrorCode` schema."
+        },
+        "paths": {
+            "/api/health": {
+                "get": operation("Liveness check.", None, ok("text/plain", json!({ "type": "string" })), &[])
+            },
+            "/api/openapi.json": {
+                "get": operation("This document.", None, ok_json(json!({ "type": "object" })), &[])
+            },
+            "/api/auth": {
+                "get": operation("Whether the API requires credentials, and who is signed in.", None, ok_json(json!({ "$ref": "#/components/schemas/AuthStatus" })), &[])
+            },
+            "/api/login": {
+                "post": operation(
+                    "Sign in with a user name and password, or a token; sets the session cookie.",
+                    Some(json!({
+                        "required": true,
+                        "content": { "application/json": { "schema": {
+                            "type": "object",
+                            "properties": {
+                                "username": { "type": "string" },
+                                "password": { "type": "string" },
+                                "token": { "type": "string" }
+                            }
+                        } } }
+                    })),
+                    ok_json(json!({ "$ref": "#/components/schemas/AuthStatus" })),
+                    &[ErrorCode::InvalidCredentials, ErrorCode::NotFound],
+                )
+            },
+            "/api/logout": {
+                "post": operation("Sign out and clear the session cookie.", None, no_content(), &[])
+            },
+            "/api/upload": {
+                "post": operation(
+                    "Parse a project and return its graphs.",
+                    Some(upload_form.clone()),
+                    ok_json(json!({ "$ref": "#/components/schemas/ProjectGraphs" })),
+                    UPLOAD_ERRORS,
+                )
+            },
+            "/api/inspect": {
+                "post": operation(
+                    "Summarise a project without parsing it, e.g. to ask for its password first.",
+                    Some(upload_form.clone()),
+                    ok_json(json!({ "type": "object" })),
+                    &[ErrorCode::BadRequest, ErrorCode::InvalidFileFormat, ErrorCode::InvalidArchive, ErrorCode::ArchiveTooLarge],
+                )
+            },
+            "/api/jobs": {
+                "post": operation(
+                    "Start a job to follow an upload's progress; only the same user can use it.",
+                    None,
+                    created(json!({
+                        "type": "object",
+                        "properties": { "id": { "type": "string" } }
+                    })),
+                    &[ErrorCode::ServerBusy],
+                )
+            },
+            "/api/jobs/{id}/events": {
+                "get": with_parameters(
+                    operation(
+                        "Server-sent `progress` and `error` events of an upload with this `job_id`. An `error` event carries `code` and `message`.",
+                        None,
+                        ok("text/event-stream", json!({ "type": "string" })),
+                        &[ErrorCode::BadRequest, ErrorCode::NotFound],
+                    ),
+                    vec![path_parameter("id", "The upload's `job_id`.")],
+                )
+            },
+            "/api/library": {
+                "get": operation(
+                    "Projects of the directory watched with `--watch`.",
+                    None,
+                    ok_json(json!({
+                        "type": "object",
+                        "properties": { "projects": { "type": "array", "items": { "$ref": "#/components/schemas/LibraryEntry" } } }
+                    })),
+                    &[ErrorCode::NotFound],
+                )
+            },
+            "/api/library/events": {
+                "get": operation(
+                    "Server-sent `changed` (`id`, `revision`), `failed` (`id`, `code`, `message`) and `removed` (`id`) events of the watched directory.",
+                    None,
+                    ok("text/event-stream", json!({ "type": "string" })),
+                    &[ErrorCode::NotFound],
+                )
+            },
+            "/api/library/{id}": {
+                "get": with_parameters(
+                    operation(
+                        "Graphs of a library project, as returned by `/api/upload`.",
+                        None,
+                        ok_json(json!({ "$ref": "#/components/schemas/ProjectGraphs" })),
+                        &[ErrorCode::NotFound, ErrorCode::ServerBusy, ErrorCode::PasswordRequired, ErrorCode::ParseFailed],
+                    ),
+                    vec![path_parameter("id", "File name without `.knxproj`.")],
+                )
+            },
+            "/api/store": {
+                "get": with_parameters(
+                    operation(
+                        "Projects kept in the store, most recent first.",
+                        None,
+                        ok_json(json!({
+                            "type": "object",
+                            "properties": { "projects": { "type": "array", "items": { "$ref": "#/components/schemas/StoredProject" } } }
+                        })),
+                        &[ErrorCode::NotFound],
+                    ),
+                    vec![
+                        query_parameter("q", "string", "Words that must all appear in the name, project or contract number, description, file name, uploader or tags."),
+                        query_parameter("tag", "string", "Only projects with this tag."),
+                    ],
+                ),
+                "post": operation(
+                    "Parse a project and keep it, encrypted, in the store shared by all users.",
+                    Some(store_form),
+                    created(json!({ "$ref": "#/components/schemas/StoredProject" })),
+                    UPLOAD_ERRORS,
+                )
+            },
+            "/api/store/{id}": {
+                "get": with_parameters(
+                    operation("Metadata of a stored project.", None, ok_json(json!({ "$ref": "#/components/schemas/StoredProject" })), &[ErrorCode::NotFound]),
+                    vec![stored_id.clone()],
+                ),
+                "delete": with_parameters(
+                    operation("Delete a stored project.", None, no_content(), &[ErrorCode::NotFound]),
+                    vec![stored_id.clone()],
+                )
+            },
+            "/api/store/{id}/graphs": {
+                "get": with_parameters(
+                    operation(
+                        "Graphs of a stored project, as returned by `/api/upload`.",
+                        None,
+                        ok_json(json!({ "$ref": "#/components/schemas/ProjectGraphs" })),
+                        &[ErrorCode::NotFound],
+                    ),
+                    vec![stored_id.clone()],
+                )
+            },
+            "/api/store/{id}/download": {
+                "get": with_parameters(
+                    operation(
+                        "The project file as it was uploaded.",
+                        None,
+                        ok("application/octet-stream", json!({ "type": "string", "format": "binary" })),
+                        &[ErrorCode::NotFound],
+                    ),
+                    vec![stored_id],
+                )
+            },
+            "/api/projects": {
+                "post": operation(
+                    "Parse a project and keep it as a session for the endpoints below.",
+                    Some(upload_form),
+                    created(json!({ "$ref": "#/components/schemas/ProjectSession" })),
+                    UPLOAD_ERRORS,
+                )
+            },
+            "/api/projects/{id}": {
+                "get": with_parameters(
+                    operation("Project session summary.", None, ok_json(json!({ "$ref": "#/components/schemas/ProjectSession" })), &[ErrorCode::NotFound]),
+                    vec![id.clone()],
+                ),
+                "delete": with_parameters(
+                    operation("Drop a project session.", None, no_content(), &[ErrorCode::NotFound, ErrorCode::ProjectPinned]),
+                    vec![id.clone()],
+                )
+            },
+            "/api/projects/{id}/devices": {
+                "get": with_parameters(
+                    operation("Devices, optionally of one area or line.", None, ok_json(page()), &[ErrorCode::NotFound]),
+                    vec![
+                        id.clone(),
+                        query_parameter("area", "string", "Area number, e.g. `1`."),
+                        query_parameter("line", "string", "Line as `area.line`, e.g. `1.1`."),
+                        paging[0].clone(),
+                        paging[1].clone(),
+                    ],
+                )
+            },
+            "/api/projects/{id}/devices/{address}": {
+                "get": with_parameters(
+                    operation("One device.", None, ok_json(json!({ "type": "object" })), &[ErrorCode::NotFound]),
+                    vec![id.clone(), address.clone()],
+                )
+            },
+            "/api/projects/{id}/group-addresses": {
+                "get": with_parameters(
+                    operation("Group addresses, optionally of one main or middle group.", None, ok_json(page()), &[ErrorCode::BadRequest, ErrorCode::NotFound]),
+                    vec![
+                        id.clone(),
+                        query_parameter("main", "string", "Main group, e.g. `1`."),
+                        query_parameter("middle", "string", "Middle group, e.g. `2`; requires `main`."),
+                        paging[0].clone(),
+                        paging[1].clone(),
+                    ],
+                )
+            },
+            "/api/projects/{id}/group-addresses/{address}": {
+                "get": with_parameters(
+                    operation("One group address.", None, ok_json(json!({ "type": "object" })), &[ErrorCode::NotFound]),
+                    vec![id, address],
+                )
+            }
+        },
+        "components": {
+            "schemas": {
+                "Error": {
+                    "type": "object",
+                    "required": ["code", "message", "details", "diagnostics"],
+                    "properties": {
+                        "code": { "$ref": "#/components/schemas/ErrorCode" },
+                        "message": { "type": "string", "description": "Human-readable; may change between versions." },
+                        "details": { "type": ["object", "null"], "description": "Structured data about the error, depending on `code`." },
+                        "diagnostics": { "type": "array", "items": { "type": "string" }, "description": "Underlying causes, for bug reports." }
+                    }
+                },
+                "ErrorCode": error_code_schema(),
+                "AuthStatus": {
+                    "type": "object",
+                    "properties": {
+                        "enabled": { "type": "boolean" },
+                        "user": { "type": ["string", "null"] }
+                    }
+                },
+                "LibraryEntry": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "file_name": { "type": "string" },
+                        "size": { "type": "integer" },
+                        "modified": { "type": ["integer", "null"], "description": "Seconds since the Unix epoch." },
+                        "status": { "type": "string", "enum": ["loading", "ready", "failed"] },
+                        "revision": { "type": "integer", "description": "Number of successful parses." },
+                        "project_name": { "type": ["string", "null"] },
+                        "devices": { "type": "integer" },
+                        "group_addresses": { "type": "integer" },
+                        "error": {
+                            "type": "object",
+                            "properties": { "code": { "$ref": "#/components/schemas/ErrorCode" }, "message": { "type": "string" } }
+                        }
+                    }
+                },
+                "StoredProject": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "file_name": { "type": "string" },
+                        "size": { "type": "integer" },
+                        "project_name": { "type": "string" },
+                        "project_info": { "type": "object" },
+                        "uploaded_at": { "type": "integer", "description": "Seconds since the Unix epoch." },
+                        "uploaded_by": { "type": ["string", "null"], "description": "User name, `token` for token logins; null without authentication." },
+                        "tags": { "type": "array", "items": { "type": "string" } },
+                        "devices": { "type": "integer" },
+                        "group_addresses": { "type": "integer" }
+                    }
+                },
+                "ProjectSession": {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "project_name": { "type": "string" },
+                        "project_info": { "type": "object" },
+                        "areas": { "type": "integer" },
+                        "lines": { "type": "integer" },
+                        "devices": { "type": "integer" },
+                        "group_addresses": { "type": "integer" }
+                    }
+                },
+                "ProjectGraphs": {
+                    "type": "object",
+                    "properties": {
+                        "project_name": { "type": "string" },
+                        "project_info": { "type": "object" },
+                        "topology_graph": { "type": "object" },
+                        "group_address_graph": { "type": "object" },
+                        "devices": { "type": "array", "items": { "type": "object" } },
+                        "group_addresses": { "type": "array", "items": { "type": "object" } },
+                        "locations": { "type": "array", "items": { "type": "object" } }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Errors an upload can end with, besides authentication.
+const UPLOAD_ERRORS: &[ErrorCode] = &[
+    ErrorCode::BadRequest,
+    ErrorCode::InvalidFileFormat,
+    ErrorCode::InvalidArchive,
+    ErrorCode::ArchiveTooLarge,
+    ErrorCode::PasswordRequired,
+    ErrorCode::InvalidPassword,
+    ErrorCode::UnsupportedSchema,
+    ErrorCode::ParseFailed,
+    ErrorCode::ServerBusy,
+    ErrorCode::ParseTimeout,
+];
+
+fn error_code_schema() -> Value {
+    let descriptions: Vec<String> = ErrorCode::ALL
+        .iter()
+        .map(|code| format!("- `{}`: {}", code.as_str(), code.description()))
+        .collect();
+    json!({
+        "type": "string",
+        "enum": ErrorCode::ALL.iter().map(|code| code.as_str()).collect::<Vec<_>>(),
+        "description": descriptions.join("\n")
+    })
+}
+
+fn operation(summary: &str, body: Option<Value>, success: (&str, Value), errors: &[ErrorCode]) -> Value {
+    let mut responses = Map::new();
+    responses.insert(success.0.to_string(), success.1);
+    let codes: Vec<_> = errors
+        .iter()
+        .chain([ErrorCode::AuthRequired, ErrorCode::TooManyAttempts, ErrorCode::InternalError].iter())
+        .map(|code| code.as_str())
+        .collect();
+    responses.insert(
+        "default".to_string(),
+        json!({
+            "description": format!("Error; `code` is one of: {}.", codes.join(", ")),
+            "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Error" } } }
+        }),
+    );
+    let mut operation = json!({ "summary": summary, "responses": responses });
+    if let Some(body) = body {
+        operation["requestBody"] = body;
+    }
+    operation
+}
+
+fn with_parameters(mut operation: Value, parameters: Vec<Value>) -> Value {
+    operation["parameters"] = Value::Array(parameters);
+    operation
+}
+
+fn ok(content_type: &str, schema: Value) -> (&'static str, Value) {
+    ("200", json!({ "description": "OK", "content": { content_type: { "schema": schema } } }))
+}
+
+fn ok_json(schema: Value) -> (&'static str, Value) {
+    ok("application/json", schema)
+}
+
+fn created(schema: Value) -> (&'static str, Value) {
+    ("201", json!({ "description": "Created", "content": { "application/json": { "schema": schema } } }))
+}
+
+fn no_content() -> (&'static str, Value) {
+    ("204", json!({ "description": "No content" }))
+}
+
+fn page() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "items": { "type": "array", "items": { "type": "object" } },
+            "total": { "type": "integer" },
+            "offset": { "type": "integer" },
+            "limit": { "type": "integer" }
+        }
+    })
+}
+
+fn path_parameter(name: &str, description: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" }, "description": description })
+}
+
+fn query_parameter(name: &str, kind: &str, description: &str) -> Value {
+    json!({ "name": name, "in": "query", "required": false, "schema": { "type": kind }, "description": description })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::document;
+    use crate::server::error::ErrorCode;
+
+    #[test]
+    fn documents_every_error_code() {
+        let document = document();
+        let codes = document["components"]["schemas"]["ErrorCode"]["enum"].as_array().unwrap();
+        assert_eq!(codes.len(), ErrorCode::ALL.len());
+        for code in ["password_required", "invalid_password", "archive_too_large", "unsupported_schema"] {
+            assert!(codes.iter().any(|listed| listed == code), "{}", code);
+        }
+        let upload = &document["paths"]["/api/upload"]["post"];
+        assert!(upload["responses"]["default"]["description"]
+            .as_str()
+            .unwrap()
+            .contains("password_required"));
+        assert_eq!(document["paths"]["/api/projects/{id}"]["delete"]["parameters"][0]["name"], "id");
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+
+/// Runs CPU-heavy project parsing on the blocking thread pool.
+///
+/// At most `max_concurrent` jobs run at once and at most `max_queued` more wait for a slot;
+/// anything beyond that is rejected immediately so the caller can answer 503. A job whose caller
+/// goes away (client disconnect, timeout) before it starts is skipped; a job that is already
+/// running keeps its slot until it finishes, so the concurrency bound always holds.
+pub struct ParsePool {
+    permits: Arc<Semaphore>,
+    max_pending: usize,
+    pending: AtomicUsize,
+    timeout: Duration,
+}
+
+#[derive(Debug)]
+pub enum PoolError {
+    /// Every slot and queue entry is taken.
+    Busy,
+    /// The job did not finish within the configured timeout.
+    TimedOut(Duration),
+    /// The job panicked.
+    Panicked,
+}
+
+impl ParsePool {
+    pub fn new(max_concurrent: usize, max_queued: usize, timeout: Duration) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_pending: max_concurrent.saturating_add(max_queued),
+            pending: AtomicUsize::new(0),
+            timeout,
+        }
+    }
+
+    /// Run `job` on the blocking pool.
+    ///
+    /// The job receives a flag that becomes `true` once the caller stopped waiting for it.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, PoolError>
+    where
+        F: FnOnce(&AtomicBool) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let _slot = PendingSlot::acquire(&self.pending, self.max_pending).ok_or(PoolError::Busy)?;
+        let cancelled = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+
+        let work = async {
+            let permit = self
+                .permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("parse semaphore is never closed");
+            let flag = cancelled.0.clone();
+            tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                if flag.load(Ordering::Relaxed) {
+                    return None;
+                }
+                Some(job(&flag))
+            })
+            .await
+        };
+
+        match tokio::time::timeout(self.timeout, work).await {
+            Ok(Ok(Some(value))) => Ok(value),
+            // Only reachable when the flag was set, i.e. after the caller stopped waiting.
+            Ok(Ok(None)) => Err(PoolError::TimedOut(self.timeout)),
+            Ok(Err(_)) => Err(PoolError::Panicked),
+            Err(_) => Err(PoolError::TimedOut(self.timeout)),
+        }
+    }
+}
+
+struct PendingSlot<'a>(&'a AtomicUsize);
+
+impl<'a> PendingSlot<'a> {
+    fn acquire(counter: &'a AtomicUsize, max: usize) -> Option<Self> {
+        counter
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (current < max).then_some(current + 1)
+            })
+            .ok()
+            .map(|_| Self(counter))
+    }
+}
+
+impl Drop for PendingSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Marks the job as abandoned when the request future is dropped or times out.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParsePool, PoolError};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn rejects_jobs_beyond_queue_capacity() {
+        let pool = Arc::new(ParsePool::new(1, 0, Duration::from_secs(5)));
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+
+        let running = {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                pool.run(move |_| {
+                    started_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    1
+                })
+                .await
+            })
+        };
+        tokio::task::spawn_blocking(move || started_rx.recv().unwrap())
+            .await
+            .unwrap();
+
+        assert!(matches!(pool.run(|_| 2).await, Err(PoolError::Busy)));
+
+        release_tx.send(()).unwrap();
+        assert_eq!(running.await.unwrap().unwrap(), 1);
+        assert_eq!(pool.run(|_| 3).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn times_out_long_jobs() {
+        let pool = ParsePool::new(1, 1, Duration::from_millis(10));
+        let result = pool
+            .run(|_| std::thread::sleep(Duration::from_millis(200)))
+            .await;
+        assert!(matches!(result, Err(PoolError::TimedOut(_))));
+    }
+}
@@ -0,0 +1,246 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use topobus_core::knx::{DeviceInfo, GroupAddressInfo, KnxProjectData, ProjectInfo};
+use topobus_core::Redacted;
+
+use crate::server::api::{parse_upload, read_upload_form};
+use crate::server::auth::Principal;
+use crate::server::error::ApiError;
+use crate::server::sessions::Removal;
+use crate::server::state::AppState;
+
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
+
+/// Responses carry project secrets as the server's redaction policy allows.
+type ApiResult<T> = Result<Json<Redacted<T>>, ApiError>;
+
+/// Summary returned when a project is uploaded or fetched by id.
+#[derive(Debug, Serialize)]
+pub struct ProjectSession {
+    pub id: String,
+    pub project_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_info: Option<ProjectInfo>,
+    pub areas: usize,
+    pub lines: usize,
+    pub devices: usize,
+    pub group_addresses: usize,
+}
+
+impl ProjectSession {
+    fn new(id: String, project: &KnxProjectData) -> Self {
+        Self {
+            id,
+            project_name: project.project_name.clone(),
+            project_info: project.project_info.clone(),
+            areas: project.areas.len(),
+            lines: project.lines.len(),
+            devices: project.devices.len(),
+            group_addresses: project.group_addresses.len(),
+        }
+    }
+}
+
+/// One page of a list endpoint.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceQuery {
+    /// Area number, e.g. `1`.
+    pub area: Option<String>,
+    /// Line as `area.line`, e.g. `1.1`.
+    pub line: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupAddressQuery {
+    /// Main group number, e.g. `2`.
+    pub main: Option<String>,
+    /// Middle group number; requires `main`.
+    pub middle: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+pub async fn create_project(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<Redacted<ProjectSession>>), ApiError> {
+    let principal = principal.map(|Extension(principal)| principal);
+    let form = read_upload_form(&state, principal.as_ref(), multipart).await?;
+    let project = parse_upload(&state, form).await?;
+    let mut session = ProjectSession::new(String::new(), &project);
+    session.id = state.sessions.insert(project);
+    log::info!("Created project session {}", session.id);
+    Ok((StatusCode::CREATED, respond(&state, session)))
+}
+
+pub async fn get_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<ProjectSession> {
+    let project = lookup(&state, &id)?;
+    Ok(respond(&state, ProjectSession::new(id, &project)))
+}
+
+pub async fn delete_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    match state.sessions.remove(&id) {
+        Removal::Removed => Ok(StatusCode::NO_CONTENT),
+        Removal::Pinned => Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Project {} is served for the lifetime of the server", id),
+        )),
+        Removal::Missing => Err(not_found(&id)),
+    }
+}
+
+pub async fn list_devices(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<DeviceQuery>,
+) -> ApiResult<Page<DeviceInfo>> {
+    let project = lookup(&state, &id)?;
+    let prefix = match (query.line.as_deref(), query.area.as_deref()) {
+        (Some(line), _) => Some(format!("{}.", line.trim())),
+        (None, Some(area)) => Some(format!("{}.", area.trim())),
+        (None, None) => None,
+    };
+    let devices = project.devices.iter().filter(|device| match &prefix {
+        Some(prefix) => device.individual_address.starts_with(prefix.as_str()),
+        None => true,
+    });
+    Ok(respond(&state, paginate(devices, query.offset, query.limit)))
+}
+
+pub async fn get_device(
+    State(state): State<AppState>,
+    Path((id, address)): Path<(String, String)>,
+) -> ApiResult<DeviceInfo> {
+    let project = lookup(&state, &id)?;
+    project
+        .devices
+        .iter()
+        .find(|device| device.individual_address == address)
+        .cloned()
+        .map(|device| respond(&state, device))
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, format!("Device {} not found", address)))
+}
+
+pub async fn list_group_addresses(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<GroupAddressQuery>,
+) -> ApiResult<Page<GroupAddressInfo>> {
+    let main = query.main.as_deref().map(str::trim);
+    let middle = query.middle.as_deref().map(str::trim);
+    if middle.is_some() && main.is_none() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "The middle group filter requires main",
+        ));
+    }
+    let project = lookup(&state, &id)?;
+    let group_addresses = project.group_addresses.iter().filter(|ga| {
+        let mut parts = ga.address.split('/');
+        let ga_main = parts.next();
+        let ga_middle = parts.next();
+        main.is_none_or(|main| ga_main == Some(main))
+            && middle.is_none_or(|middle| ga_middle == Some(middle))
+    });
+    Ok(respond(&state, paginate(group_addresses, query.offset, query.limit)))
+}
+
+pub async fn get_group_address(
+    State(state): State<AppState>,
+    Path((id, address)): Path<(String, String)>,
+) -> ApiResult<GroupAddressInfo> {
+    let project = lookup(&state, &id)?;
+    // `/` cannot appear in a path segment unencoded, so `2-1-3` is accepted as well as `2%2F1%2F3`.
+    let address = address.replace('-', "/");
+    project
+        .group_addresses
+        .iter()
+        .find(|ga| ga.address == address)
+        .cloned()
+        .map(|ga| respond(&state, ga))
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                format!("Group address {} not found", address),
+            )
+        })
+}
+
+fn respond<T>(state: &AppState, value: T) -> Json<Redacted<T>> {
+    Json(Redacted::new(value, state.config.redaction))
+}
+
+fn lookup(state: &AppState, id: &str) -> Result<Arc<KnxProjectData>, ApiError> {
+    state.sessions.get(id).ok_or_else(|| not_found(id))
+}
+
+fn not_found(id: &str) -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        format!("Project session {} not found or expired", id),
+    )
+}
+
+fn paginate<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = &'a T>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Page<T> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+    let mut total = 0;
+    let mut page = Vec::new();
+    for item in items {
+        if total >= offset && page.len() < limit {
+            page.push(item.clone());
+        }
+        total += 1;
+    }
+    Page {
+        items: page,
+        total,
+        offset,
+        limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::paginate;
+
+    #[test]
+    fn paginates_and_counts_filtered_items() {
+        let items: Vec<u32> = (0..10).collect();
+        let page = paginate(items.iter().filter(|n| *n % 2 == 0), Some(1), Some(2));
+        assert_eq!(page.items, vec![2, 4]);
+        assert_eq!(page.total, 5);
+
+        let page = paginate(items.iter(), Some(8), Some(0));
+        assert_eq!(page.items, vec![8]);
+        assert_eq!(page.limit, 1);
+    }
+}
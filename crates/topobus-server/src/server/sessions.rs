@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use topobus_core::KnxProjectData;
+
+/// In-memory store of parsed projects, addressed by an opaque session id.
+///
+/// Entries expire after `ttl` without access; when `max_sessions` is reached the least recently
+/// used entry is evicted to make room for a new one. Pinned entries, such as projects preloaded
+/// at startup, neither expire nor count towards `max_sessions`.
+pub struct SessionStore {
+    ttl: Duration,
+    max_sessions: usize,
+    inner: Mutex<HashMap<String, Session>>,
+}
+
+/// Outcome of [`SessionStore::remove`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Removal {
+    Removed,
+    /// Pinned sessions stay for the lifetime of the server.
+    Pinned,
+    Missing,
+}
+
+struct Session {
+    project: Arc<KnxProjectData>,
+    last_access: Instant,
+    pinned: bool,
+}
+
+impl SessionStore {
+    pub fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            ttl,
+            max_sessions: max_sessions.max(1),
+            inner: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store a project and return its new session id.
+    pub fn insert(&self, project: KnxProjectData) -> String {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let now = Instant::now();
+        let mut sessions = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        self.purge_expired(&mut sessions, now);
+        while sessions.values().filter(|session| !session.pinned).count() >= self.max_sessions {
+            let oldest = sessions
+                .iter()
+                .filter(|(_, session)| !session.pinned)
+                .min_by_key(|(_, session)| session.last_access)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(oldest) => {
+                    log::info!("Evicting least recently used session {}", oldest);
+                    sessions.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        sessions.insert(
+            id.clone(),
+            Session {
+                project: Arc::new(project),
+                last_access: now,
+                pinned: false,
+            },
+        );
+        id
+    }
+
+    /// Store a project under a fixed id for the lifetime of the server.
+    pub fn insert_pinned(&self, id: String, project: Arc<KnxProjectData>) {
+        let mut sessions = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        sessions.insert(
+            id,
+            Session {
+                project,
+                last_access: Instant::now(),
+                pinned: true,
+            },
+        );
+    }
+
+    /// Look up a session and refresh its last access time.
+    pub fn get(&self, id: &str) -> Option<Arc<KnxProjectData>> {
+        let now = Instant::now();
+        let mut sessions = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        self.purge_expired(&mut sessions, now);
+        let session = sessions.get_mut(id)?;
+        session.last_access = now;
+        Some(session.project.clone())
+    }
+
+    pub fn remove(&self, id: &str) -> Removal {
+        let mut sessions = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        match sessions.get(id) {
+            None => Removal::Missing,
+            Some(session) if session.pinned => Removal::Pinned,
+            Some(_) => {
+                sessions.remove(id);
+                Removal::Removed
+            }
+        }
+    }
+
+    fn purge_expired(&self, sessions: &mut HashMap<String, Session>, now: Instant) {
+        sessions.retain(|id, session| {
+            let alive = session.pinned || now.duration_since(session.last_access) < self.ttl;
+            if !alive {
+                log::info!("Session {} expired", id);
+            }
+            alive
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Removal, SessionStore};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use topobus_core::KnxProjectData;
+
+    fn project(name: &str) -> KnxProjectData {
+        KnxProjectData {
+            project_name: name.to_string(),
+            ..KnxProjectData::default()
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used_session() {
+        let store = SessionStore::new(Duration::from_secs(60), 2);
+        let first = store.insert(project("first"));
+        let second = store.insert(project("second"));
+        assert!(store.get(&first).is_some());
+
+        let third = store.insert(project("third"));
+        assert!(store.get(&second).is_none());
+        assert!(store.get(&first).is_some());
+        assert_eq!(store.get(&third).unwrap().project_name, "third");
+    }
+
+    #[test]
+    fn expires_idle_sessions() {
+        let store = SessionStore::new(Duration::ZERO, 4);
+        let id = store.insert(project("idle"));
+        assert!(store.get(&id).is_none());
+    }
+
+    #[test]
+    fn keeps_pinned_sessions() {
+        let store = SessionStore::new(Duration::ZERO, 1);
+        store.insert_pinned("house".to_string(), Arc::new(project("house")));
+        let id = store.insert(project("upload"));
+        assert!(store.get(&id).is_none());
+        store.insert(project("other"));
+        assert_eq!(store.get("house").unwrap().project_name, "house");
+
+        assert_eq!(store.remove("house"), Removal::Pinned);
+        assert!(store.get("house").is_some());
+        let id = store.insert(project("upload"));
+        assert_eq!(store.remove(&id), Removal::Removed);
+        assert_eq!(store.remove(&id), Removal::Missing);
+    }
+}
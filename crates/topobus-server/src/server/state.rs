@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use topobus_core::AppProgramStore;
+
+use crate::server::auth::Authenticator;
+use crate::server::config::ServerConfig;
+use crate::server::graph_cache::GraphCache;
+use crate::server::jobs::JobRegistry;
+use crate::server::library::Library;
+use crate::server::metrics::Metrics;
+use crate::server::parse_pool::ParsePool;
+use crate::server::sessions::SessionStore;
+use crate::server::store::ProjectStore;
+
+/// Shared state handed to every request handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: ServerConfig,
+    pub sessions: Arc<SessionStore>,
+    pub parse_pool: Arc<ParsePool>,
+    pub jobs: Arc<JobRegistry>,
+    pub graph_cache: Arc<GraphCache>,
+    pub app_programs: Arc<AppProgramStore>,
+    pub auth: Arc<Authenticator>,
+    pub metrics: Arc<Metrics>,
+    /// Projects of the watched directory, when there is one.
+    pub library: Option<Arc<Library>>,
+    /// Projects kept for all users, when a store directory is configured.
+    pub store: Option<Arc<ProjectStore>>,
+}
+
+impl AppState {
+    /// Fails when the project store cannot be opened, e.g. with the wrong key.
+    pub fn new(config: ServerConfig) -> Result<Self> {
+        let sessions = SessionStore::new(
+            Duration::from_secs(config.session_ttl_secs),
+            config.max_sessions,
+        );
+        let parse_pool = ParsePool::new(
+            config.max_concurrent_parses,
+            config.max_queued_parses,
+            Duration::from_secs(config.parse_timeout_secs),
+        );
+        let graph_cache = GraphCache::new(
+            config.cache_entries,
+            config.cache_dir.clone(),
+            config.cache_disk_entries,
+        );
+        let app_programs = AppProgramStore::new(config.app_program_cache_entries);
+        let auth = Authenticator::new(&config.auth);
+        let library = config.watch_dir.clone().map(|dir| Arc::new(Library::new(dir)));
+        let store = match (&config.store.dir, &config.store.key_file) {
+            (Some(dir), Some(key_file)) => Some(Arc::new(ProjectStore::open(dir.clone(), key_file)?)),
+            _ => None,
+        };
+        Ok(Self {
+            config,
+            sessions: Arc::new(sessions),
+            parse_pool: Arc::new(parse_pool),
+            jobs: Arc::new(JobRegistry::new()),
+            graph_cache: Arc::new(graph_cache),
+            app_programs: Arc::new(app_programs),
+            auth: Arc::new(auth),
+            metrics: Arc::new(Metrics::new()),
+            library,
+            store,
+        })
+    }
+}
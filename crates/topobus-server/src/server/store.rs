@@ -0,0 +1,592 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{
+    body::Body,
+    extract::{Multipart, Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use topobus_core::knx::ProjectInfo;
+use topobus_core::{build_project_graphs, ProjectGraphs, Redacted, RedactionPolicy};
+
+use crate::server::api::{parse_upload, read_upload_form};
+use crate::server::auth::Principal;
+use crate::server::error::ApiError;
+use crate::server::graph_cache::create_private_dir;
+use crate::server::state::AppState;
+
+/// Start of every encrypted file, followed by the nonce and the AES-256-GCM ciphertext.
+const MAGIC: &[u8; 4] = b"TBS1";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const MAX_TAGS: usize = 32;
+const MAX_TAG_LEN: usize = 64;
+
+/// A project of the store, as listed on `/api/store`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredProject {
+    pub id: String,
+    /// Name of the uploaded file.
+    pub file_name: String,
+    /// Size of the uploaded file, in bytes.
+    pub size: u64,
+    pub project_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_info: Option<ProjectInfo>,
+    /// Upload time, in seconds since the Unix epoch.
+    pub uploaded_at: u64,
+    /// User who uploaded the project; unknown when the server has no authentication.
+    pub uploaded_by: Option<String>,
+    pub tags: Vec<String>,
+    pub devices: usize,
+    pub group_addresses: usize,
+}
+
+impl StoredProject {
+    fn matches(&self, query: &StoreQuery) -> bool {
+        if let Some(tag) = query.tag.as_deref().map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+        let Some(words) = query.q.as_deref() else {
+            return true;
+        };
+        let info = self.project_info.as_ref();
+        let fields = [
+            Some(self.project_name.as_str()),
+            Some(self.file_name.as_str()),
+            self.uploaded_by.as_deref(),
+            info.and_then(|info| info.project_number.as_deref()),
+            info.and_then(|info| info.contract_number.as_deref()),
+            info.and_then(|info| info.description.as_deref()),
+        ];
+        let text = fields
+            .into_iter()
+            .flatten()
+            .chain(self.tags.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase();
+        words
+            .split_whitespace()
+            .all(|word| text.contains(&word.to_lowercase()))
+    }
+}
+
+/// Uploaded projects kept on disk for all users, encrypted with a key of the server.
+///
+/// Each project is three files named after its id: the uploaded archive (`.archive`), the graphs
+/// served to the frontend (`.graphs`) and its [`StoredProject`] metadata (`.meta`), written last.
+/// The metadata is read into memory when the store is opened.
+pub struct ProjectStore {
+    dir: PathBuf,
+    cipher: Aes256Gcm,
+    index: Mutex<BTreeMap<String, StoredProject>>,
+}
+
+impl ProjectStore {
+    /// Open the store in `dir`, with the key in `key_file`, generated if the file does not exist.
+    /// Blocking: call from a blocking context.
+    pub fn open(dir: PathBuf, key_file: &FsPath) -> Result<Self> {
+        create_private_dir(&dir)
+            .with_context(|| format!("Failed to create project store {}", dir.display()))?;
+        let key = if key_file.exists() {
+            read_key(key_file)?
+        } else {
+            generate_key(key_file)?
+        };
+        let store = Self {
+            dir,
+            cipher: Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow!("Invalid store key"))?,
+            index: Mutex::new(BTreeMap::new()),
+        };
+
+        let mut index = BTreeMap::new();
+        let mut unreadable = 0;
+        for entry in fs::read_dir(&store.dir)? {
+            let path = entry?.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".meta"))
+                .filter(|id| is_valid_id(id))
+            else {
+                continue;
+            };
+            match store.read(id, "meta").and_then(|bytes| Ok(serde_json::from_slice(&bytes)?)) {
+                Ok(project) => {
+                    index.insert(id.to_string(), project);
+                }
+                Err(err) => {
+                    log::warn!("Skipping stored project {}: {:#}", path.display(), err);
+                    unreadable += 1;
+                }
+            }
+        }
+        if index.is_empty() && unreadable > 0 {
+            bail!(
+                "None of the projects in {} can be decrypted; were they stored with the key in {}?",
+                store.dir.display(),
+                key_file.display()
+            );
+        }
+        log::info!("Project store {} holds {} project(s)", store.dir.display(), index.len());
+        *store.index.lock().unwrap_or_else(|err| err.into_inner()) = index;
+        Ok(store)
+    }
+
+    /// Projects matching `query`, most recent first.
+    pub fn list(&self, query: &StoreQuery) -> Vec<StoredProject> {
+        let index = self.index.lock().unwrap_or_else(|err| err.into_inner());
+        let mut projects: Vec<StoredProject> = index
+            .values()
+            .filter(|project| project.matches(query))
+            .cloned()
+            .collect();
+        projects.sort_by(|a, b| b.uploaded_at.cmp(&a.uploaded_at).then_with(|| a.id.cmp(&b.id)));
+        projects
+    }
+
+    pub fn get(&self, id: &str) -> Option<StoredProject> {
+        let index = self.index.lock().unwrap_or_else(|err| err.into_inner());
+        index.get(id).cloned()
+    }
+
+    /// Store a project with its archive and graphs. Blocking.
+    ///
+    /// Like the archive, the graphs and the metadata keep the project's secrets; responses
+    /// mask them as configured.
+    pub fn insert(&self, project: StoredProject, archive: &[u8], graphs: &ProjectGraphs) -> Result<()> {
+        if !is_valid_id(&project.id) {
+            bail!("Invalid project id {}", project.id);
+        }
+        let complete = RedactionPolicy::Include;
+        self.write(&project.id, "archive", archive)?;
+        self.write(&project.id, "graphs", &serde_json::to_vec(&Redacted::new(graphs, complete))?)?;
+        self.write(&project.id, "meta", &serde_json::to_vec(&Redacted::new(&project, complete))?)?;
+        let mut index = self.index.lock().unwrap_or_else(|err| err.into_inner());
+        index.insert(project.id.clone(), project);
+        Ok(())
+    }
+
+    /// The uploaded archive of a project. Blocking.
+    pub fn archive(&self, id: &str) -> Result<Vec<u8>> {
+        self.read(id, "archive")
+    }
+
+    /// The graphs of a project. Blocking.
+    pub fn graphs(&self, id: &str) -> Result<ProjectGraphs> {
+        Ok(serde_json::from_slice(&self.read(id, "graphs")?)?)
+    }
+
+    /// Delete a project; false when there is none with this id. Blocking.
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let removed = {
+            let mut index = self.index.lock().unwrap_or_else(|err| err.into_inner());
+            index.remove(id).is_some()
+        };
+        if !removed {
+            return Ok(false);
+        }
+        // The metadata goes first, so a failure halfway leaves no listed, incomplete project.
+        for kind in ["meta", "graphs", "archive"] {
+            match fs::remove_file(self.path(id, kind)) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn path(&self, id: &str, kind: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", id, kind))
+    }
+
+    fn write(&self, id: &str, kind: &str, plaintext: &[u8]) -> Result<()> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: aad(id, kind).as_bytes() })
+            .map_err(|_| anyhow!("Failed to encrypt {}.{}", id, kind))?;
+        let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+
+        let path = self.path(id, kind);
+        let tmp = path.with_extension(format!("{}.tmp", kind));
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn read(&self, id: &str, kind: &str) -> Result<Vec<u8>> {
+        if !is_valid_id(id) {
+            bail!("Invalid project id {}", id);
+        }
+        let bytes = fs::read(self.path(id, kind))?;
+        let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            bail!("{}.{} is not an encrypted store file", id, kind);
+        };
+        if rest.len() < NONCE_LEN {
+            bail!("{}.{} is truncated", id, kind);
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        // Binding the id and kind means files cannot be swapped between projects unnoticed.
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload { msg: ciphertext, aad: aad(id, kind).as_bytes() },
+            )
+            .map_err(|_| anyhow!("{}.{} does not decrypt with the store key", id, kind))
+    }
+}
+
+fn aad(id: &str, kind: &str) -> String {
+    format!("topobus-store/{}/{}", id, kind)
+}
+
+/// Store ids are simple UUIDs; checking them keeps paths inside the store directory.
+fn is_valid_id(id: &str) -> bool {
+    id.len() == 32 && id.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+/// The base64 key in `path`.
+pub(crate) fn read_key(path: &FsPath) -> Result<Vec<u8>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read store key {}", path.display()))?;
+    let key = BASE64_STANDARD
+        .decode(text.trim())
+        .ok()
+        .filter(|key| key.len() == KEY_LEN)
+        .ok_or_else(|| anyhow!("{} does not hold a base64 key of {} bytes", path.display(), KEY_LEN))?;
+    Ok(key)
+}
+
+fn generate_key(path: &FsPath) -> Result<Vec<u8>> {
+    let key = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create store key {}", path.display()))?;
+    writeln!(file, "{}", BASE64_STANDARD.encode(&key))?;
+    log::info!("Generated store key {}", path.display());
+    Ok(key)
+}
+
+/// Trimmed, without empty tags or duplicates, within the limits on count and length.
+fn clean_tags(tags: &[String]) -> Result<Vec<String>, ApiError> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                format!("Tags are limited to {} characters", MAX_TAG_LEN),
+            ));
+        }
+        if !cleaned.iter().any(|own| own.eq_ignore_ascii_case(tag)) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    if cleaned.len() > MAX_TAGS {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!("A project can have at most {} tags", MAX_TAGS),
+        ));
+    }
+    Ok(cleaned)
+}
+
+/// `Content-Disposition` offering `file_name` for download, with an ASCII fallback.
+fn attachment(file_name: &str) -> HeaderValue {
+    let fallback: String = file_name
+        .chars()
+        .map(|ch| match ch {
+            ' ' => ch,
+            '"' | '\\' => '_',
+            ch if ch.is_ascii_graphic() => ch,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+    HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    ))
+    .unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
+
+fn store(state: &AppState) -> Result<&Arc<ProjectStore>, ApiError> {
+    state.store.as_ref().ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "No project store; set [store] dir in the server configuration",
+        )
+    })
+}
+
+fn not_found(id: &str) -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, format!("No stored project {}", id))
+}
+
+/// Run a blocking store operation, logging failures as internal errors.
+async fn blocking<T: Send + 'static>(
+    action: &'static str,
+    task: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+        .map_err(|err| {
+            log::error!("Failed to {}: {:#}", action, err);
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to {}", action))
+        })
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StoreQuery {
+    /// Words that must all appear in the project name or number, contract number, description,
+    /// file name, uploader or tags.
+    pub q: Option<String>,
+    /// Only projects with this tag.
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoreListing {
+    pub projects: Vec<StoredProject>,
+}
+
+pub async fn list_store(
+    State(state): State<AppState>,
+    Query(query): Query<StoreQuery>,
+) -> Result<Json<Redacted<StoreListing>>, ApiError> {
+    let listing = StoreListing {
+        projects: store(&state)?.list(&query),
+    };
+    Ok(Json(Redacted::new(listing, state.config.redaction)))
+}
+
+/// Parse an upload like `/api/projects` does and keep it in the store.
+pub async fn create_stored_project(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<Redacted<StoredProject>>), ApiError> {
+    let store = store(&state)?.clone();
+    let principal = principal.map(|Extension(principal)| principal);
+    let form = read_upload_form(&state, principal.as_ref(), multipart).await?;
+    let tags = clean_tags(&form.tags)?;
+    let file_name = form
+        .filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let archive = form.data.clone();
+    let project = parse_upload(&state, form).await?;
+
+    let stored = StoredProject {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        file_name,
+        size: archive.len() as u64,
+        project_name: project.project_name.clone(),
+        project_info: project.project_info.clone(),
+        uploaded_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|age| age.as_secs())
+            .unwrap_or_default(),
+        uploaded_by: principal.map(|principal| principal.0),
+        tags,
+        devices: project.devices.len(),
+        group_addresses: project.group_addresses.len(),
+    };
+    let entry = stored.clone();
+    blocking("store the project", move || {
+        store.insert(entry, archive.as_ref(), &build_project_graphs(&project))
+    })
+    .await?;
+    log::info!("Stored {} as {}", stored.file_name, stored.id);
+    Ok((StatusCode::CREATED, Json(Redacted::new(stored, state.config.redaction))))
+}
+
+pub async fn get_stored_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Redacted<StoredProject>>, ApiError> {
+    let project = store(&state)?.get(&id).ok_or_else(|| not_found(&id))?;
+    Ok(Json(Redacted::new(project, state.config.redaction)))
+}
+
+/// Graphs of a stored project, like `/api/upload` returns them.
+pub async fn get_stored_graphs(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let store = store(&state)?.clone();
+    store.get(&id).ok_or_else(|| not_found(&id))?;
+    let graphs = blocking("read the stored project", move || store.graphs(&id)).await?;
+    Ok(Json(Redacted::new(graphs, state.config.redaction)).into_response())
+}
+
+/// The archive as it was uploaded; protected projects still need their password.
+pub async fn download_stored_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let store = store(&state)?.clone();
+    let project = store.get(&id).ok_or_else(|| not_found(&id))?;
+    let archive = blocking("read the stored project", move || store.archive(&id)).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/octet-stream")),
+            (header::CONTENT_DISPOSITION, attachment(&project.file_name)),
+        ],
+        Body::from(archive),
+    )
+        .into_response())
+}
+
+pub async fn delete_stored_project(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let store = store(&state)?.clone();
+    let target = id.clone();
+    if !blocking("delete the stored project", move || store.remove(&target)).await? {
+        return Err(not_found(&id));
+    }
+    match principal {
+        Some(Extension(principal)) => log::info!("{} deleted stored project {}", principal.0, id),
+        None => log::info!("Deleted stored project {}", id),
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attachment, clean_tags, ProjectStore, StoreQuery, StoredProject};
+    use std::path::PathBuf;
+    use topobus_core::knx::GroupAddressInfo;
+    use topobus_core::{build_project_graphs, KnxProjectData, ProjectGraphs};
+
+    fn graphs(name: &str) -> ProjectGraphs {
+        build_project_graphs(&KnxProjectData {
+            project_name: name.to_string(),
+            group_addresses: vec![GroupAddressInfo {
+                address: "1/1/1".to_string(),
+                security_key: Some("c2VjcmV0".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("topobus-store-{}-{}", name, std::process::id()))
+    }
+
+    fn project(id: &str, name: &str, tags: &[&str], uploaded_at: u64) -> StoredProject {
+        StoredProject {
+            id: id.repeat(32),
+            file_name: format!("{}.knxproj", name),
+            size: 3,
+            project_name: name.to_string(),
+            project_info: None,
+            uploaded_at,
+            uploaded_by: Some("alice".to_string()),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            devices: 0,
+            group_addresses: 0,
+        }
+    }
+
+    #[test]
+    fn keeps_projects_encrypted_across_restarts() {
+        let dir = temp_dir("roundtrip");
+        let key = temp_dir("roundtrip-key");
+        let store = ProjectStore::open(dir.join("store"), &key).unwrap();
+        store
+            .insert(project("a", "Haus Müller", &["customer"], 10), b"zip", &graphs("Haus"))
+            .unwrap();
+        store.insert(project("b", "Office", &[], 20), b"zip", &graphs("Office")).unwrap();
+        let on_disk = std::fs::read(dir.join("store").join(format!("{}.meta", "a".repeat(32)))).unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("Haus"));
+
+        let reopened = ProjectStore::open(dir.join("store"), &key).unwrap();
+        let listed: Vec<_> = reopened.list(&StoreQuery::default());
+        assert_eq!(listed[0].project_name, "Office");
+        // Secrets are kept, encrypted, and only masked in responses.
+        let stored = reopened.graphs(&"a".repeat(32)).unwrap();
+        assert_eq!(stored.project_name, "Haus");
+        assert_eq!(stored.group_addresses[0].security_key.as_deref(), Some("c2VjcmV0"));
+        assert_eq!(reopened.archive(&"a".repeat(32)).unwrap(), b"zip");
+
+        let search = |q: Option<&str>, tag: Option<&str>| {
+            reopened
+                .list(&StoreQuery {
+                    q: q.map(str::to_string),
+                    tag: tag.map(str::to_string),
+                })
+                .len()
+        };
+        assert_eq!(search(Some("müller haus"), None), 1);
+        assert_eq!(search(Some("ALICE"), None), 2);
+        assert_eq!(search(None, Some("Customer")), 1);
+        assert_eq!(search(Some("office"), Some("customer")), 0);
+
+        assert!(reopened.remove(&"a".repeat(32)).unwrap());
+        assert!(!reopened.remove(&"a".repeat(32)).unwrap());
+        assert!(reopened.graphs("../../etc/passwd").is_err());
+
+        // Another key cannot read the store.
+        let other_key = temp_dir("roundtrip-other-key");
+        assert!(ProjectStore::open(dir.join("store"), &other_key).is_err());
+        for path in [&key, &other_key] {
+            std::fs::remove_file(path).unwrap();
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cleans_tags_and_download_names() {
+        let tags = ["office", " Office ", "2024", " "].map(str::to_string);
+        assert_eq!(clean_tags(&tags).unwrap(), ["office", "2024"]);
+        assert!(clean_tags(&["x".repeat(65)]).is_err());
+        assert_eq!(clean_tags(&[format!(" {} ", "x".repeat(64))]).unwrap(), ["x".repeat(64)]);
+        assert_eq!(
+            attachment("Haus \"Müller\".knxproj").to_str().unwrap(),
+            "attachment; filename=\"Haus _M_ller_.knxproj\"; \
+             filename*=UTF-8''Haus%20%22M%C3%BCller%22.knxproj"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use rcgen::CertifiedKey;
+
+use crate::server::config::TlsConfig;
+
+/// Load the certificate for HTTPS, generating a self-signed one first if so configured.
+pub async fn rustls_config(tls: &TlsConfig, bind_addresses: &[std::net::IpAddr]) -> Result<RustlsConfig> {
+    // Only the ring provider is compiled in; installing fails harmlessly if already done.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let (Some(cert), Some(key)) = (&tls.cert, &tls.key) else {
+        anyhow::bail!("tls: set both cert and key");
+    };
+    if tls.self_signed && !cert.exists() && !key.exists() {
+        let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+        names.extend(
+            bind_addresses
+                .iter()
+                .filter(|address| !address.is_unspecified() && !address.is_loopback())
+                .map(|address| address.to_string()),
+        );
+        names.extend(tls.hostnames.iter().cloned());
+        write_self_signed(cert, key, names)?;
+        log::info!(
+            "Generated a self-signed certificate at {}; browsers will ask to trust it once",
+            cert.display()
+        );
+    }
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .with_context(|| format!("Failed to load TLS certificate {}", cert.display()))
+}
+
+fn write_self_signed(cert_path: &Path, key_path: &Path, mut names: Vec<String>) -> Result<()> {
+    names.dedup();
+    let CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)
+        .context("Failed to generate a self-signed certificate")?;
+    for path in [cert_path, key_path] {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+    }
+    write_private(key_path, key_pair.serialize_pem().as_bytes())?;
+    std::fs::write(cert_path, cert.pem())
+        .with_context(|| format!("Failed to write {}", cert_path.display()))?;
+    Ok(())
+}
+
+/// Write a file only its owner can read.
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rustls_config;
+    use crate::server::config::TlsConfig;
+
+    #[tokio::test]
+    async fn generates_and_reuses_self_signed_certificate() {
+        let dir = std::env::temp_dir().join(format!("topobus-tls-{}", std::process::id()));
+        let tls = TlsConfig {
+            cert: Some(dir.join("tls/cert.pem")),
+            key: Some(dir.join("tls/key.pem")),
+            self_signed: true,
+            hostnames: vec!["topobus.office.lan".to_string()],
+        };
+        let addresses = ["192.168.1.20".parse().unwrap()];
+
+        let first = rustls_config(&tls, &addresses).await.map(|_| ());
+        let cert = std::fs::read_to_string(dir.join("tls/cert.pem"));
+        let second = rustls_config(&tls, &addresses).await.map(|_| ());
+        let reused = std::fs::read_to_string(dir.join("tls/cert.pem"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        first.unwrap();
+        second.unwrap();
+        let cert = cert.unwrap();
+        assert!(cert.starts_with("-----BEGIN CERTIFICATE-----"));
+        assert_eq!(reused.unwrap(), cert);
+    }
+}
@@ -54,6 +54,19 @@ pub enum ValidationError {
     ArchiveError(String),
 }
 
+impl ValidationError {
+    /// Name of the failed check, e.g. for metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ValidationError::FileTooLarge { .. } => "file_too_large",
+            ValidationError::InvalidFileFormat { .. } => "invalid_file_format",
+            ValidationError::InvalidArchive => "invalid_archive",
+            ValidationError::UncompressedTooLarge { .. } => "uncompressed_too_large",
+            ValidationError::ArchiveError(_) => "archive_error",
+        }
+    }
+}
+
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -4,7 +4,9 @@ use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
-use topobus_core::{build_project_graphs, load_knxproj_bytes_with_language};
+use topobus_core::{
+    build_project_graphs, inspect_knxproj, ProgressEvent, ProjectLoader, Redacted, RedactionPolicy,
+};
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(start)]
@@ -12,22 +14,62 @@ pub fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// Parse a project into graphs. The tracing password, BCU key and group address keys are
+/// masked unless `include_secrets` is true.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn parse_knxproj(
     data: &[u8],
     password: Option<String>,
     preferred_language: Option<String>,
+    include_secrets: Option<bool>,
 ) -> Result<JsValue, JsValue> {
-    let project = load_knxproj_bytes_with_language(
-        data,
-        password.as_deref(),
-        preferred_language.as_deref(),
-    )
-    .map_err(to_js_error)?;
+    let project = ProjectLoader::from_bytes(data)
+        .password(password.as_deref())
+        .language(preferred_language.as_deref())
+        .load()
+        .map_err(to_js_error)?;
     let graphs = build_project_graphs(&project);
+    let policy = RedactionPolicy::include_secrets(include_secrets.unwrap_or(false));
     let serializer = serde_wasm_bindgen::Serializer::json_compatible();
-    graphs.serialize(&serializer).map_err(to_js_error)
+    Redacted::new(&graphs, policy).serialize(&serializer).map_err(to_js_error)
+}
+
+/// Like `parse_knxproj`, calling `on_progress` with each loading phase (same objects as the
+/// server's `/api/jobs/{id}/events` stream, without the `type` field).
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn parse_knxproj_with_progress(
+    data: &[u8],
+    password: Option<String>,
+    preferred_language: Option<String>,
+    include_secrets: Option<bool>,
+    on_progress: &js_sys::Function,
+) -> Result<JsValue, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    let observer = |event: &ProgressEvent| {
+        if let Ok(value) = event.serialize(&serializer) {
+            let _ = on_progress.call1(&JsValue::NULL, &value);
+        }
+    };
+    let project = ProjectLoader::from_bytes(data)
+        .password(password.as_deref())
+        .language(preferred_language.as_deref())
+        .progress(&observer)
+        .load()
+        .map_err(to_js_error)?;
+    let graphs = build_project_graphs(&project);
+    let policy = RedactionPolicy::include_secrets(include_secrets.unwrap_or(false));
+    Redacted::new(&graphs, policy).serialize(&serializer).map_err(to_js_error)
+}
+
+/// Summarise a project without parsing it, e.g. to ask for its password before `parse_knxproj`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = inspect_knxproj)]
+pub fn inspect(data: &[u8]) -> Result<JsValue, JsValue> {
+    let summary = inspect_knxproj(data).map_err(to_js_error)?;
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    summary.serialize(&serializer).map_err(to_js_error)
 }
 
 #[cfg(target_arch = "wasm32")]
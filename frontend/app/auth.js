@@ -0,0 +1,20 @@
+import { ApiClient } from './utils/api_client.js';
+
+/** Send the browser to the login page, coming back to the current page afterwards. */
+export function redirectToLogin() {
+    const next = `${window.location.pathname}${window.location.search}${window.location.hash}`;
+    const url = new URL('login.html', window.location.href);
+    url.searchParams.set('next', next);
+    window.location.assign(url.toString());
+}
+
+/**
+ * Go to the login page when the TopoBus server requires authentication and nobody is signed in.
+ * Without a server (static hosting, offline) there is nothing to sign in to.
+ */
+export async function ensureSignedIn() {
+    const status = await new ApiClient().getAuthStatus();
+    if (status && status.enabled && !status.user) {
+        redirectToLogin();
+    }
+}
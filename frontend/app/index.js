@@ -9,8 +9,12 @@ import { initSettings } from './settings.js';
 import { initHelp } from './help.js';
 import { initDetails } from './details.js';
 import { initNavigationLinks } from './navigation.js';
+import { ensureSignedIn } from './auth.js';
+import { followLibraryProject, libraryProjectFromUrl, setupLibraryList } from './library.js';
+import { openStoredProject, setupStoreList, storedProjectFromUrl } from './store.js';
 
 export function initApp() {
+    ensureSignedIn();
     initDom();
     initSettings();
     initHelp();
@@ -26,7 +30,25 @@ export function initApp() {
     setupMinimap();
     setupPasswordControls();
 
-    restoreLastProjectFromStorage().catch((error) => {
-        console.warn('Project restore failed.', error);
+    const libraryProject = libraryProjectFromUrl();
+    const storedProject = storedProjectFromUrl();
+    if (libraryProject) {
+        followLibraryProject(libraryProject).catch((error) => {
+            console.warn('Library project failed to load.', error);
+        });
+    } else if (storedProject) {
+        openStoredProject(storedProject).catch((error) => {
+            console.warn('Stored project failed to load.', error);
+        });
+    } else {
+        restoreLastProjectFromStorage().catch((error) => {
+            console.warn('Project restore failed.', error);
+        });
+    }
+    setupLibraryList().catch((error) => {
+        console.warn('Project library unavailable.', error);
+    });
+    setupStoreList().catch((error) => {
+        console.warn('Project store unavailable.', error);
     });
 }
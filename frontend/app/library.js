@@ -0,0 +1,105 @@
+import { getDom } from './dom.js';
+import { ApiClient, ApiError } from './utils/api_client.js';
+import { prepareForProjectLoad } from './project_cleanup.js';
+import { showProject } from './upload.js';
+
+const RETRY_DELAY_MS = 2000;
+
+/** Library project named in the page URL (`?library=<id>`), if any. */
+export function libraryProjectFromUrl() {
+    if (typeof window === 'undefined') return null;
+    const id = new URLSearchParams(window.location.search).get('library');
+    return id ? id : null;
+}
+
+/** List the projects of the server's watched directory in the upload zone. */
+export async function setupLibraryList() {
+    const library = await new ApiClient().getLibrary();
+    const projects = library && Array.isArray(library.projects) ? library.projects : [];
+    const dom = getDom();
+    const content = dom && dom.uploadZone ? dom.uploadZone.querySelector('.upload-content') : null;
+    if (!projects.length || !content) return;
+
+    const section = document.createElement('div');
+    section.className = 'library-list';
+    const heading = document.createElement('p');
+    heading.textContent = 'or open a project from the server (follows new exports):';
+    const list = document.createElement('ul');
+    for (const project of projects) {
+        const item = document.createElement('li');
+        const link = document.createElement('a');
+        const url = new URL(window.location.href);
+        url.search = '';
+        url.searchParams.set('library', project.id);
+        link.href = url.toString();
+        link.textContent = project.project_name || project.id;
+        item.appendChild(link);
+        if (project.status !== 'ready') {
+            const status = document.createElement('span');
+            status.className = 'library-status';
+            const labels = { failed: ' (failed to load)', locked: ' (password protected)' };
+            status.textContent = labels[project.status] || ' (loading)';
+            item.appendChild(status);
+        }
+        list.appendChild(item);
+    }
+    section.append(heading, list);
+    content.appendChild(section);
+}
+
+/**
+ * Show a project of the server's watched directory, and show it again whenever the server
+ * parsed a new export of it.
+ */
+export async function followLibraryProject(id) {
+    const client = new ApiClient();
+    let shown = false;
+    let loading = null;
+    let pending = false;
+
+    const load = async () => {
+        const dom = getDom();
+        if (!dom) return;
+        try {
+            // Later reloads replace the project in place, without hiding the current one.
+            if (!shown && dom.loading && dom.loadingMessage) {
+                dom.loadingMessage.textContent = 'Loading project from the server...';
+                dom.loading.classList.remove('hidden');
+            }
+            const data = await client.getLibraryProject(id);
+            prepareForProjectLoad();
+            await showProject(data, id);
+            shown = true;
+        } catch (error) {
+            if (error instanceof ApiError && error.status === 503) {
+                setTimeout(reload, RETRY_DELAY_MS);
+                return;
+            }
+            console.warn(`Unable to load library project ${id}.`, error);
+            if (dom.loading) dom.loading.classList.add('hidden');
+            if (dom.uploadZone && !shown) dom.uploadZone.classList.remove('hidden');
+        }
+    };
+
+    // Changes arriving during a load are applied once it is done.
+    const reload = async () => {
+        if (loading) {
+            pending = true;
+            return;
+        }
+        loading = load();
+        await loading;
+        loading = null;
+        if (pending) {
+            pending = false;
+            reload();
+        }
+    };
+
+    client.watchLibrary((type, event) => {
+        if (event && event.id === id && type === 'changed') {
+            reload();
+        }
+    });
+    await reload();
+}
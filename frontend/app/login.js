@@ -0,0 +1,61 @@
+const form = document.getElementById('login-form');
+const usernameInput = document.getElementById('login-username');
+const passwordInput = document.getElementById('login-password');
+const submitButton = document.getElementById('login-submit');
+const hint = document.getElementById('login-hint');
+
+/** Page to return to; only same-origin paths, so the login cannot redirect elsewhere. */
+function nextUrl() {
+    const next = new URLSearchParams(window.location.search).get('next') || '';
+    if (next.startsWith('/') && !next.startsWith('//') && !next.includes('login.html')) {
+        return next;
+    }
+    return new URL('./', window.location.href).pathname;
+}
+
+async function signIn(event) {
+    event.preventDefault();
+    const username = usernameInput.value.trim();
+    const secret = passwordInput.value;
+    if (!secret) {
+        hint.textContent = 'Enter a password or an access token.';
+        passwordInput.focus();
+        return;
+    }
+
+    const body = username ? { username, password: secret } : { token: secret };
+    submitButton.disabled = true;
+    hint.textContent = '';
+    try {
+        const response = await fetch(new URL('api/login', new URL('./', window.location.href)), {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            credentials: 'same-origin',
+            body: JSON.stringify(body)
+        });
+        if (response.ok) {
+            window.location.replace(nextUrl());
+            return;
+        }
+        hint.textContent = errorMessage(await response.text()) || `Sign in failed (${response.status}).`;
+        passwordInput.select();
+    } catch (error) {
+        hint.textContent = 'Network error. Please check your connection and try again.';
+    } finally {
+        submitButton.disabled = false;
+    }
+}
+
+/** Message of a JSON API error, or the body itself if it is not one. */
+function errorMessage(bodyText) {
+    try {
+        const body = JSON.parse(bodyText);
+        if (body && typeof body.message === 'string') return body.message;
+    } catch {
+        // Not JSON, e.g. from a proxy in front of TopoBus.
+    }
+    return bodyText;
+}
+
+form.addEventListener('submit', signIn);
+usernameInput.focus();
@@ -1,11 +1,13 @@
 import { state } from './state.js';
 import { ApiClient } from './utils/api_client.js';
-import { parseKnxprojBytesWithWorker } from './wasm_worker_client.js';
+import { inspectKnxprojBytesWithWorker, parseKnxprojBytesWithWorker } from './wasm_worker_client.js';
 
 export async function parseKnxprojFile(file, password, options = {}) {
     const allowServerFallback = options && options.allowServerFallback !== false;
+    const onProgress = options && typeof options.onProgress === 'function' ? options.onProgress : null;
     const wasmData = await tryParseWithWasm(file, password, {
-        strict: !allowServerFallback
+        strict: !allowServerFallback,
+        onProgress
     });
     if (wasmData) {
         return wasmData;
@@ -13,7 +15,47 @@ export async function parseKnxprojFile(file, password, options = {}) {
     if (!allowServerFallback) {
         throw new Error('Local parsing failed and server fallback is disabled.');
     }
-    return parseWithServer(file, password);
+    return parseWithServer(file, password, onProgress);
+}
+
+/**
+ * Summarise a project (name, schema version, whether it needs a password) without parsing it.
+ * Resolves to null when local inspection is unavailable; the parse then reports problems itself.
+ */
+export async function inspectKnxprojFile(file) {
+    try {
+        return await inspectKnxprojBytesWithWorker(await file.arrayBuffer());
+    } catch (error) {
+        console.warn('Project inspection unavailable.', error);
+        return null;
+    }
+}
+
+/** Human readable label for a parse-phase event emitted by the core loader. */
+export function describeParseProgress(event) {
+    if (!event || !event.phase) return 'Loading project...';
+    switch (event.phase) {
+        case 'deriving_key':
+            return 'Deriving decryption key...';
+        case 'locating_documents':
+            return 'Reading project archive...';
+        case 'topology':
+            return 'Reading topology...';
+        case 'group_addresses':
+            return 'Reading group addresses...';
+        case 'devices':
+            return 'Reading devices...';
+        case 'hardware':
+            return `Loading hardware catalog ${event.manufacturer}...`;
+        case 'app_program':
+            return `Loading application program ${event.loaded}/${event.total}...`;
+        case 'locations':
+            return 'Reading building structure...';
+        case 'done':
+            return 'Building views...';
+        default:
+            return 'Loading project...';
+    }
 }
 
 async function tryParseWithWasm(file, password, options = {}) {
@@ -22,7 +64,9 @@ async function tryParseWithWasm(file, password, options = {}) {
         const preferredLanguage = state.uiSettings && state.uiSettings.productLanguage
             ? String(state.uiSettings.productLanguage)
             : undefined;
-        return await parseKnxprojBytesWithWorker(buffer, password, preferredLanguage);
+        return await parseKnxprojBytesWithWorker(buffer, password, preferredLanguage, {
+            onProgress: options.onProgress
+        });
     } catch (error) {
         if (isPasswordError(error)) {
             throw error;
@@ -35,12 +79,16 @@ async function tryParseWithWasm(file, password, options = {}) {
     }
 }
 
-async function parseWithServer(file, password) {
+async function parseWithServer(file, password, onProgress) {
     const apiClient = new ApiClient();
     const preferredLanguage = state.uiSettings && state.uiSettings.productLanguage
         ? String(state.uiSettings.productLanguage)
         : undefined;
-    return apiClient.uploadProject(file, password, preferredLanguage, { maxRetries: 3, timeout: 60000 });
+    return apiClient.uploadProject(file, password, preferredLanguage, {
+        maxRetries: 3,
+        timeout: 60000,
+        onProgress
+    });
 }
 
 function isPasswordError(error) {
@@ -0,0 +1,176 @@
+import { getDom } from './dom.js';
+import { ApiClient, ApiError } from './utils/api_client.js';
+import { redirectToLogin } from './auth.js';
+import { prepareForProjectLoad } from './project_cleanup.js';
+import { setUploadError, showProject } from './upload.js';
+
+const SEARCH_DELAY_MS = 300;
+
+/** Stored project named in the page URL (`?stored=<id>`), if any. */
+export function storedProjectFromUrl() {
+    if (typeof window === 'undefined') return null;
+    const id = new URLSearchParams(window.location.search).get('stored');
+    return id ? id : null;
+}
+
+function pageUrl(params) {
+    const url = new URL(window.location.href);
+    url.search = '';
+    for (const [key, value] of Object.entries(params)) {
+        url.searchParams.set(key, value);
+    }
+    return url.toString();
+}
+
+/** Show a project of the server's shared store. */
+export async function openStoredProject(id) {
+    const dom = getDom();
+    if (!dom) return;
+    if (dom.loading && dom.loadingMessage) {
+        dom.loadingMessage.textContent = 'Loading project from the server...';
+        dom.loading.classList.remove('hidden');
+    }
+    try {
+        const data = await new ApiClient().getStoredProject(id);
+        prepareForProjectLoad();
+        await showProject(data, id);
+    } catch (error) {
+        if (dom.loading) dom.loading.classList.add('hidden');
+        if (error instanceof ApiError && error.code === 'auth_required') {
+            redirectToLogin();
+            return;
+        }
+        console.warn(`Unable to load stored project ${id}.`, error);
+        if (dom.uploadZone) dom.uploadZone.classList.remove('hidden');
+        setUploadError(`Unable to open the stored project: ${error.message || error}`);
+    }
+}
+
+/**
+ * List the projects of the server's shared store in the upload zone, with a search field and a
+ * form to add one. Does nothing when the server keeps no store.
+ */
+export async function setupStoreList() {
+    const client = new ApiClient();
+    const store = await client.getStore();
+    const dom = getDom();
+    const content = dom && dom.uploadZone ? dom.uploadZone.querySelector('.upload-content') : null;
+    if (!store || !content) return;
+
+    const section = document.createElement('div');
+    section.className = 'library-list store-list';
+    const heading = document.createElement('p');
+    heading.textContent = 'or open a project shared on the server:';
+    const search = document.createElement('input');
+    search.type = 'search';
+    search.placeholder = 'Search name, number, customer tag...';
+    search.className = 'store-search';
+    const list = document.createElement('ul');
+    section.append(heading, search, list, buildAddForm(client));
+    content.appendChild(section);
+
+    const render = (projects) => {
+        list.replaceChildren(...projects.map((project) => buildItem(client, project, refresh)));
+        if (!projects.length) {
+            const empty = document.createElement('li');
+            empty.className = 'library-status';
+            empty.textContent = search.value ? 'No matching project.' : 'No project shared yet.';
+            list.appendChild(empty);
+        }
+    };
+    const refresh = async () => {
+        const result = await client.getStore(search.value.trim());
+        render(result && Array.isArray(result.projects) ? result.projects : []);
+    };
+
+    let timer = null;
+    search.addEventListener('input', () => {
+        clearTimeout(timer);
+        timer = setTimeout(refresh, SEARCH_DELAY_MS);
+    });
+    render(Array.isArray(store.projects) ? store.projects : []);
+}
+
+function buildItem(client, project, refresh) {
+    const item = document.createElement('li');
+    const link = document.createElement('a');
+    link.href = pageUrl({ stored: project.id });
+    link.textContent = project.project_name || project.file_name;
+
+    const details = [];
+    if (project.uploaded_at) {
+        details.push(new Date(project.uploaded_at * 1000).toLocaleDateString());
+    }
+    if (project.uploaded_by) details.push(project.uploaded_by);
+    if (Array.isArray(project.tags) && project.tags.length) details.push(project.tags.join(', '));
+    const meta = document.createElement('span');
+    meta.className = 'library-status';
+    meta.textContent = details.length ? ` (${details.join(' · ')})` : '';
+
+    const download = document.createElement('a');
+    download.className = 'store-action';
+    download.href = client.storedProjectDownloadUrl(project.id);
+    download.textContent = 'download';
+    download.setAttribute('download', project.file_name || '');
+
+    const remove = document.createElement('button');
+    remove.type = 'button';
+    remove.className = 'store-action';
+    remove.textContent = 'delete';
+    remove.addEventListener('click', async () => {
+        if (!window.confirm(`Delete "${link.textContent}" from the server for everyone?`)) return;
+        try {
+            await client.deleteStoredProject(project.id);
+        } catch (error) {
+            setUploadError(`Unable to delete the project: ${error.message || error}`);
+        }
+        refresh();
+    });
+
+    item.append(link, meta, download, remove);
+    return item;
+}
+
+function buildAddForm(client) {
+    const form = document.createElement('form');
+    form.className = 'store-add password-actions';
+    form.noValidate = true;
+    const file = document.createElement('input');
+    file.type = 'file';
+    file.accept = '.knxproj';
+    const tags = document.createElement('input');
+    tags.type = 'text';
+    tags.placeholder = 'Tags, comma-separated';
+    const password = document.createElement('input');
+    password.type = 'password';
+    password.placeholder = 'Password, if any';
+    password.autocomplete = 'off';
+    const submit = document.createElement('button');
+    submit.type = 'submit';
+    submit.textContent = 'Share';
+    form.append(file, tags, password, submit);
+
+    form.addEventListener('submit', async (event) => {
+        event.preventDefault();
+        const chosen = file.files && file.files[0];
+        if (!chosen) {
+            setUploadError('Choose a .knxproj file to share.');
+            return;
+        }
+        submit.disabled = true;
+        try {
+            const tagList = tags.value.split(',').map((tag) => tag.trim()).filter(Boolean);
+            const stored = await client.storeProject(chosen, password.value.trim() || null, null, tagList);
+            window.location.href = pageUrl({ stored: stored.id });
+        } catch (error) {
+            if (error instanceof ApiError && error.code === 'auth_required') {
+                redirectToLogin();
+                return;
+            }
+            setUploadError(`Unable to share the project: ${error.message || error}`);
+        } finally {
+            submit.disabled = false;
+        }
+    });
+    return form;
+}
@@ -71,6 +71,16 @@ function formatBytes(value) {
     return `${size.toFixed(precision)} ${units[unit]}`;
 }
 
+const ETS_GENERATION_LABELS = { ets4: 'ETS4', ets5: 'ETS5', ets6: 'ETS6' };
+
+function formatSchema(info) {
+    if (info.schema_version == null) return '';
+    const generation = ETS_GENERATION_LABELS[info.ets_generation];
+    return generation
+        ? `project/${info.schema_version} (${generation})`
+        : `project/${info.schema_version}`;
+}
+
 function formatTimestamp(value) {
     if (!value) return '';
     if (typeof value === 'number') {
@@ -550,6 +560,8 @@ export function renderDetails(entity, container, options = {}) {
         addRow(overview, 'Status', info.completion_status || info.status);
         addRow(overview, 'Archived Version', info.archived_version || info.archivedVersion);
         addRow(overview, 'Group Address Style', info.group_address_style || info.groupAddressStyle);
+        addRow(overview, 'Schema', formatSchema(info));
+        addRow(overview, 'Created By', [info.created_by, info.tool_version].filter(Boolean).join(' '));
 
         const tagSection = createSection('Tags');
         const tags = buildTagBadges(info.tags);
@@ -2,9 +2,10 @@ import { state } from './state.js';
 import { getDom } from './dom.js';
 // import { refreshViewControls } from './controls.js'; // Deprecated
 import { applyFiltersAndRender, updateFilterOptions } from './filters.js';
-import { parseKnxprojFile } from './parser.js';
+import { describeParseProgress, inspectKnxprojFile, parseKnxprojFile } from './parser.js';
 import { updateClassicView } from './classic_view.js';
 import { ApiError, NetworkError } from './utils/api_client.js';
+import { redirectToLogin } from './auth.js';
 import { stateManager } from './state_manager.js';
 import { prepareForProjectLoad } from './project_cleanup.js';
 import { offloadDevicePayloads, offloadProjectGraphs, getProjectCacheStats } from './cache/project_payload_cache.js';
@@ -179,10 +180,23 @@ async function uploadFile(file, options = {}) {
     try {
         // Reset password input if not needed
         const password = dom.passwordInput ? dom.passwordInput.value.trim() : '';
+        if (!password) {
+            // Ask for the password now rather than after loading the project.
+            const summary = await inspectKnxprojFile(file);
+            if (summary && summary.password_required) {
+                const name = summary.project_name ? `"${summary.project_name}"` : 'This project';
+                throw new Error(`${name} is password protected. Enter its password to open it.`);
+            }
+        }
 
         // Parsing
         const data = await parseKnxprojFile(file, password || null, {
-            allowServerFallback: options && options.allowServerFallback !== false
+            allowServerFallback: options && options.allowServerFallback !== false,
+            onProgress: (event) => {
+                if (dom.loadingMessage) {
+                    dom.loadingMessage.textContent = describeParseProgress(event);
+                }
+            }
         });
         if (data && typeof data === 'object') {
             const info = data.project_info || {};
@@ -202,48 +216,7 @@ async function uploadFile(file, options = {}) {
                 info.last_modified = new Date(file.lastModified).toISOString();
             }
         }
-        const projectKey = buildProjectKey(file, data);
-        try {
-            await offloadDevicePayloads(projectKey, data);
-            await offloadProjectGraphs(projectKey, data);
-            const stats = await getProjectCacheStats(projectKey);
-            if (stats) {
-                stateManager.setState('cacheStats', stats);
-                if (typeof window !== 'undefined') {
-                    window.__topobusCacheStats = stats;
-                }
-            }
-        } catch (error) {
-            console.warn('Failed to offload device configs.', error);
-        }
-        stateManager.setStatePatch({
-            currentProject: data,
-            currentProjectKey: projectKey,
-            lastGraphKey: null,
-            lastGraphViewType: null,
-            graphLoadingActive: false,
-            groupSummaryMode: false,
-            groupAddressIndex: buildGroupAddressIndex(data),
-            deviceIndex: buildDeviceIndex(data)
-        });
-        updateFilterOptions(data);
-
-        hidePasswordPrompt();
-
-        if (dom.loading) dom.loading.classList.add('hidden');
-
-        // Update Project Stats / Title in Classic View
-        if (dom.projectTitle) {
-            dom.projectTitle.textContent = data.project_name || file.name;
-        }
-
-        // Initialize Views
-        updateClassicView();
-
-        // Render graph only if the graph view is visible
-        if (dom.graphView && dom.graphView.style.display !== 'none') {
-            applyFiltersAndRender();
-        }
+        await showProject(data, file.name);
         await persistPromise;
         return true;
 
@@ -259,14 +232,70 @@ async function uploadFile(file, options = {}) {
     }
 }
 
+/**
+ * Display parsed project data, from an upload or from the server's library.
+ * `name` labels the project when it has no name of its own.
+ */
+export async function showProject(data, name) {
+    const dom = getDom();
+    if (!dom) return;
+    const projectKey = buildProjectKey(name, data);
+    try {
+        await offloadDevicePayloads(projectKey, data);
+        await offloadProjectGraphs(projectKey, data);
+        const stats = await getProjectCacheStats(projectKey);
+        if (stats) {
+            stateManager.setState('cacheStats', stats);
+            if (typeof window !== 'undefined') {
+                window.__topobusCacheStats = stats;
+            }
+        }
+    } catch (error) {
+        console.warn('Failed to offload device configs.', error);
+    }
+    stateManager.setStatePatch({
+        currentProject: data,
+        currentProjectKey: projectKey,
+        lastGraphKey: null,
+        lastGraphViewType: null,
+        graphLoadingActive: false,
+        groupSummaryMode: false,
+        groupAddressIndex: buildGroupAddressIndex(data),
+        deviceIndex: buildDeviceIndex(data)
+    });
+    updateFilterOptions(data);
+
+    hidePasswordPrompt();
+
+    if (dom.loading) dom.loading.classList.add('hidden');
+
+    // Update Project Stats / Title in Classic View
+    if (dom.projectTitle) {
+        dom.projectTitle.textContent = data.project_name || name;
+    }
+
+    // Initialize Views
+    updateClassicView();
+
+    // Render graph only if the graph view is visible
+    if (dom.graphView && dom.graphView.style.display !== 'none') {
+        applyFiltersAndRender();
+    }
+}
+
 function handleUploadError(error) {
     const dom = getDom();
     if (!dom) return;
     if (dom.loading) dom.loading.classList.add('hidden');
     if (dom.uploadZone) dom.uploadZone.classList.remove('hidden');
 
+    if (error instanceof ApiError && error.code === 'auth_required') {
+        redirectToLogin();
+        return;
+    }
+
     const message = error && error.message ? error.message : String(error || 'Upload failed.');
-    if (isPasswordError(message)) {
+    if (isPasswordError(error, message)) {
         showPasswordPrompt(message);
         return;
     }
@@ -283,7 +312,11 @@ function handleUploadError(error) {
     setUploadError(`Upload failed: ${message}`);
 }
 
-function isPasswordError(message) {
+function isPasswordError(error, message) {
+    if (error instanceof ApiError && error.status) {
+        return error.code === 'password_required' || error.code === 'invalid_password';
+    }
+    // The WASM parser only reports messages.
     const lower = String(message || '').toLowerCase();
     return lower.includes('password') || lower.includes('encrypted');
 }
@@ -309,7 +342,7 @@ function hidePasswordPrompt() {
     }
 }
 
-function setUploadError(message) {
+export function setUploadError(message) {
     const dom = getDom();
     if (!dom || !dom.uploadZone) return;
     let errorBox = dom.uploadZone.querySelector('.upload-error');
@@ -346,9 +379,9 @@ function buildDeviceIndex(project) {
     return map;
 }
 
-function buildProjectKey(file, project) {
+function buildProjectKey(fileName, project) {
     const base = project && project.project_name ? String(project.project_name) : '';
-    const name = base || (file && file.name ? String(file.name) : '');
+    const name = base || (fileName ? String(fileName) : '');
     const seed = `${Date.now()}-${Math.random().toString(36).slice(2, 8)}`;
     return name ? `${name}::${seed}` : seed;
 }
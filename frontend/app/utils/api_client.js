@@ -1,9 +1,31 @@
 export class ApiError extends Error {
-    constructor(message, code, status) {
+    constructor(message, code, status, details = null, diagnostics = []) {
         super(message || 'Request failed');
         this.name = 'ApiError';
         this.code = code || 'api_error';
         this.status = status || 0;
+        this.details = details;
+        this.diagnostics = diagnostics;
+    }
+
+    /** Build from a failed response; the server sends `{code, message, details, diagnostics}`. */
+    static fromResponse(bodyText, status, fallbackCode, fallbackMessage) {
+        let body = null;
+        try {
+            body = JSON.parse(bodyText);
+        } catch {
+            body = null;
+        }
+        if (body && typeof body.code === 'string') {
+            return new ApiError(
+                body.message || fallbackMessage,
+                body.code,
+                status,
+                body.details || null,
+                Array.isArray(body.diagnostics) ? body.diagnostics : []
+            );
+        }
+        return new ApiError(bodyText || fallbackMessage, fallbackCode, status);
     }
 }
 
@@ -15,9 +37,15 @@ export class NetworkError extends Error {
     }
 }
 
+function pageBaseUrl() {
+    if (typeof window === 'undefined' || !window.location) return '';
+    return new URL('./', window.location.href).pathname.replace(/\/$/, '');
+}
+
 export class ApiClient {
     constructor(options = {}) {
-        this.baseUrl = options.baseUrl || '';
+        // Relative to the page, so the app also works when mounted under a path like /topobus/.
+        this.baseUrl = options.baseUrl ?? pageBaseUrl();
         this.defaultTimeout = options.timeout || 60000;
         this.defaultRetries = options.maxRetries || 3;
     }
@@ -27,15 +55,21 @@ export class ApiClient {
         const timeout = Number.isFinite(options.timeout) ? options.timeout : this.defaultTimeout;
 
         for (let attempt = 1; attempt <= maxRetries; attempt += 1) {
+            const progress = await this.#openProgressStream(options.onProgress);
             try {
+                const formData = this.#buildFormData(file, password, preferredLanguage);
+                if (progress) {
+                    formData.append('job_id', progress.jobId);
+                }
                 const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/upload`, {
                     method: 'POST',
-                    body: this.#buildFormData(file, password, preferredLanguage)
+                    body: formData
                 }, timeout);
 
                 const bodyText = await response.text();
                 if (!response.ok) {
-                    throw new ApiError(bodyText || response.statusText, 'upload_failed', response.status);
+                    const fallbackCode = response.status === 401 ? 'auth_required' : 'upload_failed';
+                    throw ApiError.fromResponse(bodyText, response.status, fallbackCode, response.statusText);
                 }
 
                 const data = JSON.parse(bodyText);
@@ -47,12 +81,175 @@ export class ApiClient {
                 }
                 const delay = Math.min(1000 * Math.pow(2, attempt), 10000);
                 await this.#sleep(delay);
+            } finally {
+                if (progress) {
+                    progress.close();
+                }
             }
         }
 
         throw new ApiError('Upload failed after retries', 'upload_failed', 0);
     }
 
+    /**
+     * Whether the server asks for credentials and who is signed in.
+     * Resolves to null when there is no TopoBus server, e.g. on static hosting or offline.
+     */
+    async getAuthStatus() {
+        try {
+            const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/auth`, {
+                cache: 'no-store'
+            }, 10000);
+            if (!response.ok) return null;
+            return await response.json();
+        } catch {
+            return null;
+        }
+    }
+
+    /**
+     * Projects of the directory the server watches (`topobus serve --watch`).
+     * Resolves to null when the server watches none, or there is no server.
+     */
+    async getLibrary() {
+        try {
+            const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/library`, {
+                cache: 'no-store'
+            }, 10000);
+            if (!response.ok) return null;
+            return await response.json();
+        } catch {
+            return null;
+        }
+    }
+
+    /** Graphs of a project of the watched directory, shaped like an upload response. */
+    async getLibraryProject(id) {
+        const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/library/${encodeURIComponent(id)}`, {
+            cache: 'no-store'
+        }, this.defaultTimeout);
+        const bodyText = await response.text();
+        if (!response.ok) {
+            throw ApiError.fromResponse(bodyText, response.status, 'library_failed', response.statusText);
+        }
+        const data = JSON.parse(bodyText);
+        this.#validateProjectData(data);
+        return data;
+    }
+
+    /**
+     * Call `onEvent(type, event)` for `changed`, `failed` and `removed` events of the watched
+     * directory. The browser reconnects by itself; the returned function stops listening.
+     */
+    watchLibrary(onEvent) {
+        if (typeof EventSource === 'undefined') {
+            return () => {};
+        }
+        const source = new EventSource(`${this.baseUrl}/api/library/events`);
+        for (const type of ['changed', 'failed', 'removed']) {
+            source.addEventListener(type, (event) => {
+                try {
+                    onEvent(type, JSON.parse(event.data));
+                } catch (error) {
+                    console.warn('Ignoring malformed library event', error);
+                }
+            });
+        }
+        return () => source.close();
+    }
+
+    /**
+     * Projects of the server's shared store matching `query` (words to search for).
+     * Resolves to null when the server has no store, or there is no server.
+     */
+    async getStore(query = '') {
+        const params = query ? `?${new URLSearchParams({ q: query })}` : '';
+        try {
+            const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/store${params}`, {
+                cache: 'no-store'
+            }, 10000);
+            if (!response.ok) return null;
+            return await response.json();
+        } catch {
+            return null;
+        }
+    }
+
+    /** Graphs of a stored project, shaped like an upload response. */
+    async getStoredProject(id) {
+        const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/store/${encodeURIComponent(id)}/graphs`, {
+            cache: 'no-store'
+        }, this.defaultTimeout);
+        const bodyText = await response.text();
+        if (!response.ok) {
+            throw ApiError.fromResponse(bodyText, response.status, 'store_failed', response.statusText);
+        }
+        const data = JSON.parse(bodyText);
+        this.#validateProjectData(data);
+        return data;
+    }
+
+    /** Parse a project on the server and keep it in the shared store; resolves to its metadata. */
+    async storeProject(file, password, preferredLanguage, tags = []) {
+        const formData = this.#buildFormData(file, password, preferredLanguage);
+        if (tags.length) {
+            formData.append('tags', tags.join(','));
+        }
+        const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/store`, {
+            method: 'POST',
+            body: formData
+        }, this.defaultTimeout);
+        const bodyText = await response.text();
+        if (!response.ok) {
+            const fallbackCode = response.status === 401 ? 'auth_required' : 'store_failed';
+            throw ApiError.fromResponse(bodyText, response.status, fallbackCode, response.statusText);
+        }
+        return JSON.parse(bodyText);
+    }
+
+    async deleteStoredProject(id) {
+        const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/store/${encodeURIComponent(id)}`, {
+            method: 'DELETE'
+        }, 10000);
+        if (!response.ok) {
+            throw ApiError.fromResponse(await response.text(), response.status, 'store_failed', response.statusText);
+        }
+    }
+
+    /** Link to the file of a stored project, as it was uploaded. */
+    storedProjectDownloadUrl(id) {
+        return `${this.baseUrl}/api/store/${encodeURIComponent(id)}/download`;
+    }
+
+    async #openProgressStream(onProgress) {
+        if (typeof onProgress !== 'function' || typeof EventSource === 'undefined') {
+            return null;
+        }
+        // The server hands out job ids, and only to the signed-in user who follows the upload.
+        let jobId;
+        try {
+            const response = await this.#fetchWithTimeout(`${this.baseUrl}/api/jobs`, {
+                method: 'POST'
+            }, 10000);
+            if (!response.ok) return null;
+            jobId = (await response.json()).id;
+        } catch {
+            return null;
+        }
+        if (typeof jobId !== 'string') return null;
+        const source = new EventSource(`${this.baseUrl}/api/jobs/${jobId}/events`);
+        source.addEventListener('progress', (event) => {
+            try {
+                onProgress(JSON.parse(event.data));
+            } catch (error) {
+                console.warn('Ignoring malformed progress event', error);
+            }
+        });
+        // The stream is optional: never let it retry forever or surface errors.
+        source.addEventListener('error', () => source.close());
+        return { jobId, close: () => source.close() };
+    }
+
     #buildFormData(file, password, preferredLanguage) {
         const formData = new FormData();
         formData.append('file', file);
@@ -31,10 +31,15 @@ export async function initWasm() {
     return wasmInitPromise;
 }
 
-export async function parseKnxprojBytes(bytes, password, preferredLanguage) {
+export async function parseKnxprojBytes(bytes, password, preferredLanguage, includeSecrets = false) {
     const ready = await initWasm();
     if (!ready || !wasmModule || typeof wasmModule.parse_knxproj !== 'function') {
         throw new Error('WASM parser not available');
     }
-    return wasmModule.parse_knxproj(bytes, password || undefined, preferredLanguage || undefined);
+    return wasmModule.parse_knxproj(
+        bytes,
+        password || undefined,
+        preferredLanguage || undefined,
+        includeSecrets
+    );
 }
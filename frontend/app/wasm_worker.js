@@ -40,19 +40,36 @@ function serializeError(error) {
 }
 
 self.addEventListener('message', async (event) => {
-    const { id, buffer, password, preferredLanguage } = event.data || {};
+    const { id, op, buffer, password, preferredLanguage, includeSecrets } = event.data || {};
     if (!id) return;
     try {
         await initWasm();
+        if (op === 'inspect') {
+            if (!wasmModule || typeof wasmModule.inspect_knxproj !== 'function') {
+                throw new Error('WASM inspection not available');
+            }
+            const bytes = buffer instanceof Uint8Array ? buffer : new Uint8Array(buffer || []);
+            self.postMessage({ id, ok: true, result: wasmModule.inspect_knxproj(bytes) });
+            return;
+        }
         if (!wasmModule || typeof wasmModule.parse_knxproj !== 'function') {
             throw new Error('WASM parser not available');
         }
         const bytes = buffer instanceof Uint8Array ? buffer : new Uint8Array(buffer || []);
-        const result = wasmModule.parse_knxproj(
-            bytes,
-            password || undefined,
-            preferredLanguage || undefined
-        );
+        const result = typeof wasmModule.parse_knxproj_with_progress === 'function'
+            ? wasmModule.parse_knxproj_with_progress(
+                bytes,
+                password || undefined,
+                preferredLanguage || undefined,
+                Boolean(includeSecrets),
+                (progress) => self.postMessage({ id, progress })
+            )
+            : wasmModule.parse_knxproj(
+                bytes,
+                password || undefined,
+                preferredLanguage || undefined,
+                Boolean(includeSecrets)
+            );
         self.postMessage({ id, ok: true, result });
     } catch (error) {
         self.postMessage({ id, ok: false, error: serializeError(error) });
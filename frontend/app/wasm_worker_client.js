@@ -1,11 +1,31 @@
 let workerCounter = 0;
 
+/** Parse a project; secrets stay masked unless `options.includeSecrets` is set. */
 export function parseKnxprojBytesWithWorker(buffer, password, preferredLanguage, options = {}) {
+    return runWorker(
+        'parse',
+        {
+            buffer,
+            password: password || null,
+            preferredLanguage: preferredLanguage || null,
+            includeSecrets: Boolean(options.includeSecrets)
+        },
+        buffer ? [buffer] : [],
+        options
+    );
+}
+
+/** Summarise a project without parsing it. The buffer is copied, not transferred. */
+export function inspectKnxprojBytesWithWorker(buffer, options = {}) {
+    return runWorker('inspect', { buffer }, [], { timeoutMs: 15000, ...options });
+}
+
+function runWorker(op, message, transfer, options) {
     if (typeof Worker === 'undefined') {
         return Promise.reject(new Error('Web Worker not supported'));
     }
     const timeoutMs = Number(options.timeoutMs || 0) || 120000;
-    const id = `parse-${Date.now()}-${workerCounter++}`;
+    const id = `${op}-${Date.now()}-${workerCounter++}`;
     const worker = new Worker(new URL('./wasm_worker.js', import.meta.url), { type: 'module' });
 
     return new Promise((resolve, reject) => {
@@ -29,6 +49,12 @@ export function parseKnxprojBytesWithWorker(buffer, password, preferredLanguage,
         worker.addEventListener('message', (event) => {
             const data = event.data || {};
             if (data.id !== id) return;
+            if (data.progress) {
+                if (typeof options.onProgress === 'function') {
+                    options.onProgress(data.progress);
+                }
+                return;
+            }
             cleanup();
             if (data.ok) {
                 resolve(data.result);
@@ -46,9 +72,6 @@ export function parseKnxprojBytesWithWorker(buffer, password, preferredLanguage,
             reject(event.error || new Error('WASM worker failed'));
         });
 
-        worker.postMessage(
-            { id, buffer, password: password || null, preferredLanguage: preferredLanguage || null },
-            buffer ? [buffer] : []
-        );
+        worker.postMessage({ id, op, ...message }, transfer);
     });
 }
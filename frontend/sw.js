@@ -35,12 +35,15 @@ const CORE_ASSETS = [
   './wasm/topobus_wasm.js',
   './wasm/topobus_wasm_bg.wasm',
   './app/index.js',
+  './app/auth.js',
   './app/controls.js',
   './app/details.js',
   './app/dom.js',
   './app/dpt.js',
   './app/filters.js',
   './app/interactions.js',
+  './app/library.js',
+  './app/store.js',
   './app/minimap.js',
   './app/parser.js',
   './app/selection.js',
@@ -126,6 +129,11 @@ self.addEventListener('fetch', (event) => {
   const url = new URL(request.url);
   if (url.origin !== self.location.origin) return;
 
+  // The login page talks to the server; it is never served from the app shell cache.
+  if (url.pathname.endsWith('/login.html')) return;
+  // API responses are live data, and event streams never end; leave them to the network.
+  if (url.pathname.includes('/api/')) return;
+
   if (request.mode === 'navigate') {
     event.respondWith(handleNavigation(request));
     return;